@@ -31,13 +31,15 @@ console.log("ts_verify_share_response (expected true): ", verification_expected_
 let verification = ts_verify_share_response(secret_id, 1n, shares.value.get(2), response);
 console.log("ts_verify_share_response (expected false): ", verification);
 
+// the sharer asking for its shares back must have paired as a recovery sharer
+const role_sharer_recovery = 1;
 let share_request = ts_generate_share_request(1n, secret_id, version);
 console.log("ts_generate_share_request: ", share_request);
-let share_response_1 = ts_generate_share_response(secret_id, 1n, shares.value.get(1), share_request);
+let share_response_1 = ts_generate_share_response(secret_id, 1n, shares.value.get(1), share_request, role_sharer_recovery);
 console.log("ts_generate_share_response: ", share_response_1);
-let share_response_2 = ts_generate_share_response(secret_id, 2n, shares.value.get(2), share_request);
+let share_response_2 = ts_generate_share_response(secret_id, 2n, shares.value.get(2), share_request, role_sharer_recovery);
 console.log("ts_generate_share_response: ", share_response_2);
-let share_response_3 = ts_generate_share_response(secret_id, 3n, shares.value.get(3), share_request);
+let share_response_3 = ts_generate_share_response(secret_id, 3n, shares.value.get(3), share_request, role_sharer_recovery);
 console.log("ts_generate_share_response: ", share_response_3);
 
 const responses = new Map<number, number[]>();
@@ -45,7 +47,7 @@ responses.set(1, Array.from(share_response_1));
 responses.set(2, Array.from(share_response_2));
 responses.set(3, Array.from(share_response_3));
 try {
-    let recovered = ts_recover_from_share_responses({"value": responses}, secret_id, version);
+    let recovered = ts_recover_from_share_responses({"value": responses}, secret_id, version, threshold);
     console.log("ts_recover_from_share_responses: ", recovered);
 } catch (e) {
     console.error("Error recovering from share responses: ", e);
@@ -71,6 +73,7 @@ console.log("ts_produce_pairing_request_message: ", produce_pairing_request_mess
 // run by Alice, who receives Bob's pairing request message
 let produce_pairing_response_message_result = ts_produce_pairing_response_message(
     role_sharer,
+    create_contact_message_result.contact_message,
     produce_pairing_request_message_result.pair_request_message,
     create_contact_message_result.secret_key_material
 );
@@ -79,6 +82,7 @@ console.log("ts_produce_pairing_response_message: ", produce_pairing_response_me
 // run by Bob, who receives Alice's pairing response message
 let process_pairing_response_message_result = ts_process_pairing_response_message(
     create_contact_message_result.contact_message,
+    produce_pairing_request_message_result.pair_request_message,
     produce_pairing_response_message_result.pair_response_message,
     produce_pairing_request_message_result.secret_key_material
 );
@@ -3,14 +3,49 @@
 //! This module provides cryptographic primitives for encrypting and decrypting messages
 //! given a (shared) symmetric key using AES-256-GCM authenticated encryption.
 
-use aes_gcm::{aead::Aead, Aes256Gcm, Nonce, Key};
+use aes_gcm::{aead::{Aead, AeadInPlace, Payload}, Aes256Gcm, Nonce, Key};
 use aes::cipher::KeyInit;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+use zeroize::Zeroize;
+use crate::rng::SecureRng;
+
+/// Domain separator for the HKDF step used by [`ratchet`].
+const RATCHET_INFO: &[u8] = b"derec-channel-ratchet";
+
+/// How many chain steps a receiver will skip ahead to catch up with the sender.
+pub const MAX_RATCHET_SKIP: u64 = 128;
+
+/// Length of the nonce prefixed to every ciphertext.
+const NONCE_SIZE: usize = 12;
+
+/// Length of the AES-GCM authentication tag appended to every ciphertext.
+const TAG_SIZE: usize = 16;
 
 /// Custom error type for Derec channel encryption and decryption operations.
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum DerecChannelError {
+    #[error("encryption failed")]
     EncryptionError(aead::Error),
+    /// The ciphertext did not authenticate: the key is wrong or the ciphertext (or its
+    /// associated data) was tampered with.
+    #[error("decryption failed: wrong key or tampered ciphertext")]
     DecryptionError(aead::Error),
+    /// The ratcheted message's chain index is behind the receiver (its key is
+    /// already gone) or more than [`MAX_RATCHET_SKIP`] steps ahead of it.
+    #[error("ratchet index {index} is outside the window at {expected}")]
+    RatchetIndexOutOfWindow { index: u64, expected: u64 },
+    /// The ciphertext is too short to hold its framing (index, nonce and tag).
+    #[error("ciphertext is malformed")]
+    MalformedCiphertext,
+    /// The ciphertext names a cipher this library does not implement.
+    #[error("unsupported cipher")]
+    UnsupportedCipher,
+    /// A channel key could not be derived from its input keying material.
+    #[error("key derivation failed")]
+    KeyDerivationFailed,
 }
 
 /// Encrypts a message using AES-256-GCM authenticated encryption.
@@ -38,10 +73,10 @@ pub enum DerecChannelError {
 /// ```
 pub fn encrypt_message(msg: &[u8], key: &[u8; 32], nonce: &[u8; 32]) -> Result<Vec<u8>, DerecChannelError> {
     let key: &Key<Aes256Gcm> = key.into();
-    let cipher = Aes256Gcm::new(&key);
+    let cipher = Aes256Gcm::new(key);
 
     let e = cipher
-        .encrypt(&Nonce::from_slice(&nonce[0..12]), msg)
+        .encrypt(Nonce::from_slice(&nonce[0..12]), msg)
         .map_err(DerecChannelError::EncryptionError)?;
 
     let mut ctxt = Vec::new();
@@ -50,6 +85,79 @@ pub fn encrypt_message(msg: &[u8], key: &[u8; 32], nonce: &[u8; 32]) -> Result<V
     Ok(ctxt)
 }
 
+/// Encrypts a message like [`encrypt_message`], under a nonce drawn from `rng`.
+///
+/// # Arguments
+///
+/// * `msg` - The plaintext message to encrypt as a byte slice.
+/// * `key` - A 32-byte array representing the AES-256 encryption key.
+/// * `rng` - The generator to draw the nonce from, e.g. `rand::rngs::OsRng`.
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::{encrypt_message_with_rng, decrypt_message};
+/// let ciphertext = encrypt_message_with_rng(b"hello world", &[0u8; 32], &mut rand::rngs::OsRng).unwrap();
+/// assert_eq!(decrypt_message(&ciphertext, &[0u8; 32]).unwrap(), b"hello world");
+/// ```
+pub fn encrypt_message_with_rng(
+    msg: &[u8],
+    key: &[u8; 32],
+    rng: &mut impl SecureRng
+) -> Result<Vec<u8>, DerecChannelError> {
+    let mut nonce = [0u8; 32];
+    rng.fill_bytes(&mut nonce);
+    encrypt_message(msg, key, &nonce)
+}
+
+/// Encrypts a message using AES-256-GCM into a caller-provided buffer.
+///
+/// This produces exactly the same bytes as [`encrypt_message`], but reserves the output
+/// (`12 + msg.len() + 16` bytes) once and encrypts in place, avoiding the intermediate
+/// ciphertext allocation and copy. Reusing `out` across calls avoids allocating at all
+/// once it has grown large enough, which matters for high-throughput helpers.
+///
+/// # Arguments
+///
+/// * `msg` - The plaintext message to encrypt as a byte slice.
+/// * `key` - A 32-byte array representing the AES-256 encryption key.
+/// * `nonce` - A 32-byte array used as the nonce; only the first 12 bytes are used for AES-GCM.
+/// * `out` - The output buffer; any previous contents are discarded.
+///
+/// # Returns
+///
+/// Returns `Ok(())` with `out` holding the 12-byte nonce prefix followed by the encrypted
+/// message and authentication tag, or an error if encryption fails.
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::{encrypt_message, encrypt_message_into};
+/// let mut out = Vec::new();
+/// encrypt_message_into(b"hello world", &[0u8; 32], &[0u8; 32], &mut out).unwrap();
+/// assert_eq!(out, encrypt_message(b"hello world", &[0u8; 32], &[0u8; 32]).unwrap());
+/// ```
+pub fn encrypt_message_into(
+    msg: &[u8],
+    key: &[u8; 32],
+    nonce: &[u8; 32],
+    out: &mut Vec<u8>
+) -> Result<(), DerecChannelError> {
+    let key: &Key<Aes256Gcm> = key.into();
+    let cipher = Aes256Gcm::new(key);
+
+    out.clear();
+    out.reserve(12 + msg.len() + 16);
+    out.extend_from_slice(&nonce[0..12]);
+    out.extend_from_slice(msg);
+
+    let tag = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(&nonce[0..12]), b"", &mut out[12..])
+        .map_err(DerecChannelError::EncryptionError)?;
+    out.extend_from_slice(&tag);
+    Ok(())
+}
+
 /// Decrypts a message encrypted with AES-256-GCM authenticated encryption.
 ///
 /// # Arguments
@@ -61,7 +169,7 @@ pub fn encrypt_message(msg: &[u8], key: &[u8; 32], nonce: &[u8; 32]) -> Result<V
 /// # Returns
 ///
 /// Returns a `Result` containing the decrypted plaintext as a `Vec<u8>` on success.
-/// Returns an error if decryption fails or authentication does not pass.
+/// Returns an error if `ctxt` is too short to hold a nonce, or if decryption fails or authentication does not pass.
 ///
 /// # Example
 ///
@@ -75,14 +183,221 @@ pub fn encrypt_message(msg: &[u8], key: &[u8; 32], nonce: &[u8; 32]) -> Result<V
 /// assert_eq!(plaintext, msg);
 /// ```
 pub fn decrypt_message(ctxt: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, DerecChannelError> {
+    if ctxt.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(DerecChannelError::MalformedCiphertext);
+    }
+
+    let key: &Key<Aes256Gcm> = key.into();
+    let cipher = Aes256Gcm::new(key);
+
+    cipher
+        .decrypt(Nonce::from_slice(&ctxt[0..12]), &ctxt[12..])
+        .map_err(DerecChannelError::DecryptionError)
+}
+
+/// Encrypts a message like [`encrypt_message`], additionally authenticating `aad`.
+///
+/// The associated data (e.g. the channel id) is not included in the ciphertext; the
+/// receiver must supply the same bytes to [`decrypt_message_with_aad`].
+///
+/// # Arguments
+///
+/// * `msg` - The plaintext message to encrypt as a byte slice.
+/// * `key` - A 32-byte array representing the AES-256 encryption key.
+/// * `nonce` - A 32-byte array used as the nonce; only the first 12 bytes are used for AES-GCM.
+/// * `aad` - Associated data bound to the ciphertext but not encrypted or transmitted.
+///
+/// # Returns
+///
+/// The 12-byte nonce prefix followed by the encrypted message and authentication tag,
+/// or an error if encryption fails.
+pub fn encrypt_message_with_aad(
+    msg: &[u8],
+    key: &[u8; 32],
+    nonce: &[u8; 32],
+    aad: &[u8]
+) -> Result<Vec<u8>, DerecChannelError> {
+    let key: &Key<Aes256Gcm> = key.into();
+    let cipher = Aes256Gcm::new(key);
+
+    let e = cipher
+        .encrypt(Nonce::from_slice(&nonce[0..12]), Payload { msg, aad })
+        .map_err(DerecChannelError::EncryptionError)?;
+
+    let mut ctxt = Vec::new();
+    ctxt.extend_from_slice(&nonce[0..12]);
+    ctxt.extend_from_slice(&e);
+    Ok(ctxt)
+}
+
+/// Decrypts a message produced by [`encrypt_message_with_aad`].
+///
+/// # Arguments
+///
+/// * `ctxt` - The ciphertext as a byte slice. The first 12 bytes are expected to be the nonce,
+///   followed by the encrypted message and authentication tag.
+/// * `key` - A 32-byte array representing the AES-256 decryption key.
+/// * `aad` - The associated data the message was encrypted with.
+///
+/// # Returns
+///
+/// The decrypted plaintext, or an error if `ctxt` is too short to hold a nonce, or if
+/// authentication fails (including when `aad` differs from the sender's).
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::{encrypt_message_with_aad, decrypt_message_with_aad};
+/// let key = [0u8; 32];
+/// let ctxt = encrypt_message_with_aad(b"hello", &key, &[0u8; 32], &42u64.to_be_bytes()).unwrap();
+/// assert_eq!(decrypt_message_with_aad(&ctxt, &key, &42u64.to_be_bytes()).unwrap(), b"hello");
+/// assert!(decrypt_message_with_aad(&ctxt, &key, &43u64.to_be_bytes()).is_err());
+/// ```
+pub fn decrypt_message_with_aad(ctxt: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>, DerecChannelError> {
+    if ctxt.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(DerecChannelError::MalformedCiphertext);
+    }
+
     let key: &Key<Aes256Gcm> = key.into();
-    let cipher = Aes256Gcm::new(&key);
+    let cipher = Aes256Gcm::new(key);
 
     cipher
-        .decrypt(&Nonce::from_slice(&ctxt[0..12]), &ctxt[12..])
+        .decrypt(Nonce::from_slice(&ctxt[0..12]), Payload { msg: &ctxt[12..], aad })
         .map_err(DerecChannelError::DecryptionError)
 }
 
+/// Encrypts a message like [`encrypt_message`], deriving the nonce from the key and the message.
+///
+/// The nonce is the first 12 bytes of HMAC-SHA256(`key`, `msg`) (a synthetic IV), so the caller
+/// keeps no nonce state and distinct messages never share a nonce under the same key. The output
+/// is decrypted with [`decrypt_message`].
+///
+/// Encryption is deterministic: the same message under the same key always yields the same
+/// ciphertext, which reveals to an observer when two messages are equal. Only use it where that
+/// is acceptable (e.g. to deduplicate stored blobs).
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::{encrypt_deterministic, decrypt_message};
+/// let key = [0u8; 32];
+/// let ctxt = encrypt_deterministic(b"hello", &key).unwrap();
+/// assert_eq!(ctxt, encrypt_deterministic(b"hello", &key).unwrap());
+/// assert_eq!(decrypt_message(&ctxt, &key).unwrap(), b"hello");
+/// ```
+pub fn encrypt_deterministic(msg: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, DerecChannelError> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(msg);
+
+    let mut nonce = [0u8; 32];
+    nonce[0..12].copy_from_slice(&mac.finalize().into_bytes()[0..12]);
+    encrypt_message(msg, key, &nonce)
+}
+
+/// Steps a channel key forward one position in a symmetric ratchet.
+///
+/// The next key is derived with HKDF-SHA256 from the current one, so learning a later
+/// key reveals nothing about the keys before it.
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::ratchet;
+/// let key = [1u8; 32];
+/// assert_ne!(ratchet(&key), key);
+/// ```
+pub fn ratchet(key: &[u8; 32]) -> [u8; 32] {
+    let mut next = [0u8; 32];
+    Hkdf::<Sha256>::new(None, key)
+        .expand(RATCHET_INFO, &mut next)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    next
+}
+
+/// One direction of a ratcheted channel: the key for the next message and its index.
+///
+/// Each side of a channel keeps two chains, one for sending and one for receiving,
+/// both seeded from the shared channel key. The current key is zeroized whenever the
+/// chain steps forward and when the chain is dropped.
+pub struct RatchetChain {
+    key: [u8; 32],
+    index: u64,
+}
+
+impl RatchetChain {
+    /// Starts a chain at index 0 from the given key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        RatchetChain { key: *key, index: 0 }
+    }
+
+    /// The index of the next message on this chain.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    fn step(&mut self) {
+        let mut next = ratchet(&self.key);
+        self.key.zeroize();
+        self.key = next;
+        next.zeroize();
+        self.index += 1;
+    }
+}
+
+impl Drop for RatchetChain {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// Encrypts a message under the current key of `chain` and then steps the chain forward.
+///
+/// The output is the 8-byte big-endian chain index followed by the [`encrypt_message`]
+/// ciphertext. Since every key encrypts exactly one message, a fixed nonce is safe here.
+///
+/// # Example
+///
+/// ```
+/// use derec_cryptography::channel::{RatchetChain, encrypt_ratcheted, decrypt_ratcheted};
+/// let mut send = RatchetChain::new(&[3u8; 32]);
+/// let mut recv = RatchetChain::new(&[3u8; 32]);
+/// let ctxt = encrypt_ratcheted(&mut send, b"hello").unwrap();
+/// assert_eq!(decrypt_ratcheted(&mut recv, &ctxt).unwrap(), b"hello");
+/// ```
+pub fn encrypt_ratcheted(chain: &mut RatchetChain, msg: &[u8]) -> Result<Vec<u8>, DerecChannelError> {
+    let mut ctxt = chain.index.to_be_bytes().to_vec();
+    ctxt.extend_from_slice(&encrypt_message(msg, &chain.key, &[0u8; 32])?);
+    chain.step();
+    Ok(ctxt)
+}
+
+/// Decrypts a message produced by [`encrypt_ratcheted`], catching `chain` up to it.
+///
+/// If the embedded index is ahead of the chain, the chain steps forward (by at most
+/// [`MAX_RATCHET_SKIP`]) to reach it; the keys of any skipped messages are discarded.
+/// Messages whose index is behind the chain can no longer be decrypted. The chain is
+/// only advanced if decryption succeeds.
+pub fn decrypt_ratcheted(chain: &mut RatchetChain, ctxt: &[u8]) -> Result<Vec<u8>, DerecChannelError> {
+    if ctxt.len() < 8 {
+        return Err(DerecChannelError::MalformedCiphertext);
+    }
+    let index = u64::from_be_bytes(ctxt[0..8].try_into().unwrap());
+    if index < chain.index || index - chain.index > MAX_RATCHET_SKIP {
+        return Err(DerecChannelError::RatchetIndexOutOfWindow { index, expected: chain.index });
+    }
+
+    let mut candidate = RatchetChain::new(&chain.key);
+    candidate.index = chain.index;
+    while candidate.index < index {
+        candidate.step();
+    }
+
+    let msg = decrypt_message(&ctxt[8..], &candidate.key)?;
+    candidate.step();
+    std::mem::swap(chain, &mut candidate);
+    Ok(msg)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +417,138 @@ mod tests {
 
         assert_eq!(received, msg);
     }
+
+    #[test]
+    fn test_encrypt_message_into_matches_allocating_version() {
+        let key = [7u8; 32];
+        let nonce = [9u8; 32];
+        let mut out = vec![0xAA; 3]; // stale contents must be discarded
+
+        for msg in [&b""[..], &b"hello derec"[..], &[0x5Au8; 1000][..]] {
+            encrypt_message_into(msg, &key, &nonce, &mut out).unwrap();
+            assert_eq!(out, encrypt_message(msg, &key, &nonce).unwrap());
+            assert_eq!(decrypt_message(&out, &key).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_with_aad_binds_channel_id() {
+        let key = [3u8; 32];
+        let nonce = [5u8; 32];
+        let channel_id: u64 = 0x1234_5678_9abc_def0;
+
+        let ctxt = encrypt_message_with_aad(b"hello derec", &key, &nonce, &channel_id.to_be_bytes()).unwrap();
+        assert_eq!(decrypt_message_with_aad(&ctxt, &key, &channel_id.to_be_bytes()).unwrap(), b"hello derec");
+
+        // the same ciphertext presented on another channel fails authentication
+        assert!(matches!(
+            decrypt_message_with_aad(&ctxt, &key, &(channel_id + 1).to_be_bytes()),
+            Err(DerecChannelError::DecryptionError(_))
+        ));
+        assert!(decrypt_message(&ctxt, &key).is_err());
+    }
+
+    #[test]
+    fn test_ratcheted_messages_in_order() {
+        let key = [0x42u8; 32];
+        let mut alice = RatchetChain::new(&key);
+        let mut bob = RatchetChain::new(&key);
+
+        let msgs: [&[u8]; 3] = [b"first", b"second", b"third"];
+        let ctxts: Vec<Vec<u8>> = msgs
+            .iter()
+            .map(|m| encrypt_ratcheted(&mut alice, m).unwrap())
+            .collect();
+        assert_eq!(alice.index(), 3);
+
+        for (ctxt, msg) in ctxts.iter().zip(msgs) {
+            assert_eq!(decrypt_ratcheted(&mut bob, ctxt).unwrap(), msg);
+        }
+        assert_eq!(bob.index(), 3);
+
+        // the first message's key has been ratcheted away
+        assert!(matches!(
+            decrypt_ratcheted(&mut bob, &ctxts[0]),
+            Err(DerecChannelError::RatchetIndexOutOfWindow { index: 0, expected: 3 })
+        ));
+
+        // and a message too far ahead is refused without moving the chain
+        let mut far = ctxts[2].clone();
+        far[0..8].copy_from_slice(&(3 + MAX_RATCHET_SKIP + 1).to_be_bytes());
+        assert!(matches!(
+            decrypt_ratcheted(&mut bob, &far),
+            Err(DerecChannelError::RatchetIndexOutOfWindow { .. })
+        ));
+        assert_eq!(bob.index(), 3);
+    }
+
+    #[test]
+    fn test_ratcheted_receiver_catches_up() {
+        let key = [0x24u8; 32];
+        let mut alice = RatchetChain::new(&key);
+        let mut bob = RatchetChain::new(&key);
+
+        let _lost = encrypt_ratcheted(&mut alice, b"dropped").unwrap();
+        let ctxt = encrypt_ratcheted(&mut alice, b"delivered").unwrap();
+
+        // a forged message at the right index must not advance the receiver
+        let mut forged = ctxt.clone();
+        *forged.last_mut().unwrap() ^= 1;
+        assert!(decrypt_ratcheted(&mut bob, &forged).is_err());
+        assert_eq!(bob.index(), 0);
+
+        assert_eq!(decrypt_ratcheted(&mut bob, &ctxt).unwrap(), b"delivered");
+        assert_eq!(bob.index(), 2);
+    }
+
+    #[test]
+    fn test_encrypt_deterministic_derives_nonce_from_message() {
+        let key = [6u8; 32];
+
+        let first = encrypt_deterministic(b"hello derec", &key).unwrap();
+        assert_eq!(first, encrypt_deterministic(b"hello derec", &key).unwrap());
+        assert_eq!(decrypt_message(&first, &key).unwrap(), b"hello derec");
+
+        // another message, or the same message under another key, gets another nonce
+        let other = encrypt_deterministic(b"hello derek", &key).unwrap();
+        assert_ne!(first[0..12], other[0..12]);
+        let rekeyed = encrypt_deterministic(b"hello derec", &[7u8; 32]).unwrap();
+        assert_ne!(first[0..12], rekeyed[0..12]);
+    }
+
+    #[test]
+    fn test_encrypt_message_with_seeded_rng_is_reproducible() {
+        use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
+
+        let key = [3u8; 32];
+        let first = encrypt_message_with_rng(b"hello derec", &key, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        let again = encrypt_message_with_rng(b"hello derec", &key, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert_eq!(first, again);
+        assert_ne!(first, encrypt_message_with_rng(b"hello derec", &key, &mut ChaCha8Rng::seed_from_u64(2)).unwrap());
+        assert_eq!(decrypt_message(&first, &key).unwrap(), b"hello derec");
+    }
+
+    #[test]
+    fn test_short_ciphertext_is_malformed_and_tampered_tag_fails_decryption() {
+        let key = [6u8; 32];
+        let ctxt = encrypt_message(b"hello derec", &key, &[1u8; 32]).unwrap();
+
+        // too short to hold a nonce and a tag, so it is not even tried
+        for len in [0, NONCE_SIZE, NONCE_SIZE + TAG_SIZE - 1] {
+            assert!(matches!(decrypt_message(&ctxt[..len], &key), Err(DerecChannelError::MalformedCiphertext)));
+            assert!(matches!(
+                decrypt_message_with_aad(&ctxt[..len], &key, b"aad"),
+                Err(DerecChannelError::MalformedCiphertext)
+            ));
+        }
+        let mut chain = RatchetChain::new(&key);
+        assert!(matches!(decrypt_ratcheted(&mut chain, &[0u8; 7]), Err(DerecChannelError::MalformedCiphertext)));
+
+        // a well-framed ciphertext with a flipped tag bit fails authentication
+        let mut tampered = ctxt.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        let err = decrypt_message(&tampered, &key).unwrap_err();
+        assert!(matches!(err, DerecChannelError::DecryptionError(_)));
+        assert_eq!(err.to_string(), "decryption failed: wrong key or tampered ciphertext");
+    }
 }
@@ -2,4 +2,7 @@
 
 pub mod vss;
 pub mod channel;
-pub mod pairing;
\ No newline at end of file
+pub mod pairing;
+pub mod suite;
+pub mod seed;
+pub mod rng;
@@ -10,6 +10,15 @@
 //! - `pairing_mlkem`: ML-KEM (Kyber) encapsulation/decapsulation primitives.
 //! - `pairing_ecies`: ECIES (Elliptic Curve Integrated Encryption Scheme) primitives.
 //!
+//! - `pairing_x25519`: X25519 Diffie-Hellman primitives, an alternative to ECIES.
+//!
+//! # Pluggable Primitives
+//! The post-quantum half of the protocol is abstracted by the `Kem` trait, and the classical half by
+//! the `KeyExchange` trait. The functions below use ML-KEM-768 (`pairing_mlkem::MlKem`) and secp256k1
+//! (`pairing_ecies::Secp256k1Ecies`); their `_with` variants accept any `K: Kem` and `X: KeyExchange`,
+//! e.g. to benchmark a different KEM or to use X25519 (`pairing_x25519::X25519`). The `mlkem_*` and
+//! `ecies_*` fields of the material structs then hold the chosen primitives' keys and ciphertexts.
+//!
 //! # Error Handling
 //! Defines `DerecPairingError` for error reporting throughout the pairing process.
 //!
@@ -23,27 +32,64 @@
 //! 1. **Contact Message Generation**: The contactor generates a contact message and secret material.
 //! 2. **Pairing Request Message**: The requestor uses the contact message to generate a request message and secret material.
 //! 3. **Shared Key Derivation**: Both parties independently derive the shared key by xor-ing secrets from ML-KEM and ECIES.
+//!    When a deployment context is supplied, the xor-ed secret is further passed through HKDF-SHA256 with the context
+//!    as `info`, so that distinct deployments derive independent keys from identical inputs.
 //!
 //! # Functions
 //! - `contact_message`: Generates a contact message and secret key material for the contactor.
+//! - `reconstruct_contact_material`: Re-derives the contact message material from the contactor's secret key material.
 //! - `pairing_request_message`: Generates a pairing request message and secret key material for the requestor.
 //! - `finish_pairing_requestor`: Used by the requestor to derive the shared key.
-//! - `finish_pairing_contactor`: Used by the contactor to derive the shared key.
+//! - `finish_pairing_contactor`: Used by the contactor to derive the shared key; `finish_pairing_contactor_with_limiter`
+//!   also caps the attempts per decapsulation key with a `DecapsulationLimiter`.
+//! - `rekey_ephemeral` / `rekey_pairing`: Re-key an established pairing from fresh ephemeral key exchange keys.
 //!
 
 use rand_chacha::rand_core::SeedableRng;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand_core::CryptoRngCore;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate};
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::seed::{require_nonzero_seed, WeakSeedError};
+use thiserror::Error;
 
 pub mod pairing_mlkem;
 pub mod pairing_ecies;
+pub mod pairing_x25519;
 
 /// Custom error type for Derec pairing operations.
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum DerecPairingError {
+    #[error("serialization error: {0}")]
     SerializationError(ark_serialize::SerializationError),
+    #[error("ML-KEM encapsulation failed")]
     MLKemEncapsulationError,
+    #[error("ML-KEM decapsulation failed")]
     MLKemDecapsulationError,
+    #[error("pairing state is missing or does not fit this step")]
     PairingStateError,
+    #[error("invalid public key")]
+    InvalidPublicKey,
+    /// The KEM and key exchange secrets are not both `PairingSharedKey`-sized.
+    #[error("shared secrets have the wrong length: ML-KEM {mlkem} bytes, key exchange {ecies} bytes")]
+    LengthMismatch { mlkem: usize, ecies: usize },
+    /// A freshly generated key exchange public key does not match its secret key.
+    #[error("generated public key does not match its secret key")]
+    KeyGenSelfTestFailed,
+    /// The caller's seed is obviously not random; see `seed::is_weak_seed`.
+    #[error("weak seed; seeds must be drawn from a CSPRNG")]
+    WeakSeed,
+    /// The decapsulation key has been used for as many attempts as its `DecapsulationLimiter` allows.
+    #[error("too many decapsulation attempts with this key")]
+    RateLimited,
+}
+
+impl From<WeakSeedError> for DerecPairingError {
+    fn from(_: WeakSeedError) -> Self {
+        DerecPairingError::WeakSeed
+    }
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -52,11 +98,109 @@ pub struct PairingContactMessageMaterial {
     pub ecies_public_key: Vec<u8>,
 }
 
-#[derive(CanonicalSerialize, CanonicalDeserialize)]
+/// Secret material held by each party during the protocol.
+///
+/// The uncompressed serialization (`serialize_uncompressed`) stores every field but `mlkem_seed` in full,
+/// including the 2400-byte ML-KEM decapsulation key. The compressed serialization (`serialize_compressed`)
+/// stores the 64-byte `mlkem_seed` instead of the decapsulation key whenever the seed is known, and
+/// `deserialize_compressed` regenerates the identical key from it. The key exchange secret is a bare
+/// scalar, which is already as small as it gets, so both forms store it as is.
 pub struct PairingSecretKeyMaterial {
     pub mlkem_decapsulation_key: Option<Vec<u8>>,
     pub mlkem_shared_secret: Option<[u8; 32]>,
     pub ecies_secret_key: Vec<u8>,
+    /// The ML-KEM-768 seed (`d || z`) `mlkem_decapsulation_key` was generated from, when known.
+    /// Only kept in memory and in the compressed serialization.
+    pub mlkem_seed: Option<Vec<u8>>,
+}
+
+// how the compressed serialization stores the decapsulation key
+const COMPRESSED_NO_DECAPSULATION_KEY: u8 = 0;
+const COMPRESSED_DECAPSULATION_KEY: u8 = 1;
+const COMPRESSED_MLKEM_SEED: u8 = 2;
+
+impl PairingSecretKeyMaterial {
+    // the seed stored in place of the decapsulation key, if the compressed form can use it
+    fn compressible_seed(&self) -> Option<&[u8; pairing_mlkem::SEED_SIZE]> {
+        self.mlkem_decapsulation_key.as_ref()?;
+        self.mlkem_seed.as_deref()?.try_into().ok()
+    }
+}
+
+impl CanonicalSerialize for PairingSecretKeyMaterial {
+    fn serialize_with_mode<W: std::io::Write>(
+        &self,
+        mut writer: W,
+        compress: Compress
+    ) -> Result<(), SerializationError> {
+        match compress {
+            Compress::No => self.mlkem_decapsulation_key.serialize_with_mode(&mut writer, compress)?,
+            Compress::Yes => match (&self.mlkem_decapsulation_key, self.compressible_seed()) {
+                (None, _) => COMPRESSED_NO_DECAPSULATION_KEY.serialize_with_mode(&mut writer, compress)?,
+                (Some(_), Some(seed)) => {
+                    COMPRESSED_MLKEM_SEED.serialize_with_mode(&mut writer, compress)?;
+                    seed.serialize_with_mode(&mut writer, compress)?;
+                }
+                (Some(dk), None) => {
+                    COMPRESSED_DECAPSULATION_KEY.serialize_with_mode(&mut writer, compress)?;
+                    dk.serialize_with_mode(&mut writer, compress)?;
+                }
+            },
+        }
+        self.mlkem_shared_secret.serialize_with_mode(&mut writer, compress)?;
+        self.ecies_secret_key.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        let decapsulation_key_size = match compress {
+            Compress::No => self.mlkem_decapsulation_key.serialized_size(compress),
+            Compress::Yes => 1 + match (&self.mlkem_decapsulation_key, self.compressible_seed()) {
+                (None, _) => 0,
+                (Some(_), Some(seed)) => seed.serialized_size(compress),
+                (Some(dk), None) => dk.serialized_size(compress),
+            },
+        };
+        decapsulation_key_size
+            + self.mlkem_shared_secret.serialized_size(compress)
+            + self.ecies_secret_key.serialized_size(compress)
+    }
+}
+
+impl Valid for PairingSecretKeyMaterial {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for PairingSecretKeyMaterial {
+    fn deserialize_with_mode<R: std::io::Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate
+    ) -> Result<Self, SerializationError> {
+        let (mlkem_decapsulation_key, mlkem_seed) = match compress {
+            Compress::No => (Option::<Vec<u8>>::deserialize_with_mode(&mut reader, compress, validate)?, None),
+            Compress::Yes => match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+                COMPRESSED_NO_DECAPSULATION_KEY => (None, None),
+                COMPRESSED_DECAPSULATION_KEY => {
+                    (Some(Vec::<u8>::deserialize_with_mode(&mut reader, compress, validate)?), None)
+                }
+                COMPRESSED_MLKEM_SEED => {
+                    let seed = <[u8; pairing_mlkem::SEED_SIZE]>::deserialize_with_mode(&mut reader, compress, validate)?;
+                    let (dk, _) = pairing_mlkem::generate_encapsulation_key_from_seed(&seed);
+                    (Some(dk), Some(seed.to_vec()))
+                }
+                _ => return Err(SerializationError::InvalidData),
+            },
+        };
+
+        Ok(PairingSecretKeyMaterial {
+            mlkem_decapsulation_key,
+            mlkem_shared_secret: Option::<[u8; 32]>::deserialize_with_mode(&mut reader, compress, validate)?,
+            ecies_secret_key: Vec::<u8>::deserialize_with_mode(&mut reader, compress, validate)?,
+            mlkem_seed,
+        })
+    }
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -67,6 +211,135 @@ pub struct PairingRequestMessageMaterial {
 
 pub type PairingSharedKey = [u8; 32];
 
+/// Domain separator for the HKDF step used by `rekey_pairing`.
+const REKEY_INFO: &[u8] = b"derec-pairing-rekey";
+
+/// The number of decapsulation keys a `DecapsulationLimiter::new` limiter tracks at once.
+pub const DEFAULT_LIMITER_CAPACITY: usize = 1024;
+
+/// Caps the number of decapsulations `finish_pairing_contactor_with_limiter` performs with each decapsulation key.
+///
+/// A contactor that finishes pairings for whoever sends a request exposes its decapsulation key as an
+/// oracle. ML-KEM is IND-CCA2 secure, so this is not known to leak anything, but a contact message is
+/// meant to be answered a handful of times at most; capping the attempts per key bounds what an attacker
+/// submitting crafted ciphertexts can learn. Keys are tracked by the SHA-256 hash of the decapsulation key,
+/// and the limiter can be shared between threads.
+///
+/// At most `capacity` keys are tracked; once full, the key used least recently is forgotten to make room.
+/// Only the contactor's own keys are tracked, so the capacity should exceed the number of contact messages
+/// it keeps open at once.
+///
+/// # Example
+/// ```rust
+/// use derec_cryptography::pairing::*;
+/// use derec_cryptography::pairing::pairing_ecies::Secp256k1Ecies;
+/// use derec_cryptography::pairing::pairing_mlkem::MlKem;
+/// let limiter = DecapsulationLimiter::new(1);
+/// let (contact_msg, contactor_secrets) = contact_message(rand::random()).unwrap();
+/// let (request_msg, _) = pairing_request_message(rand::random(), &contact_msg).unwrap();
+/// assert!(finish_pairing_contactor_with_limiter::<MlKem, Secp256k1Ecies>(&contactor_secrets, &request_msg, None, &limiter).is_ok());
+/// assert!(matches!(
+///     finish_pairing_contactor_with_limiter::<MlKem, Secp256k1Ecies>(&contactor_secrets, &request_msg, None, &limiter),
+///     Err(DerecPairingError::RateLimited)
+/// ));
+/// ```
+#[derive(Debug)]
+pub struct DecapsulationLimiter {
+    max_attempts: u32,
+    capacity: usize,
+    // per key: the attempts so far, and when the key was last used (by `clock`)
+    attempts: Mutex<HashMap<[u8; 32], (u32, u64)>>,
+    clock: std::sync::atomic::AtomicU64,
+}
+
+impl DecapsulationLimiter {
+    /// Creates a limiter allowing `max_attempts` decapsulations per decapsulation key, tracking up to
+    /// `DEFAULT_LIMITER_CAPACITY` keys.
+    pub fn new(max_attempts: u32) -> Self {
+        Self::with_capacity(max_attempts, DEFAULT_LIMITER_CAPACITY)
+    }
+
+    /// Creates a limiter allowing `max_attempts` decapsulations per decapsulation key, tracking up to
+    /// `capacity` keys (at least one).
+    pub fn with_capacity(max_attempts: u32, capacity: usize) -> Self {
+        DecapsulationLimiter {
+            max_attempts,
+            capacity: capacity.max(1),
+            attempts: Mutex::new(HashMap::new()),
+            clock: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the number of decapsulations attempted so far with `decapsulation_key`.
+    pub fn attempts(&self, decapsulation_key: &[u8]) -> u32 {
+        let attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        attempts.get(&Self::key_id(decapsulation_key)).map_or(0, |(count, _)| *count)
+    }
+
+    // records an attempt with the key, unless it has used up its attempts
+    fn try_acquire(&self, decapsulation_key: &[u8]) -> Result<(), DerecPairingError> {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        let key_id = Self::key_id(decapsulation_key);
+        if !attempts.contains_key(&key_id) && attempts.len() >= self.capacity {
+            let least_recent = attempts.iter().min_by_key(|(_, (_, used))| *used).map(|(id, _)| *id);
+            if let Some(id) = least_recent {
+                attempts.remove(&id);
+            }
+        }
+
+        let now = self.clock.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let (count, used) = attempts.entry(key_id).or_insert((0, now));
+        *used = now;
+        if *count >= self.max_attempts {
+            return Err(DerecPairingError::RateLimited);
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    fn key_id(decapsulation_key: &[u8]) -> [u8; 32] {
+        Sha256::digest(decapsulation_key).into()
+    }
+}
+
+/// A key encapsulation mechanism over opaque byte encodings of its keys and ciphertexts.
+pub trait Kem {
+    /// Generates a fresh key pair, returned as (decapsulation key, encapsulation key).
+    fn generate<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>);
+
+    /// Generates a fresh key pair like `generate`, also returning the seed it was derived from if the
+    /// KEM can regenerate its decapsulation key from a seed. The default keeps no seed.
+    fn generate_with_seed<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>, Option<Vec<u8>>) {
+        let (dk, ek) = Self::generate(rng);
+        (dk, ek, None)
+    }
+
+    /// Encapsulates a fresh shared secret to `ek`, returning (ciphertext, shared secret).
+    /// The shared secret must be 32 bytes to be usable for pairing.
+    fn encapsulate<R: CryptoRngCore>(ek: &[u8], rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError>;
+
+    /// Recovers the shared secret encapsulated in `ctxt` using the decapsulation key `dk`.
+    fn decapsulate(dk: &[u8], ctxt: &[u8]) -> Result<Vec<u8>, DerecPairingError>;
+
+    /// Re-derives the encapsulation key belonging to the decapsulation key `dk`.
+    fn encapsulation_key(dk: &[u8]) -> Result<Vec<u8>, DerecPairingError>;
+}
+
+/// A classical Diffie-Hellman style key exchange over opaque byte encodings of its keys.
+pub trait KeyExchange {
+    /// Generates a fresh key pair, returned as (secret key, public key).
+    fn generate_key<R: CryptoRngCore>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError>;
+
+    /// Re-derives the public key belonging to the secret key `sk`.
+    fn public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError>;
+
+    /// Checks that `pk` is a well-formed public key for this key exchange.
+    fn validate_public_key(pk: &[u8]) -> Result<(), DerecPairingError>;
+
+    /// Derives the 32-byte shared key between the secret key `sk` and the peer's public key `pk`.
+    fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<Vec<u8>, DerecPairingError>;
+}
+
 /// Generates a contact message and corresponding secret key material for the contactor (initiator) in the pairing protocol.
 ///
 /// This function performs the following steps:
@@ -77,7 +350,8 @@ pub type PairingSharedKey = [u8; 32];
 ///
 ///
 /// # Arguments
-/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes).
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes). The seed must be
+///   drawn from a CSPRNG (e.g. `OsRng`); anyone who learns it can recompute the secret key material.
 /// 
 /// # Returns
 /// - `Ok((PairingContactMessageMaterial, PairingSecretKeyMaterial))` on success, containing:
@@ -86,18 +360,28 @@ pub type PairingSharedKey = [u8; 32];
 /// - `Err(DerecPairingError)` if key generation fails.
 ///
 /// # Errors
-/// Returns `DerecPairingError` if ECIES key generation fails.
+/// Returns `DerecPairingError` if ECIES key generation fails, or `DerecPairingError::KeyGenSelfTestFailed`
+/// if the generated ECIES public key does not match the secret key, or `DerecPairingError::WeakSeed`
+/// if `entropy` is obviously not random (see `seed::is_weak_seed`).
 ///
 /// # Example
 /// ```rust
 /// use derec_cryptography::pairing::*;
-/// let (contact_msg, secret_keys) = contact_message([0u8; 32]).unwrap();
+/// let (contact_msg, secret_keys) = contact_message(rand::random()).unwrap();
 /// // Send `contact_msg` to the responder, keep `secret_keys` for later.
 /// ```
 pub fn contact_message(entropy: [u8; 32]) -> Result<(PairingContactMessageMaterial, PairingSecretKeyMaterial), DerecPairingError> {
+    contact_message_with::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(entropy)
+}
+
+/// Generates a contact message like `contact_message`, using the KEM `K` and key exchange `X`.
+pub fn contact_message_with<K: Kem, X: KeyExchange>(
+    entropy: [u8; 32]
+) -> Result<(PairingContactMessageMaterial, PairingSecretKeyMaterial), DerecPairingError> {
+    require_nonzero_seed(&entropy)?;
     let mut csprng = rand_chacha::ChaCha8Rng::from_seed(entropy);
-    let (dk, ek) = pairing_mlkem::generate_encapsulation_key(&mut csprng);
-    let (sk, pk) = pairing_ecies::generate_key(&mut csprng)?;
+    let (dk, ek, seed) = K::generate_with_seed(&mut csprng);
+    let (sk, pk) = generate_checked_key::<X>(&mut csprng)?;
     
     Ok((
         PairingContactMessageMaterial {
@@ -108,10 +392,67 @@ pub fn contact_message(entropy: [u8; 32]) -> Result<(PairingContactMessageMateri
             mlkem_decapsulation_key: Some(dk),
             mlkem_shared_secret: None,
             ecies_secret_key: sk,
+            mlkem_seed: seed,
         }
     ))
 }
 
+// generates a key exchange key pair, re-deriving the public key from the secret key
+// so that a mismatched pair is never handed out
+fn generate_checked_key<X: KeyExchange>(
+    rng: &mut impl CryptoRngCore
+) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+    let (sk, pk) = X::generate_key(rng)?;
+    if X::public_key(&sk)? != pk {
+        return Err(DerecPairingError::KeyGenSelfTestFailed);
+    }
+    Ok((sk, pk))
+}
+
+/// Re-derives the contact message material that was generated alongside `secrets`.
+///
+/// A contactor that persisted its `PairingSecretKeyMaterial` but lost the contact message
+/// it handed out can use this to resume pairing without generating new keys: the ML-KEM
+/// encapsulation key is recovered from the decapsulation key, and the ECIES public key
+/// from the ECIES secret key.
+///
+/// # Arguments
+/// * `secrets` - The `PairingSecretKeyMaterial` returned by `contact_message`.
+///
+/// # Returns
+/// - `Ok(PairingContactMessageMaterial)` equal to the one originally returned by `contact_message`.
+/// - `Err(DerecPairingError)` if the material cannot be re-derived.
+///
+/// # Errors
+/// Returns `DerecPairingError::PairingStateError` if `secrets` has no ML-KEM decapsulation key
+/// (i.e. it belongs to a requestor), or propagates decoding errors from either key.
+///
+/// # Example
+/// ```rust
+/// use derec_cryptography::pairing::*;
+/// let (contact_msg, secret_keys) = contact_message(rand::random()).unwrap();
+/// let restored = reconstruct_contact_material(&secret_keys).unwrap();
+/// assert_eq!(restored.ecies_public_key, contact_msg.ecies_public_key);
+/// ```
+pub fn reconstruct_contact_material(
+    secrets: &PairingSecretKeyMaterial
+) -> Result<PairingContactMessageMaterial, DerecPairingError> {
+    reconstruct_contact_material_with::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(secrets)
+}
+
+/// Re-derives contact message material like `reconstruct_contact_material`, using the KEM `K`
+/// and key exchange `X`. Both must be the primitives the contact message was generated with.
+pub fn reconstruct_contact_material_with<K: Kem, X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial
+) -> Result<PairingContactMessageMaterial, DerecPairingError> {
+    let mlkem_dk = secrets.mlkem_decapsulation_key.as_ref().ok_or(DerecPairingError::PairingStateError)?;
+
+    Ok(PairingContactMessageMaterial {
+        mlkem_encapsulation_key: K::encapsulation_key(mlkem_dk)?,
+        ecies_public_key: X::public_key(&secrets.ecies_secret_key)?,
+    })
+}
+
 /// Generates a pairing request message and corresponding secret key material for the requestor (responder) in the pairing protocol.
 ///
 /// This function performs the following steps:
@@ -122,7 +463,8 @@ pub fn contact_message(entropy: [u8; 32]) -> Result<(PairingContactMessageMateri
 /// 4. Returns the secret components as `PairingSecretKeyMaterial` to be retained by the requestor.
 ///
 /// # Arguments
-/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes).
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes). The seed must be
+///   drawn from a CSPRNG (e.g. `OsRng`); anyone who learns it can recompute the secret key material.
 /// * `received` - The `PairingContactMessageMaterial` received from the contactor (initiator).
 ///
 /// # Returns
@@ -132,23 +474,37 @@ pub fn contact_message(entropy: [u8; 32]) -> Result<(PairingContactMessageMateri
 /// - `Err(DerecPairingError)` if encapsulation or key generation fails.
 ///
 /// # Errors
-/// Returns `DerecPairingError` if ML-KEM encapsulation or ECIES key generation fails.
+/// Returns `DerecPairingError` if ML-KEM encapsulation or ECIES key generation fails, or
+/// `DerecPairingError::WeakSeed` if `entropy` is obviously not random (see `seed::is_weak_seed`).
 ///
 /// # Example
 /// ```rust
 /// use derec_cryptography::pairing::*;
-/// let (contact_msg, _) = contact_message([0u8; 32]).unwrap();
-/// let (request_msg, secret_keys) = pairing_request_message([0u8; 32], &contact_msg).unwrap();
+/// let (contact_msg, _) = contact_message(rand::random()).unwrap();
+/// let (request_msg, secret_keys) = pairing_request_message(rand::random(), &contact_msg).unwrap();
 /// // Send `request_msg` to the contactor, keep `secret_keys` for later.
 /// ```
 pub fn pairing_request_message(
     entropy: [u8; 32],
     received: &PairingContactMessageMaterial
 ) -> Result<(PairingRequestMessageMaterial, PairingSecretKeyMaterial), DerecPairingError> {
+    pairing_request_message_with::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(entropy, received)
+}
+
+/// Generates a pairing request message like `pairing_request_message`, using the KEM `K` and key exchange `X`.
+pub fn pairing_request_message_with<K: Kem, X: KeyExchange>(
+    entropy: [u8; 32],
+    received: &PairingContactMessageMaterial
+) -> Result<(PairingRequestMessageMaterial, PairingSecretKeyMaterial), DerecPairingError> {
+    require_nonzero_seed(&entropy)?;
     let mut csprng = rand_chacha::ChaCha8Rng::from_seed(entropy);
 
-    let (ct, shared_key) = pairing_mlkem::encapsulate(&received.mlkem_encapsulation_key, &mut csprng)?;
-    let (sk, pk) = pairing_ecies::generate_key(&mut csprng)?;
+    let (ct, shared_key) = K::encapsulate(&received.mlkem_encapsulation_key, &mut csprng)?;
+    let shared_key: [u8; 32] = shared_key
+        .as_slice()
+        .try_into()
+        .map_err(|_| DerecPairingError::LengthMismatch { mlkem: shared_key.len(), ecies: 32 })?;
+    let (sk, pk) = generate_checked_key::<X>(&mut csprng)?;
 
     Ok((
         PairingRequestMessageMaterial {
@@ -159,6 +515,7 @@ pub fn pairing_request_message(
             mlkem_decapsulation_key: None,
             mlkem_shared_secret: Some(shared_key),
             ecies_secret_key: sk,
+            mlkem_seed: None,
         },
     ))
 }
@@ -172,6 +529,7 @@ pub fn pairing_request_message(
 /// # Arguments
 /// * `secrets` - The `PairingSecretKeyMaterial` held by the requestor, containing the ML-KEM shared secret and ECIES secret key.
 /// * `received` - The `PairingContactMessageMaterial` received from the contactor, containing the ECIES public key.
+/// * `context` - An optional deployment context mixed into the HKDF `info`; both parties must supply the same value.
 ///
 /// # Returns
 /// - `Ok(PairingSharedKey)` containing the derived 256-bit shared key if successful.
@@ -184,19 +542,29 @@ pub fn pairing_request_message(
 /// # Example
 /// ```rust
 /// use derec_cryptography::pairing::*;
-/// let (contact_msg, _) = contact_message([0u8; 32]).unwrap();
-/// let (request_msg, secret_keys) = pairing_request_message([0u8; 32], &contact_msg).unwrap();
-/// let shared_key = finish_pairing_requestor(&secret_keys, &contact_msg).unwrap();
+/// let (contact_msg, _) = contact_message(rand::random()).unwrap();
+/// let (request_msg, secret_keys) = pairing_request_message(rand::random(), &contact_msg).unwrap();
+/// let shared_key = finish_pairing_requestor(&secret_keys, &contact_msg, None).unwrap();
 /// ```
 pub fn finish_pairing_requestor(
     secrets: &PairingSecretKeyMaterial,
-    received: &PairingContactMessageMaterial
+    received: &PairingContactMessageMaterial,
+    context: Option<&[u8]>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    finish_pairing_requestor_with::<pairing_ecies::Secp256k1Ecies>(secrets, received, context)
+}
+
+/// Completes the pairing protocol for the requestor like `finish_pairing_requestor`, using the key exchange `X`.
+/// `X` must be the key exchange the contact message was generated with.
+pub fn finish_pairing_requestor_with<X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial,
+    received: &PairingContactMessageMaterial,
+    context: Option<&[u8]>
 ) -> Result<PairingSharedKey, DerecPairingError> {
     let mlkem_shared_key = secrets.mlkem_shared_secret.ok_or(DerecPairingError::PairingStateError)?;
-    let ecies_shared_key = pairing_ecies::derive_shared_key(&secrets.ecies_secret_key, &received.ecies_public_key)?;
+    let ecies_shared_key = X::derive_shared_key(&secrets.ecies_secret_key, &received.ecies_public_key)?;
 
-    // xor and return
-    Ok(std::array::from_fn(|i| mlkem_shared_key[i] ^ ecies_shared_key[i]))
+    combine_shared_keys(&mlkem_shared_key, &ecies_shared_key, context)
 }
 
 /// Completes the pairing protocol for the contactor (initiator) and derives the final shared 256-bit key.
@@ -211,6 +579,7 @@ pub fn finish_pairing_requestor(
 /// # Arguments
 /// * `secrets` - The `PairingSecretKeyMaterial` held by the contactor, containing the ML-KEM decapsulation key and ECIES secret key.
 /// * `received` - The `PairingRequestMessageMaterial` received from the requestor, containing the ML-KEM ciphertext and ECIES public key.
+/// * `context` - An optional deployment context mixed into the HKDF `info`; both parties must supply the same value.
 ///
 /// # Returns
 /// - `Ok(PairingSharedKey)` containing the derived 256-bit shared key if successful.
@@ -223,20 +592,218 @@ pub fn finish_pairing_requestor(
 /// # Example
 /// ```rust
 /// use derec_cryptography::pairing::*;
-/// let (contact_msg, contactor_secrets) = contact_message([0u8; 32]).unwrap();
-/// let (request_msg, _) = pairing_request_message([0u8; 32], &contact_msg).unwrap();
-/// let shared_key = finish_pairing_contactor(&contactor_secrets, &request_msg).unwrap();
+/// let (contact_msg, contactor_secrets) = contact_message(rand::random()).unwrap();
+/// let (request_msg, _) = pairing_request_message(rand::random(), &contact_msg).unwrap();
+/// let shared_key = finish_pairing_contactor(&contactor_secrets, &request_msg, None).unwrap();
 /// ```
 pub fn finish_pairing_contactor(
     secrets: &PairingSecretKeyMaterial,
-    received: &PairingRequestMessageMaterial
+    received: &PairingRequestMessageMaterial,
+    context: Option<&[u8]>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    finish_pairing_contactor_with::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(secrets, received, context)
+}
+
+/// Completes the pairing protocol for the contactor like `finish_pairing_contactor`, using the KEM `K`
+/// and key exchange `X`. Both must be the primitives the contact message was generated with.
+pub fn finish_pairing_contactor_with<K: Kem, X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial,
+    received: &PairingRequestMessageMaterial,
+    context: Option<&[u8]>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    finish_contactor::<K, X>(secrets, received, context, None)
+}
+
+/// Completes the pairing protocol for the contactor like `finish_pairing_contactor_with`, first recording
+/// the attempt with the contactor's decapsulation key in `limiter`.
+///
+/// # Errors
+/// Returns `DerecPairingError::RateLimited` if the limiter allows no further attempts with the key, besides
+/// the errors of `finish_pairing_contactor`.
+pub fn finish_pairing_contactor_with_limiter<K: Kem, X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial,
+    received: &PairingRequestMessageMaterial,
+    context: Option<&[u8]>,
+    limiter: &DecapsulationLimiter
+) -> Result<PairingSharedKey, DerecPairingError> {
+    finish_contactor::<K, X>(secrets, received, context, Some(limiter))
+}
+
+fn finish_contactor<K: Kem, X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial,
+    received: &PairingRequestMessageMaterial,
+    context: Option<&[u8]>,
+    limiter: Option<&DecapsulationLimiter>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    let mlkem_dk = secrets.mlkem_decapsulation_key.as_ref().ok_or(DerecPairingError::PairingStateError)?;
+    if let Some(limiter) = limiter {
+        limiter.try_acquire(mlkem_dk)?;
+    }
+    let mlkem_shared_key = K::decapsulate(mlkem_dk, &received.mlkem_ciphertext)?;
+    let ecies_shared_key = X::derive_shared_key(&secrets.ecies_secret_key, &received.ecies_public_key)?;
+
+    combine_shared_keys(&mlkem_shared_key, &ecies_shared_key, context)
+}
+
+/// The public material a party received from its peer during pairing, as persisted for `resume_shared_key`.
+pub enum PeerPublicMaterial<'a> {
+    /// The contact message, held by the requestor.
+    Contact(&'a PairingContactMessageMaterial),
+    /// The pairing request message, held by the contactor.
+    Request(&'a PairingRequestMessageMaterial),
+}
+
+/// Re-derives the shared key of a completed pairing, e.g. after a restart, without pairing again.
+///
+/// Each party persists its `PairingSecretKeyMaterial` and the public material it received from its peer
+/// (both are `CanonicalSerialize`). Feeding them back in here yields exactly the key `finish_pairing_requestor`
+/// or `finish_pairing_contactor` derived, for either role: the role is given by the kind of peer material.
+/// `context` must be the context the pairing was finished with. No `DecapsulationLimiter` is consulted, as
+/// the contactor only decapsulates a ciphertext it has already accepted.
+///
+/// # Errors
+/// Returns `DerecPairingError::PairingStateError` if `secrets` do not belong to the role implied by `peer`
+/// (e.g. a requestor's secrets with a pairing request), or the errors of the `finish_pairing_*` functions.
+///
+/// # Example
+/// ```rust
+/// use derec_cryptography::pairing::*;
+/// let (contact_msg, contactor_secrets) = contact_message(rand::random()).unwrap();
+/// let (request_msg, requestor_secrets) = pairing_request_message(rand::random(), &contact_msg).unwrap();
+/// let shared_key = finish_pairing_requestor(&requestor_secrets, &contact_msg, None).unwrap();
+///
+/// // after a restart
+/// let resumed = resume_shared_key(&contactor_secrets, PeerPublicMaterial::Request(&request_msg), None).unwrap();
+/// assert_eq!(resumed, shared_key);
+/// ```
+pub fn resume_shared_key(
+    secrets: &PairingSecretKeyMaterial,
+    peer: PeerPublicMaterial,
+    context: Option<&[u8]>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    resume_shared_key_with::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(secrets, peer, context)
+}
+
+/// Re-derives the shared key of a pairing like `resume_shared_key`, using the KEM `K` and key exchange `X`.
+/// Both must be the primitives the pairing was completed with.
+pub fn resume_shared_key_with<K: Kem, X: KeyExchange>(
+    secrets: &PairingSecretKeyMaterial,
+    peer: PeerPublicMaterial,
+    context: Option<&[u8]>
 ) -> Result<PairingSharedKey, DerecPairingError> {
-    let mlkem_dk = secrets.mlkem_decapsulation_key.to_owned().ok_or(DerecPairingError::PairingStateError)?;
-    let mlkem_shared_key = pairing_mlkem::decapsulate(&mlkem_dk, &received.mlkem_ciphertext)?;
-    let ecies_shared_key = pairing_ecies::derive_shared_key(&secrets.ecies_secret_key, &received.ecies_public_key)?;
+    match peer {
+        PeerPublicMaterial::Contact(contact) => finish_pairing_requestor_with::<X>(secrets, contact, context),
+        PeerPublicMaterial::Request(request) => finish_pairing_contactor_with::<K, X>(secrets, request, context),
+    }
+}
 
-    // xor and return
-    Ok(std::array::from_fn(|i| mlkem_shared_key[i] ^ ecies_shared_key[i]))
+/// Generates an ephemeral key pair for one round of `rekey_pairing`, returned as (secret key, public key).
+///
+/// Each party generates its own pair per round, sends the public key to its peer and discards the
+/// secret key once the new pairing key has been derived.
+///
+/// # Errors
+/// Returns `DerecPairingError::WeakSeed` if `entropy` is obviously not random, or the errors of
+/// key generation as for `contact_message`.
+pub fn rekey_ephemeral(entropy: [u8; 32]) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+    rekey_ephemeral_with::<pairing_ecies::Secp256k1Ecies>(entropy)
+}
+
+/// Generates an ephemeral key pair like `rekey_ephemeral`, using the key exchange `X`.
+pub fn rekey_ephemeral_with<X: KeyExchange>(entropy: [u8; 32]) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+    require_nonzero_seed(&entropy)?;
+    let mut csprng = rand_chacha::ChaCha8Rng::from_seed(entropy);
+    generate_checked_key::<X>(&mut csprng)
+}
+
+/// Derives a fresh pairing key from the current one and a round of ephemeral key exchange.
+///
+/// The contactor and requestor each generate an ephemeral key pair with `rekey_ephemeral` and exchange
+/// only the public keys. Each then calls this function with its own ephemeral secret key and both public
+/// keys (in the same contactor-then-requestor order on both sides). The new key is HKDF-SHA256 of the
+/// ephemeral shared secret, salted with the current key and bound to both public keys.
+///
+/// The derivation is one-way, so a compromise of the new key does not reveal the old one; and since the
+/// ephemeral secrets are discarded after the round, a compromised old key alone does not reveal the new
+/// one either. Channels can thus be re-keyed periodically without repeating the full pairing.
+///
+/// # Errors
+/// Returns `DerecPairingError::PairingStateError` if `own_ephemeral_sk` belongs to neither public key,
+/// or propagates errors from the key exchange.
+///
+/// # Example
+/// ```rust
+/// use derec_cryptography::pairing::*;
+/// let current = [7u8; 32];
+/// let (contactor_sk, contactor_pk) = rekey_ephemeral(rand::random()).unwrap();
+/// let (requestor_sk, requestor_pk) = rekey_ephemeral(rand::random()).unwrap();
+/// let contactor_key = rekey_pairing(&current, &contactor_sk, &contactor_pk, &requestor_pk).unwrap();
+/// let requestor_key = rekey_pairing(&current, &requestor_sk, &contactor_pk, &requestor_pk).unwrap();
+/// assert_eq!(contactor_key, requestor_key);
+/// ```
+pub fn rekey_pairing(
+    current: &PairingSharedKey,
+    own_ephemeral_sk: &[u8],
+    contactor_ephemeral_pk: &[u8],
+    requestor_ephemeral_pk: &[u8]
+) -> Result<PairingSharedKey, DerecPairingError> {
+    rekey_pairing_with::<pairing_ecies::Secp256k1Ecies>(current, own_ephemeral_sk, contactor_ephemeral_pk, requestor_ephemeral_pk)
+}
+
+/// Re-keys a pairing like `rekey_pairing`, using the key exchange `X`.
+pub fn rekey_pairing_with<X: KeyExchange>(
+    current: &PairingSharedKey,
+    own_ephemeral_sk: &[u8],
+    contactor_ephemeral_pk: &[u8],
+    requestor_ephemeral_pk: &[u8]
+) -> Result<PairingSharedKey, DerecPairingError> {
+    let own_pk = X::public_key(own_ephemeral_sk)?;
+    let peer_pk = if own_pk == contactor_ephemeral_pk {
+        requestor_ephemeral_pk
+    } else if own_pk == requestor_ephemeral_pk {
+        contactor_ephemeral_pk
+    } else {
+        return Err(DerecPairingError::PairingStateError);
+    };
+    let ephemeral_shared_key = X::derive_shared_key(own_ephemeral_sk, peer_pk)?;
+
+    let info = [REKEY_INFO, contactor_ephemeral_pk, requestor_ephemeral_pk].concat();
+    let mut okm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(current), &ephemeral_shared_key)
+        .expand(&info, &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Ok(okm)
+}
+
+// Combines the ML-KEM and ECIES secrets into the final pairing key.
+// The two secrets are xor-ed together; if a deployment context is given,
+// the result is additionally run through HKDF-SHA256 with the context as `info`.
+// Both secrets must be exactly as long as the pairing key, or the xor would
+// silently truncate (or pad) one of them.
+fn combine_shared_keys(
+    mlkem_shared_key: &[u8],
+    ecies_shared_key: &[u8],
+    context: Option<&[u8]>
+) -> Result<PairingSharedKey, DerecPairingError> {
+    let key_size = std::mem::size_of::<PairingSharedKey>();
+    if mlkem_shared_key.len() != key_size || ecies_shared_key.len() != key_size {
+        return Err(DerecPairingError::LengthMismatch {
+            mlkem: mlkem_shared_key.len(),
+            ecies: ecies_shared_key.len(),
+        });
+    }
+    let combined: PairingSharedKey = std::array::from_fn(|i| mlkem_shared_key[i] ^ ecies_shared_key[i]);
+
+    Ok(match context {
+        None => combined,
+        Some(info) => {
+            let mut okm = [0u8; 32];
+            Hkdf::<Sha256>::new(None, &combined)
+                .expand(info, &mut okm)
+                .expect("32 bytes is a valid HKDF-SHA256 output length");
+            okm
+        }
+    })
 }
 
 #[cfg(test)]
@@ -249,9 +816,352 @@ mod tests {
         let (bob_contact, bob_secrets) = contact_message([0u8; 32]).unwrap();
         let (alice_request, alice_secrets) = pairing_request_message([0u8; 32], &bob_contact).unwrap();
 
-        let alice_shared_key = finish_pairing_requestor(&alice_secrets, &bob_contact).unwrap();
-        let bob_shared_key = finish_pairing_contactor(&bob_secrets, &alice_request).unwrap();
+        let alice_shared_key = finish_pairing_requestor(&alice_secrets, &bob_contact, None).unwrap();
+        let bob_shared_key = finish_pairing_contactor(&bob_secrets, &alice_request, None).unwrap();
 
         assert_eq!(alice_shared_key, bob_shared_key);
     }
+
+    #[test]
+    fn test_pairing_with_deployment_context() {
+        let (bob_contact, bob_secrets) = contact_message([1u8; 32]).unwrap();
+        let (alice_request, alice_secrets) = pairing_request_message([2u8; 32], &bob_contact).unwrap();
+
+        let staging = Some(&b"derec-staging"[..]);
+        let production = Some(&b"derec-production"[..]);
+
+        // same context on both sides agrees
+        let alice_staging = finish_pairing_requestor(&alice_secrets, &bob_contact, staging).unwrap();
+        let bob_staging = finish_pairing_contactor(&bob_secrets, &alice_request, staging).unwrap();
+        assert_eq!(alice_staging, bob_staging);
+
+        // distinct contexts derive independent keys from the same secrets
+        let alice_production = finish_pairing_requestor(&alice_secrets, &bob_contact, production).unwrap();
+        let bob_production = finish_pairing_contactor(&bob_secrets, &alice_request, production).unwrap();
+        assert_eq!(alice_production, bob_production);
+        assert_ne!(alice_staging, alice_production);
+
+        // and both differ from the context-free key
+        let alice_plain = finish_pairing_requestor(&alice_secrets, &bob_contact, None).unwrap();
+        assert_ne!(alice_plain, alice_staging);
+    }
+
+    // a deliberately insecure KEM: the encapsulation key equals the decapsulation key,
+    // and the ciphertext is the shared secret xor-ed with it
+    struct ToyKem;
+
+    impl Kem for ToyKem {
+        fn generate<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+            let mut key = [0u8; 32];
+            rng.fill_bytes(&mut key);
+            (key.to_vec(), key.to_vec())
+        }
+
+        fn encapsulate<R: CryptoRngCore>(ek: &[u8], rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+            let mut shared = [0u8; 32];
+            rng.fill_bytes(&mut shared);
+            let ct = shared.iter().zip(ek).map(|(s, k)| s ^ k).collect();
+            Ok((ct, shared.to_vec()))
+        }
+
+        fn decapsulate(dk: &[u8], ctxt: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            if dk.len() != 32 || ctxt.len() != 32 {
+                return Err(DerecPairingError::MLKemDecapsulationError);
+            }
+            Ok(ctxt.iter().zip(dk).map(|(c, k)| c ^ k).collect())
+        }
+
+        fn encapsulation_key(dk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            Ok(dk.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_pairing_with_custom_kem() {
+        type Ecies = pairing_ecies::Secp256k1Ecies;
+        let (bob_contact, bob_secrets) = contact_message_with::<ToyKem, Ecies>([3u8; 32]).unwrap();
+        assert_eq!(bob_contact.mlkem_encapsulation_key.len(), 32);
+
+        let (alice_request, alice_secrets) = pairing_request_message_with::<ToyKem, Ecies>([4u8; 32], &bob_contact).unwrap();
+        assert_eq!(alice_request.mlkem_ciphertext.len(), 32);
+
+        let alice_shared_key = finish_pairing_requestor(&alice_secrets, &bob_contact, None).unwrap();
+        let bob_shared_key = finish_pairing_contactor_with::<ToyKem, Ecies>(&bob_secrets, &alice_request, None).unwrap();
+        assert_eq!(alice_shared_key, bob_shared_key);
+
+        // the ML-KEM contactor cannot decapsulate the toy ciphertext
+        assert!(finish_pairing_contactor(&bob_secrets, &alice_request, None).is_err());
+    }
+
+    #[test]
+    fn test_pairing_over_x25519() {
+        type MlKem = pairing_mlkem::MlKem;
+        type X25519 = pairing_x25519::X25519;
+
+        let (bob_contact, bob_secrets) = contact_message_with::<MlKem, X25519>([5u8; 32]).unwrap();
+        assert_eq!(bob_contact.ecies_public_key.len(), pairing_x25519::KEY_SIZE);
+        let (alice_request, alice_secrets) = pairing_request_message_with::<MlKem, X25519>([6u8; 32], &bob_contact).unwrap();
+
+        let alice_shared_key = finish_pairing_requestor_with::<X25519>(&alice_secrets, &bob_contact, None).unwrap();
+        let bob_shared_key = finish_pairing_contactor_with::<MlKem, X25519>(&bob_secrets, &alice_request, None).unwrap();
+        assert_eq!(alice_shared_key, bob_shared_key);
+    }
+
+    // a key exchange that (wrongly) produces 16-byte shared secrets
+    struct ShortKeyExchange;
+
+    impl KeyExchange for ShortKeyExchange {
+        fn generate_key<R: CryptoRngCore>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+            let mut key = [0u8; 16];
+            rng.fill_bytes(&mut key);
+            Ok((key.to_vec(), key.to_vec()))
+        }
+
+        fn public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            Ok(sk.to_vec())
+        }
+
+        fn validate_public_key(_pk: &[u8]) -> Result<(), DerecPairingError> {
+            Ok(())
+        }
+
+        fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            Ok(sk.iter().zip(pk).map(|(a, b)| a ^ b).collect())
+        }
+    }
+
+    #[test]
+    fn test_pairing_rejects_mismatched_secret_lengths() {
+        type Short = ShortKeyExchange;
+        let (bob_contact, bob_secrets) = contact_message_with::<pairing_mlkem::MlKem, Short>([3u8; 32]).unwrap();
+        let (alice_request, alice_secrets) =
+            pairing_request_message_with::<pairing_mlkem::MlKem, Short>([4u8; 32], &bob_contact).unwrap();
+
+        assert!(matches!(
+            finish_pairing_requestor_with::<Short>(&alice_secrets, &bob_contact, None),
+            Err(DerecPairingError::LengthMismatch { mlkem: 32, ecies: 16 })
+        ));
+        assert!(matches!(
+            finish_pairing_contactor_with::<pairing_mlkem::MlKem, Short>(&bob_secrets, &alice_request, None),
+            Err(DerecPairingError::LengthMismatch { mlkem: 32, ecies: 16 })
+        ));
+    }
+
+    // a key exchange whose key generation (wrongly) returns an unrelated public key
+    struct MismatchedKeyExchange;
+
+    impl KeyExchange for MismatchedKeyExchange {
+        fn generate_key<R: CryptoRngCore>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+            let (sk, _) = pairing_ecies::generate_key(rng)?;
+            let (_, other_pk) = pairing_ecies::generate_key(rng)?;
+            Ok((sk, other_pk))
+        }
+
+        fn public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            pairing_ecies::derive_public_key(sk)
+        }
+
+        fn validate_public_key(pk: &[u8]) -> Result<(), DerecPairingError> {
+            pairing_ecies::validate_public_key(pk)
+        }
+
+        fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+            pairing_ecies::derive_shared_key(sk, pk).map(|key| key.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_keygen_self_test_catches_mismatched_keypair() {
+        type MlKem = pairing_mlkem::MlKem;
+        assert!(matches!(
+            contact_message_with::<MlKem, MismatchedKeyExchange>([3u8; 32]),
+            Err(DerecPairingError::KeyGenSelfTestFailed)
+        ));
+
+        let (bob_contact, _) = contact_message([3u8; 32]).unwrap();
+        assert!(matches!(
+            pairing_request_message_with::<MlKem, MismatchedKeyExchange>([4u8; 32], &bob_contact),
+            Err(DerecPairingError::KeyGenSelfTestFailed)
+        ));
+
+        // the ECIES check itself accepts a genuine pair and rejects a tampered one
+        let (sk, pk) = pairing_ecies::generate_key(&mut rand::rngs::OsRng).unwrap();
+        assert!(pairing_ecies::verify_keypair(&sk, &pk).is_ok());
+        let (_, other_pk) = pairing_ecies::generate_key(&mut rand::rngs::OsRng).unwrap();
+        assert!(matches!(
+            pairing_ecies::verify_keypair(&sk, &other_pk),
+            Err(DerecPairingError::KeyGenSelfTestFailed)
+        ));
+    }
+
+    #[test]
+    fn test_reconstruct_contact_material() {
+        let (bob_contact, bob_secrets) = contact_message([5u8; 32]).unwrap();
+        let restored = reconstruct_contact_material(&bob_secrets).unwrap();
+        assert_eq!(restored.mlkem_encapsulation_key, bob_contact.mlkem_encapsulation_key);
+        assert_eq!(restored.ecies_public_key, bob_contact.ecies_public_key);
+
+        // pairing against the restored contact material yields the same key
+        let (alice_request, alice_secrets) = pairing_request_message([6u8; 32], &restored).unwrap();
+        let alice_shared_key = finish_pairing_requestor(&alice_secrets, &restored, None).unwrap();
+        let bob_shared_key = finish_pairing_contactor(&bob_secrets, &alice_request, None).unwrap();
+        assert_eq!(alice_shared_key, bob_shared_key);
+
+        // a requestor holds no decapsulation key to reconstruct from
+        assert!(matches!(
+            reconstruct_contact_material(&alice_secrets),
+            Err(DerecPairingError::PairingStateError)
+        ));
+
+        let (x_contact, x_secrets) =
+            contact_message_with::<pairing_mlkem::MlKem, pairing_x25519::X25519>([7u8; 32]).unwrap();
+        let x_restored =
+            reconstruct_contact_material_with::<pairing_mlkem::MlKem, pairing_x25519::X25519>(&x_secrets).unwrap();
+        assert_eq!(x_restored.ecies_public_key, x_contact.ecies_public_key);
+    }
+
+    #[test]
+    fn test_rekey_pairing_agrees_on_a_fresh_key() {
+        let (bob_contact, bob_secrets) = contact_message([3u8; 32]).unwrap();
+        let (alice_request, alice_secrets) = pairing_request_message([4u8; 32], &bob_contact).unwrap();
+        let original = finish_pairing_contactor(&bob_secrets, &alice_request, None).unwrap();
+        assert_eq!(finish_pairing_requestor(&alice_secrets, &bob_contact, None).unwrap(), original);
+
+        // only the ephemeral public keys cross the wire
+        let (bob_sk, bob_pk) = rekey_ephemeral([5u8; 32]).unwrap();
+        let (alice_sk, alice_pk) = rekey_ephemeral([6u8; 32]).unwrap();
+        let bob_key = rekey_pairing(&original, &bob_sk, &bob_pk, &alice_pk).unwrap();
+        let alice_key = rekey_pairing(&original, &alice_sk, &bob_pk, &alice_pk).unwrap();
+        assert_eq!(bob_key, alice_key);
+        assert_ne!(bob_key, original);
+
+        // the new key depends on the current one, and a foreign ephemeral key is refused
+        assert_ne!(rekey_pairing(&[0u8; 32], &bob_sk, &bob_pk, &alice_pk).unwrap(), bob_key);
+        let (eve_sk, _) = rekey_ephemeral([7u8; 32]).unwrap();
+        assert!(matches!(rekey_pairing(&original, &eve_sk, &bob_pk, &alice_pk), Err(DerecPairingError::PairingStateError)));
+    }
+
+    #[test]
+    fn test_decapsulation_limiter_caps_attempts_per_key() {
+        let limiter = DecapsulationLimiter::new(2);
+        let (bob_contact, bob_secrets) = contact_message([7u8; 32]).unwrap();
+        let (alice_request, _) = pairing_request_message([8u8; 32], &bob_contact).unwrap();
+
+        // a crafted ciphertext still counts as an attempt
+        let mut crafted = PairingRequestMessageMaterial {
+            mlkem_ciphertext: alice_request.mlkem_ciphertext.clone(),
+            ecies_public_key: alice_request.ecies_public_key.clone(),
+        };
+        crafted.mlkem_ciphertext[0] ^= 1;
+        assert!(finish_pairing_contactor_with_limiter::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(&bob_secrets, &crafted, None, &limiter).is_ok());
+        assert!(finish_pairing_contactor_with_limiter::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(&bob_secrets, &alice_request, None, &limiter).is_ok());
+        assert_eq!(limiter.attempts(bob_secrets.mlkem_decapsulation_key.as_ref().unwrap()), 2);
+
+        assert!(matches!(
+            finish_pairing_contactor_with_limiter::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(&bob_secrets, &alice_request, None, &limiter),
+            Err(DerecPairingError::RateLimited)
+        ));
+
+        // other keys have their own budget
+        let (carol_contact, carol_secrets) = contact_message([9u8; 32]).unwrap();
+        let (carol_request, _) = pairing_request_message([10u8; 32], &carol_contact).unwrap();
+        assert!(finish_pairing_contactor_with_limiter::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(&carol_secrets, &carol_request, None, &limiter).is_ok());
+    }
+
+    #[test]
+    fn test_decapsulation_limiter_evicts_the_least_recent_key() {
+        let limiter = DecapsulationLimiter::with_capacity(1, 2);
+        let keys: Vec<_> = (1u8..=3).map(|seed| contact_message([seed; 32]).unwrap()).collect();
+        let requests: Vec<_> = keys.iter()
+            .map(|(contact, _)| pairing_request_message([42u8; 32], contact).unwrap().0)
+            .collect();
+        let finish = |i: usize| {
+            finish_pairing_contactor_with_limiter::<pairing_mlkem::MlKem, pairing_ecies::Secp256k1Ecies>(&keys[i].1, &requests[i], None, &limiter)
+        };
+        let dk = |i: usize| keys[i].1.mlkem_decapsulation_key.clone().unwrap();
+
+        assert!(finish(0).is_ok());
+        assert!(finish(1).is_ok());
+        // touching the first key makes the second the least recently used
+        assert!(matches!(finish(0), Err(DerecPairingError::RateLimited)));
+        assert!(finish(2).is_ok());
+
+        assert_eq!(limiter.attempts(&dk(0)), 1);
+        assert_eq!(limiter.attempts(&dk(1)), 0);
+        assert_eq!(limiter.attempts(&dk(2)), 1);
+    }
+
+    #[test]
+    fn test_resume_shared_key_after_persisting() {
+        fn persist<T: CanonicalSerialize + CanonicalDeserialize>(value: &T) -> T {
+            let mut bytes = Vec::new();
+            value.serialize_compressed(&mut bytes).unwrap();
+            T::deserialize_compressed(bytes.as_slice()).unwrap()
+        }
+
+        let context = Some(&b"test deployment"[..]);
+        let (contact, contactor_secrets) = contact_message(rand::random()).unwrap();
+        let (request, requestor_secrets) = pairing_request_message(rand::random(), &contact).unwrap();
+        let contactor_key = finish_pairing_contactor(&contactor_secrets, &request, context).unwrap();
+        let requestor_key = finish_pairing_requestor(&requestor_secrets, &contact, context).unwrap();
+
+        let (contactor_secrets, request) = (persist(&contactor_secrets), persist(&request));
+        let (requestor_secrets, contact) = (persist(&requestor_secrets), persist(&contact));
+        assert_eq!(
+            resume_shared_key(&contactor_secrets, PeerPublicMaterial::Request(&request), context).unwrap(),
+            contactor_key
+        );
+        assert_eq!(
+            resume_shared_key(&requestor_secrets, PeerPublicMaterial::Contact(&contact), context).unwrap(),
+            requestor_key
+        );
+
+        // secrets and peer material of mismatched roles are refused
+        assert!(matches!(
+            resume_shared_key(&requestor_secrets, PeerPublicMaterial::Request(&request), context),
+            Err(DerecPairingError::PairingStateError)
+        ));
+        assert!(matches!(
+            resume_shared_key(&contactor_secrets, PeerPublicMaterial::Contact(&contact), context),
+            Err(DerecPairingError::PairingStateError)
+        ));
+    }
+
+    #[test]
+    fn test_compressed_secret_key_material_regenerates_keys() {
+        let (_, contactor_secrets) = contact_message([8u8; 32]).unwrap();
+
+        let mut uncompressed = Vec::new();
+        contactor_secrets.serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut compressed = Vec::new();
+        contactor_secrets.serialize_compressed(&mut compressed).unwrap();
+        assert_eq!(compressed.len(), contactor_secrets.compressed_size());
+        assert!(compressed.len() < uncompressed.len() / 10);
+
+        let restored = PairingSecretKeyMaterial::deserialize_compressed(&mut &compressed[..]).unwrap();
+        assert_eq!(restored.mlkem_decapsulation_key, contactor_secrets.mlkem_decapsulation_key);
+        assert_eq!(restored.mlkem_shared_secret, contactor_secrets.mlkem_shared_secret);
+        assert_eq!(restored.ecies_secret_key, contactor_secrets.ecies_secret_key);
+        assert_eq!(restored.mlkem_seed, contactor_secrets.mlkem_seed);
+
+        // the uncompressed form drops the seed but keeps the full key
+        let restored = PairingSecretKeyMaterial::deserialize_uncompressed(&mut &uncompressed[..]).unwrap();
+        assert_eq!(restored.mlkem_decapsulation_key, contactor_secrets.mlkem_decapsulation_key);
+        assert_eq!(restored.mlkem_seed, None);
+
+        // without a seed, the compressed form falls back to the full key
+        let mut fallback = Vec::new();
+        restored.serialize_compressed(&mut fallback).unwrap();
+        let restored = PairingSecretKeyMaterial::deserialize_compressed(&mut &fallback[..]).unwrap();
+        assert_eq!(restored.mlkem_decapsulation_key, contactor_secrets.mlkem_decapsulation_key);
+
+        let (contact, _) = contact_message([9u8; 32]).unwrap();
+        let (_, requestor_secrets) = pairing_request_message([10u8; 32], &contact).unwrap();
+        let mut compressed = Vec::new();
+        requestor_secrets.serialize_compressed(&mut compressed).unwrap();
+        let restored = PairingSecretKeyMaterial::deserialize_compressed(&mut &compressed[..]).unwrap();
+        assert_eq!(restored.mlkem_decapsulation_key, None);
+        assert_eq!(restored.mlkem_shared_secret, requestor_secrets.mlkem_shared_secret);
+        assert_eq!(restored.ecies_secret_key, requestor_secrets.ecies_secret_key);
+    }
 }
@@ -5,39 +5,105 @@
 
 use ark_ec::*;
 use ark_ff::*;
-use rand::Rng;
+use rand::{CryptoRng, Rng};
 use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
 use sha2::*;
 
-use super::DerecPairingError;
+use rand_core::CryptoRngCore;
+
+use super::{DerecPairingError, KeyExchange};
 
 /// Generates a new secp256k1 keypair for use with ECIES.
 ///
 /// # Arguments
 ///
-/// * `rng` - A mutable reference to a random number generator implementing the `Rng` trait.
+/// * `rng` - A mutable reference to a cryptographically secure random number generator
+///   implementing the `Rng` and `CryptoRng` traits.
 ///
 /// # Returns a `Result` containing, on success, the following tuple:
 /// - The secret key as a vector of bytes (uncompressed serialization).
 /// - The public key as a vector of bytes (uncompressed serialization).
 ///
-pub fn generate_key<R: Rng>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+pub fn generate_key<R: Rng + CryptoRng>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
     let sk = ark_secp256k1::Fr::rand(rng);
     let pk = ark_secp256k1::Affine::generator() * sk;
 
     let mut sk_bytes = Vec::new();
     sk
         .serialize_uncompressed(&mut sk_bytes)
-        .map_err(|err| DerecPairingError::SerializationError(err))?;
+        .map_err(DerecPairingError::SerializationError)?;
 
     let mut pk_bytes = Vec::new();
     pk
         .serialize_uncompressed(&mut pk_bytes)
-        .map_err(|err| DerecPairingError::SerializationError(err))?;
+        .map_err(DerecPairingError::SerializationError)?;
 
     Ok((sk_bytes, pk_bytes))
 }
 
+/// Checks that a serialized secp256k1 public key belongs to a serialized secret key, i.e. `pk == g·sk`.
+///
+/// # Arguments
+///
+/// * `sk` - A byte slice containing the secret key in uncompressed serialization format.
+/// * `pk` - A byte slice containing the public key in uncompressed serialization format.
+///
+/// # Returns a `Result` which is `Ok(())` if the keys match, or `DerecPairingError::KeyGenSelfTestFailed`
+/// if they do not (or a `DerecPairingError::SerializationError` if `sk` does not decode).
+///
+pub fn verify_keypair(sk: &[u8], pk: &[u8]) -> Result<(), DerecPairingError> {
+    if derive_public_key(sk)? != pk {
+        return Err(DerecPairingError::KeyGenSelfTestFailed);
+    }
+
+    Ok(())
+}
+
+/// Re-derives the public key belonging to a serialized secp256k1 secret key.
+///
+/// # Arguments
+///
+/// * `sk` - A byte slice containing the secret key in uncompressed serialization format.
+///
+/// # Returns a `Result` containing, on success, the public key as a vector of bytes
+/// (uncompressed serialization), exactly as returned by `generate_key`.
+///
+pub fn derive_public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+    let sk = ark_secp256k1::Fr::deserialize_uncompressed(sk)
+        .map_err(DerecPairingError::SerializationError)?;
+    let pk = ark_secp256k1::Affine::generator() * sk;
+
+    let mut pk_bytes = Vec::new();
+    pk
+        .serialize_uncompressed(&mut pk_bytes)
+        .map_err(DerecPairingError::SerializationError)?;
+
+    Ok(pk_bytes)
+}
+
+/// Checks that a serialized secp256k1 public key is usable for ECDH.
+///
+/// The key must deserialize from its uncompressed encoding to a point on the curve
+/// (and in the prime-order subgroup), and must not be the point at infinity.
+///
+/// # Arguments
+///
+/// * `pk` - A byte slice containing the public key in uncompressed serialization format.
+///
+/// # Returns a `Result` which is `Ok(())` if the key is valid, or a `DerecPairingError`
+/// if the bytes do not decode to a curve point or decode to the identity.
+///
+pub fn validate_public_key(pk: &[u8]) -> Result<(), DerecPairingError> {
+    let pk = ark_secp256k1::Affine::deserialize_uncompressed(pk)
+        .map_err(DerecPairingError::SerializationError)?;
+
+    if pk.is_zero() {
+        return Err(DerecPairingError::InvalidPublicKey);
+    }
+
+    Ok(())
+}
+
 /// Derives a shared secret key using Elliptic Curve Diffie-Hellman (ECDH) over secp256k1.
 ///
 /// This function computes a shared secret by multiplying the provided secret key (`sk`)
@@ -55,18 +121,39 @@ pub fn generate_key<R: Rng>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPair
 ///
 pub fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<[u8; 32], DerecPairingError> {
     let sk = ark_secp256k1::Fr::deserialize_uncompressed(sk)
-        .map_err(|err| DerecPairingError::SerializationError(err))?;
+        .map_err(DerecPairingError::SerializationError)?;
     let pk = ark_secp256k1::Affine::deserialize_uncompressed(pk)
-        .map_err(|err| DerecPairingError::SerializationError(err))?;
+        .map_err(DerecPairingError::SerializationError)?;
 
     let shared_key = pk * sk;
 
     let mut shared_key_bytes = Vec::new();
     shared_key
         .serialize_uncompressed(&mut shared_key_bytes)
-        .map_err(|err| DerecPairingError::SerializationError(err))?;
+        .map_err(DerecPairingError::SerializationError)?;
 
     let mut hasher = sha2::Sha256::new();
     hasher.update(shared_key_bytes);
     Ok(hasher.finalize().into())
 }
+
+/// ECDH over secp256k1 as a `KeyExchange`, the key exchange used by default in the pairing protocol.
+pub struct Secp256k1Ecies;
+
+impl KeyExchange for Secp256k1Ecies {
+    fn generate_key<R: CryptoRngCore>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+        generate_key(rng)
+    }
+
+    fn public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        derive_public_key(sk)
+    }
+
+    fn validate_public_key(pk: &[u8]) -> Result<(), DerecPairingError> {
+        validate_public_key(pk)
+    }
+
+    fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        derive_shared_key(sk, pk).map(|shared_key| shared_key.to_vec())
+    }
+}
@@ -5,7 +5,7 @@ use ml_kem::array::ArrayN;
 use ml_kem::{kem, EncodedSizeUser, KemCore, MlKem768, MlKem768Params};
 use rand_core::CryptoRngCore;
 
-use super::DerecPairingError;
+use super::{DerecPairingError, Kem};
 
 type MlKem768DecapsulationKey = kem::DecapsulationKey<MlKem768Params>;
 type MlKem768EncapsulationKey = kem::EncapsulationKey<MlKem768Params>;
@@ -17,6 +17,9 @@ pub const DECAPSULATION_KEY_SIZE: usize = 2400;
 /// Size in bytes of the `Ciphertext`.
 pub const CIPHERTEXT_SIZE: usize = 1088;
 
+/// Size in bytes of the seed (`d || z`) a key pair is deterministically generated from.
+pub const SEED_SIZE: usize = 64;
+
 /// Shared secret key.
 pub type SharedSecret = [u8; 32];
 
@@ -33,11 +36,36 @@ pub type SharedSecret = [u8; 32];
 /// - The encapsulation key as a `Vec<u8>`.
 ///
 pub fn generate_encapsulation_key<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>) {
-    // Generate a (decapsulation key, encapsulation key) pair
-    let (dk, ek) = MlKem768::generate(rng);
-    let ek_bytes = ek.as_bytes();
-    let dk_bytes = dk.as_bytes();
-    (dk_bytes.to_vec(), ek_bytes.to_vec())
+    let (_, dk, ek) = generate_encapsulation_key_with_seed(rng);
+    (dk, ek)
+}
+
+/// Generates a new ML-KEM-768 key pair like `generate_encapsulation_key`, also returning the seed it was
+/// derived from.
+///
+/// The seed is drawn from `rng` exactly as `MlKem768::generate` draws it, so for the same `rng` state both
+/// functions produce the same key pair. Keeping the 64-byte seed is enough to regenerate the 2400-byte
+/// decapsulation key with `generate_encapsulation_key_from_seed`.
+///
+/// # Returns
+///
+/// A tuple containing the seed, the decapsulation key and the encapsulation key.
+///
+pub fn generate_encapsulation_key_with_seed<R: CryptoRngCore>(rng: &mut R) -> ([u8; SEED_SIZE], Vec<u8>, Vec<u8>) {
+    let mut seed = [0u8; SEED_SIZE];
+    // draw d, then z, matching the order of `MlKem768::generate`
+    rng.fill_bytes(&mut seed[..32]);
+    rng.fill_bytes(&mut seed[32..]);
+    let (dk, ek) = generate_encapsulation_key_from_seed(&seed);
+    (seed, dk, ek)
+}
+
+/// Regenerates the ML-KEM-768 key pair derived from `seed`, returned as (decapsulation key, encapsulation key).
+pub fn generate_encapsulation_key_from_seed(seed: &[u8; SEED_SIZE]) -> (Vec<u8>, Vec<u8>) {
+    let d = ArrayN::<u8, 32>::try_from(&seed[..32]).expect("seed half is 32 bytes");
+    let z = ArrayN::<u8, 32>::try_from(&seed[32..]).expect("seed half is 32 bytes");
+    let (dk, ek) = MlKem768::generate_deterministic(&d, &z);
+    (dk.as_bytes().to_vec(), ek.as_bytes().to_vec())
 }
 
 /// Performs ML-KEM-768 key encapsulation using the provided encapsulation key.
@@ -63,7 +91,7 @@ pub fn encapsulate<R: CryptoRngCore>(
 ) -> Result<(Vec<u8>, SharedSecret), DerecPairingError> {
     let ek = MlKem768EncapsulationKey::from_bytes(
         &as_array::<ENCAPSULATION_KEY_SIZE>(ek_encoded)
-            .unwrap()
+            .ok_or(DerecPairingError::MLKemEncapsulationError)?
             .into()
     );
 
@@ -94,19 +122,74 @@ pub fn decapsulate(
     ctxt: impl AsRef<[u8]>
 ) -> Result<SharedSecret, DerecPairingError> {
     let dk = MlKem768DecapsulationKey::from_bytes(
-        &as_array::<DECAPSULATION_KEY_SIZE>(dk_encoded).unwrap().into()
+        &as_array::<DECAPSULATION_KEY_SIZE>(dk_encoded)
+            .ok_or(DerecPairingError::MLKemDecapsulationError)?
+            .into()
     );
 
+    let ctxt = ArrayN::<u8, CIPHERTEXT_SIZE>::try_from(ctxt.as_ref())
+        .map_err(|_| DerecPairingError::MLKemDecapsulationError)?;
     let k_recv = dk
-        .decapsulate(&ArrayN::<u8, CIPHERTEXT_SIZE>::try_from(ctxt.as_ref()).unwrap())
+        .decapsulate(&ctxt)
         .map_err(|_| DerecPairingError::MLKemDecapsulationError)?;
 
     Ok(k_recv.0)
 }
 
+/// Recovers the encoded encapsulation key from an encoded decapsulation key.
+///
+/// An ML-KEM decapsulation key embeds its encapsulation key, so the public half of a
+/// key pair can always be re-derived from the secret half.
+///
+/// # Arguments
+///
+/// * `dk_encoded` - The encoded decapsulation key.
+///
+/// # Returns
+///
+/// The encapsulation key as a `Vec<u8>`, or `MLKemDecapsulationError` if `dk_encoded`
+/// is not a decapsulation key.
+///
+pub fn derive_encapsulation_key(dk_encoded: impl AsRef<[u8]>) -> Result<Vec<u8>, DerecPairingError> {
+    let dk = MlKem768DecapsulationKey::from_bytes(
+        &as_array::<DECAPSULATION_KEY_SIZE>(dk_encoded)
+            .ok_or(DerecPairingError::MLKemDecapsulationError)?
+            .into()
+    );
+
+    Ok(dk.encapsulation_key().as_bytes().to_vec())
+}
+
+/// ML-KEM-768, the KEM used by default in the pairing protocol.
+pub struct MlKem;
+
+impl Kem for MlKem {
+    fn generate<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+        generate_encapsulation_key(rng)
+    }
+
+    fn generate_with_seed<R: CryptoRngCore>(rng: &mut R) -> (Vec<u8>, Vec<u8>, Option<Vec<u8>>) {
+        let (seed, dk, ek) = generate_encapsulation_key_with_seed(rng);
+        (dk, ek, Some(seed.to_vec()))
+    }
+
+    fn encapsulate<R: CryptoRngCore>(ek: &[u8], rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+        let (ct, shared_secret) = encapsulate(ek, rng)?;
+        Ok((ct, shared_secret.to_vec()))
+    }
+
+    fn decapsulate(dk: &[u8], ctxt: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        decapsulate(dk, ctxt).map(|shared_secret| shared_secret.to_vec())
+    }
+
+    fn encapsulation_key(dk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        derive_encapsulation_key(dk)
+    }
+}
+
 fn as_array<const N: usize>(input: impl AsRef<[u8]>) -> Option<[u8; N]> {
     if input.as_ref().len() != N {
-        return None;
+        None
     } else {
         let mut array = [0u8; N];
         array.copy_from_slice(input.as_ref());
@@ -126,4 +209,17 @@ mod tests {
         let k_recv = decapsulate(&dk, &ct).unwrap();
         assert_eq!(k_send, k_recv);
     }
+
+    #[test]
+    fn test_seed_regenerates_key_pair() {
+        use rand_chacha::rand_core::SeedableRng;
+
+        // the seeded path draws its seed exactly as the library's own keygen does
+        let (dk, ek) = MlKem768::generate(&mut rand_chacha::ChaCha8Rng::from_seed([7u8; 32]));
+        let (dk, ek) = (dk.as_bytes().to_vec(), ek.as_bytes().to_vec());
+        let (seed, seeded_dk, seeded_ek) =
+            generate_encapsulation_key_with_seed(&mut rand_chacha::ChaCha8Rng::from_seed([7u8; 32]));
+        assert_eq!((&dk, &ek), (&seeded_dk, &seeded_ek));
+        assert_eq!(generate_encapsulation_key_from_seed(&seed), (dk, ek));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides X25519 Diffie-Hellman key establishment, an alternative
+//! to the secp256k1 ECIES primitives in `pairing_ecies`.
+
+use rand_core::CryptoRngCore;
+use sha2::*;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use super::{DerecPairingError, KeyExchange};
+
+/// Size in bytes of an X25519 secret or public key.
+pub const KEY_SIZE: usize = 32;
+
+/// X25519 Diffie-Hellman as a `KeyExchange` for the pairing protocol.
+pub struct X25519;
+
+impl KeyExchange for X25519 {
+    fn generate_key<R: CryptoRngCore>(rng: &mut R) -> Result<(Vec<u8>, Vec<u8>), DerecPairingError> {
+        let sk = StaticSecret::random_from_rng(rng);
+        let pk = PublicKey::from(&sk);
+
+        Ok((sk.to_bytes().to_vec(), pk.to_bytes().to_vec()))
+    }
+
+    fn public_key(sk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        let sk: [u8; KEY_SIZE] = sk.try_into().map_err(|_| DerecPairingError::PairingStateError)?;
+        Ok(PublicKey::from(&StaticSecret::from(sk)).as_bytes().to_vec())
+    }
+
+    fn validate_public_key(pk: &[u8]) -> Result<(), DerecPairingError> {
+        if pk.len() != KEY_SIZE {
+            return Err(DerecPairingError::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
+
+    fn derive_shared_key(sk: &[u8], pk: &[u8]) -> Result<Vec<u8>, DerecPairingError> {
+        let sk: [u8; KEY_SIZE] = sk.try_into().map_err(|_| DerecPairingError::PairingStateError)?;
+        let pk: [u8; KEY_SIZE] = pk.try_into().map_err(|_| DerecPairingError::InvalidPublicKey)?;
+
+        let shared_key = StaticSecret::from(sk).diffie_hellman(&PublicKey::from(pk));
+
+        // a low-order public key forces an all-zero shared point
+        if !shared_key.was_contributory() {
+            return Err(DerecPairingError::InvalidPublicKey);
+        }
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(shared_key.as_bytes());
+        Ok(hasher.finalize().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_shared_key() {
+        let mut rng = rand::thread_rng();
+        let (alice_sk, alice_pk) = X25519::generate_key(&mut rng).unwrap();
+        let (bob_sk, bob_pk) = X25519::generate_key(&mut rng).unwrap();
+
+        assert_eq!(
+            X25519::derive_shared_key(&alice_sk, &bob_pk).unwrap(),
+            X25519::derive_shared_key(&bob_sk, &alice_pk).unwrap()
+        );
+
+        // the identity (all-zero) public key is rejected
+        assert!(X25519::derive_shared_key(&alice_sk, &[0u8; KEY_SIZE]).is_err());
+    }
+}
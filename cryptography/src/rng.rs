@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! The bound on the random number generators accepted by the `_with_rng` variants of the
+//! channel and sharing functions.
+//!
+//! The default variants draw from the OS CSPRNG. Passing an explicit generator instead, like
+//! the crypto layer's `R: Rng + CryptoRng` parameters, lets tests inject a seeded generator such
+//! as `rand_chacha::ChaCha8Rng` and obtain reproducible outputs.
+
+use rand_core::{CryptoRng, RngCore};
+
+/// A cryptographically secure random number generator.
+///
+/// Implemented for every `RngCore + CryptoRng`, e.g. `rand::rngs::OsRng` or a seeded `ChaCha20Rng`.
+pub trait SecureRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng> SecureRng for R {}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sanity checks on the 32-byte seeds callers pass to the pairing and sharing functions.
+//!
+//! The seeds must come from a CSPRNG; nothing can tell a good seed from a bad one in general,
+//! but some seeds are certainly bad. A seed whose bytes are all equal (such as `[0u8; 32]`,
+//! the placeholder of many examples) almost always means the caller forgot to fill it, so the
+//! functions taking seeds reject it through [`require_nonzero_seed`].
+//!
+//! The crate's own unit tests use such fixed seeds for reproducibility, so the guard is not
+//! applied to them.
+
+/// Returns whether `seed` is obviously not random: empty, or a single byte value repeated.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::seed::is_weak_seed;
+/// assert!(is_weak_seed(&[0u8; 32]));
+/// assert!(is_weak_seed(&[7u8; 32]));
+/// assert!(!is_weak_seed(b"an example seed of 32 bytes long"));
+/// ```
+pub fn is_weak_seed(seed: &[u8]) -> bool {
+    seed.iter().all(|b| Some(b) == seed.first())
+}
+
+/// The error of [`require_nonzero_seed`]; converts into the pairing and sharing errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeakSeedError;
+
+/// Rejects a [weak](is_weak_seed) seed.
+///
+/// Unit tests of this crate bypass the check, so that they can keep their fixed seeds.
+///
+/// # Errors
+///
+/// Returns `WeakSeedError` if `seed` is weak.
+pub fn require_nonzero_seed(seed: &[u8; 32]) -> Result<(), WeakSeedError> {
+    if !cfg!(test) && is_weak_seed(seed) {
+        return Err(WeakSeedError);
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module enumerates the combinations of primitives ("cipher suites") this crate supports,
+//! so that two implementations can agree on one before pairing.
+//!
+//! Each suite has a stable 16-bit identifier. A party advertises the identifiers it supports
+//! (e.g. in its contact message), and its peer picks the highest identifier both support with
+//! [`negotiate`].
+
+/// Post-quantum key encapsulation mechanism used during pairing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KemAlgorithm {
+    MlKem768,
+}
+
+/// Classical key exchange combined with the KEM during pairing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KexAlgorithm {
+    Secp256k1,
+    X25519,
+}
+
+/// Authenticated cipher protecting channel messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelCipher {
+    Aes256Gcm,
+}
+
+/// Hash function used for share commitments or share verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+/// A combination of primitives two parties must agree on to interoperate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CipherSuite {
+    /// stable identifier of the suite; never reused for a different combination
+    pub id: u16,
+    /// KEM used during pairing
+    pub kem: KemAlgorithm,
+    /// classical key exchange used during pairing
+    pub kex: KexAlgorithm,
+    /// cipher protecting channel messages
+    pub channel_cipher: ChannelCipher,
+    /// hash of the Merkle commitment over shares
+    pub merkle_hash: HashAlgorithm,
+    /// hash of the share verification response
+    pub verify_hash: HashAlgorithm,
+}
+
+/// ML-KEM-768 with secp256k1 ECDH, the suite used by default.
+pub const SUITE_MLKEM768_SECP256K1: CipherSuite = CipherSuite {
+    id: 1,
+    kem: KemAlgorithm::MlKem768,
+    kex: KexAlgorithm::Secp256k1,
+    channel_cipher: ChannelCipher::Aes256Gcm,
+    merkle_hash: HashAlgorithm::Sha256,
+    verify_hash: HashAlgorithm::Sha384,
+};
+
+/// ML-KEM-768 with X25519 Diffie-Hellman.
+pub const SUITE_MLKEM768_X25519: CipherSuite = CipherSuite {
+    id: 2,
+    kem: KemAlgorithm::MlKem768,
+    kex: KexAlgorithm::X25519,
+    channel_cipher: ChannelCipher::Aes256Gcm,
+    merkle_hash: HashAlgorithm::Sha256,
+    verify_hash: HashAlgorithm::Sha384,
+};
+
+/// All suites this crate implements, in increasing order of identifier.
+pub const SUPPORTED_SUITES: &[CipherSuite] = &[SUITE_MLKEM768_SECP256K1, SUITE_MLKEM768_X25519];
+
+impl CipherSuite {
+    /// Looks up a supported suite by its identifier.
+    pub fn from_id(id: u16) -> Option<CipherSuite> {
+        SUPPORTED_SUITES.iter().find(|suite| suite.id == id).copied()
+    }
+}
+
+/// Chooses the suite to use with a peer: the local suite with the highest identifier that the
+/// peer also advertises.
+///
+/// # Arguments
+///
+/// * `local` - The suites this party is willing to use.
+/// * `remote` - The suite identifiers advertised by the peer.
+///
+/// # Returns
+///
+/// The chosen suite, or `None` if the two parties have no suite in common.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::suite::{negotiate, SUPPORTED_SUITES, SUITE_MLKEM768_SECP256K1};
+/// assert_eq!(negotiate(SUPPORTED_SUITES, &[1, 99]), Some(SUITE_MLKEM768_SECP256K1));
+/// assert_eq!(negotiate(SUPPORTED_SUITES, &[99]), None);
+/// ```
+pub fn negotiate(local: &[CipherSuite], remote: &[u16]) -> Option<CipherSuite> {
+    local
+        .iter()
+        .filter(|suite| remote.contains(&suite.id))
+        .max_by_key(|suite| suite.id)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        // both sides support both suites: the highest one wins
+        assert_eq!(negotiate(SUPPORTED_SUITES, &[2, 1]), Some(SUITE_MLKEM768_X25519));
+
+        // only the overlap counts, whatever the peer lists besides
+        assert_eq!(negotiate(SUPPORTED_SUITES, &[1, 7]), Some(SUITE_MLKEM768_SECP256K1));
+        assert_eq!(negotiate(&[SUITE_MLKEM768_SECP256K1], &[1, 2]), Some(SUITE_MLKEM768_SECP256K1));
+
+        // no overlap
+        assert_eq!(negotiate(&[SUITE_MLKEM768_SECP256K1], &[2]), None);
+        assert_eq!(negotiate(SUPPORTED_SUITES, &[]), None);
+    }
+
+    #[test]
+    fn test_suite_ids_are_unique() {
+        for suite in SUPPORTED_SUITES {
+            assert_eq!(CipherSuite::from_id(suite.id), Some(*suite));
+        }
+        assert_eq!(CipherSuite::from_id(0), None);
+    }
+}
@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reed-Solomon style erasure coding over GF(2^8), used to spread a sharing's ciphertext
+//! across helpers instead of replicating it to each of them.
+//!
+//! The data is split into `k` equal stripes (zero-padded), which are taken as the coefficients
+//! of a polynomial of degree `k - 1` at every byte position. Fragment `i` holds that polynomial's
+//! evaluations at the point `i + 1`, so any `k` distinct fragments determine the stripes.
+//!
+//! Each fragment is self-describing: it starts with a header of its index, `k`, and the length of
+//! the original data, followed by its stripe of evaluations. The code recovers from lost fragments,
+//! not from altered ones; callers must authenticate the decoded data (e.g. with the AEAD tag of the
+//! ciphertext it carries).
+
+use super::DerecVSSError;
+
+// fragment index (1 byte), k (1 byte), data length (4 bytes, big endian)
+const HEADER_SIZE: usize = 6;
+
+// the irreducible polynomial x^8 + x^4 + x^3 + x^2 + 1 defining GF(2^8)
+const POLYNOMIAL: u16 = 0x11d;
+
+// logarithm and exponentiation tables of GF(2^8) with respect to the generator 2
+const TABLES: ([u8; 256], [u8; 512]) = build_tables();
+
+const fn build_tables() -> ([u8; 256], [u8; 512]) {
+    let mut log = [0u8; 256];
+    let mut exp = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLYNOMIAL;
+        }
+        i += 1;
+    }
+    // doubling the exponent table saves a reduction modulo 255 in `mul`
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    (log, exp)
+}
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (log, exp) = &TABLES;
+    exp[log[a as usize] as usize + log[b as usize] as usize]
+}
+
+fn inv(a: u8) -> u8 {
+    let (log, exp) = &TABLES;
+    exp[255 - log[a as usize] as usize]
+}
+
+// the point fragment `index` is evaluated at; never zero
+fn point(index: u8) -> u8 {
+    index + 1
+}
+
+/// Splits `data` into `n` fragments, any `k` of which reconstruct it with [`erasure_decode`].
+///
+/// Returns `DerecVSSError::InvalidAccessStructure` unless `1 <= k <= n <= 255`, or
+/// `DerecVSSError::SecretTooLarge` if `data` is longer than `u32::MAX` bytes.
+pub fn erasure_encode(data: &[u8], k: usize, n: usize) -> Result<Vec<Vec<u8>>, DerecVSSError> {
+    if k == 0 || k > n || n > 255 {
+        return Err(DerecVSSError::InvalidAccessStructure);
+    }
+    let len = u32::try_from(data.len()).map_err(|_| DerecVSSError::SecretTooLarge { max: u32::MAX as usize })?;
+
+    let stripe_len = data.len().div_ceil(k);
+    let stripe = |m: usize, j: usize| data.get(m * stripe_len + j).copied().unwrap_or(0);
+
+    Ok((0..n as u8)
+        .map(|index| {
+            let mut fragment = Vec::with_capacity(HEADER_SIZE + stripe_len);
+            fragment.push(index);
+            fragment.push(k as u8);
+            fragment.extend_from_slice(&len.to_be_bytes());
+
+            // evaluate every byte position's polynomial at this fragment's point (Horner's rule)
+            let x = point(index);
+            fragment.extend((0..stripe_len).map(|j| {
+                (0..k).rev().fold(0u8, |acc, m| mul(acc, x) ^ stripe(m, j))
+            }));
+            fragment
+        })
+        .collect())
+}
+
+/// Reconstructs the data split by [`erasure_encode`] from any `k` of its fragments.
+///
+/// Fragments may be given in any order, and repeated fragments are ignored.
+///
+/// Returns `DerecVSSError::MalformedShare` if a fragment's header is truncated or disagrees with
+/// the others, or `DerecVSSError::InsufficientShares` if fewer than `k` distinct fragments are given.
+pub fn erasure_decode(fragments: &[impl AsRef<[u8]>]) -> Result<Vec<u8>, DerecVSSError> {
+    let first = fragments.first().ok_or(DerecVSSError::InsufficientShares)?.as_ref();
+    if first.len() < HEADER_SIZE || first[1] == 0 {
+        return Err(DerecVSSError::MalformedShare);
+    }
+    let (k, header) = (first[1] as usize, &first[1..HEADER_SIZE]);
+    let len = u32::from_be_bytes(first[2..HEADER_SIZE].try_into().unwrap()) as usize;
+    let stripe_len = len.div_ceil(k);
+
+    // keep the first fragment seen for each index, until k are found
+    let mut rows: Vec<(u8, &[u8])> = Vec::with_capacity(k);
+    for fragment in fragments {
+        let fragment = fragment.as_ref();
+        if fragment.len() != HEADER_SIZE + stripe_len || &fragment[1..HEADER_SIZE] != header || fragment[0] == 255 {
+            return Err(DerecVSSError::MalformedShare);
+        }
+        if rows.len() < k && !rows.iter().any(|(index, _)| *index == fragment[0]) {
+            rows.push((fragment[0], &fragment[HEADER_SIZE..]));
+        }
+    }
+    if rows.len() < k {
+        return Err(DerecVSSError::InsufficientShares);
+    }
+
+    // invert the Vandermonde matrix of the fragments' points (Gauss-Jordan elimination);
+    // distinct non-zero points make it invertible
+    let mut matrix: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|(index, _)| {
+            let x = point(*index);
+            (0..k).scan(1u8, |power, _| {
+                let current = *power;
+                *power = mul(*power, x);
+                Some(current)
+            }).collect()
+        })
+        .collect();
+    let mut inverse: Vec<Vec<u8>> = (0..k).map(|r| (0..k).map(|c| (r == c) as u8).collect()).collect();
+
+    for col in 0..k {
+        let pivot = (col..k).find(|r| matrix[*r][col] != 0).ok_or(DerecVSSError::MalformedShare)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = inv(matrix[col][col]);
+        for c in 0..k {
+            matrix[col][c] = mul(matrix[col][c], scale);
+            inverse[col][c] = mul(inverse[col][c], scale);
+        }
+
+        for r in (0..k).filter(|r| *r != col) {
+            let factor = matrix[r][col];
+            if factor != 0 {
+                for c in 0..k {
+                    matrix[r][c] ^= mul(factor, matrix[col][c]);
+                    inverse[r][c] ^= mul(factor, inverse[col][c]);
+                }
+            }
+        }
+    }
+
+    // stripe m is row m of the inverse applied to the fragments' evaluations
+    let mut data = Vec::with_capacity(k * stripe_len);
+    for coefficients in &inverse {
+        data.extend((0..stripe_len).map(|j| {
+            coefficients
+                .iter()
+                .zip(rows.iter())
+                .fold(0u8, |acc, (c, (_, evaluations))| acc ^ mul(*c, evaluations[j]))
+        }));
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_arithmetic() {
+        for a in 1..=255u8 {
+            assert_eq!(mul(a, inv(a)), 1);
+            assert_eq!(mul(a, 1), a);
+            assert_eq!(mul(a, 0), 0);
+        }
+        // x * x^7 overflows the byte and is reduced by the field polynomial
+        assert_eq!(mul(2, 0x80), 0x1d);
+    }
+
+    #[test]
+    fn test_decode_from_any_k_fragments() {
+        let data: Vec<u8> = (0..100u8).map(|b| b.wrapping_mul(37)).collect();
+        let fragments = erasure_encode(&data, 3, 5).unwrap();
+        assert!(fragments.iter().all(|f| f.len() == HEADER_SIZE + 34));
+
+        for skip in 0..5 {
+            for other in (skip + 1)..5 {
+                let subset: Vec<_> = (0..5).filter(|i| *i != skip && *i != other).map(|i| &fragments[i]).collect();
+                assert_eq!(erasure_decode(&subset).unwrap(), data);
+            }
+        }
+
+        // repeated fragments do not make up for missing ones
+        assert!(matches!(erasure_decode(&[&fragments[0], &fragments[1], &fragments[0]]), Err(DerecVSSError::InsufficientShares)));
+
+        // fragments of another encoding do not mix
+        let other = erasure_encode(&data[..50], 3, 5).unwrap();
+        assert!(matches!(erasure_decode(&[&fragments[0], &fragments[1], &other[2]]), Err(DerecVSSError::MalformedShare)));
+
+        assert_eq!(erasure_decode(&erasure_encode(b"", 2, 3).unwrap()).unwrap(), b"");
+        assert!(matches!(erasure_encode(&data, 4, 3), Err(DerecVSSError::InvalidAccessStructure)));
+    }
+}
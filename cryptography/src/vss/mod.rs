@@ -5,12 +5,24 @@
 //! sharing algorithm, so that each share can be individually verified during reconstruction.
 
 use thiserror::Error;
+use rand::{CryptoRng, RngCore};
 use rand_chacha::rand_core::SeedableRng;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
 
 use super::channel::{encrypt_message, decrypt_message};
+use super::seed::{require_nonzero_seed, WeakSeedError};
 
 mod shamir;
 mod utils;
+mod erasure;
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "test-vectors")]
+pub use shamir::test_vectors;
+pub use utils::detect_error;
+pub use erasure::{erasure_encode, erasure_decode};
 
 #[allow(non_upper_case_globals)]
 const λ_bits: usize = 256;
@@ -18,9 +30,23 @@ const λ_bits: usize = 256;
 #[allow(non_upper_case_globals)]
 const λ: usize = λ_bits / 8;
 
-const MERKLE_TREE_DEPTH: u32 = 7;
+/// Depth of the Merkle tree committing to the shares; at most `2^MERKLE_TREE_DEPTH` shares are supported.
+pub const MERKLE_TREE_DEPTH: u32 = 7;
+
+/// Maximum number of shares (and hence helpers) a single sharing can commit to.
+pub const MAX_SHARES: u64 = 1 << MERKLE_TREE_DEPTH;
+
+/// Largest secret, in bytes, that [`share_direct`] can Shamir-share without an AES envelope.
+pub const MAX_DIRECT_SECRET_SIZE: usize = λ - 1;
+
+/// [`MAX_DIRECT_SECRET_SIZE`] in bits. Longer secrets must be shared with [`share`], whose AES envelope
+/// takes secrets of any length.
+pub const MAX_DIRECT_SECRET_BITS: usize = MAX_DIRECT_SECRET_SIZE * 8;
 
 /// Encodes a VSS share.
+///
+/// With the `serde` feature, shares implement `Serialize`/`Deserialize` (byte fields as base64)
+/// and can be converted to and from JSON with `to_json`/`from_json`.
 #[derive(Clone)]
 pub struct VSSShare {
     /// we use the x-coordinate to uniquely identify shares
@@ -29,6 +55,10 @@ pub struct VSSShare {
     pub y: Vec<u8>,
     /// AES encryption of the secret message
     pub encrypted_secret: Vec<u8>,
+    /// hash of the shared AES key (see [`key_hash`]), checked after reconstruction; empty if none
+    pub key_hash: Vec<u8>,
+    /// public data the commitment is bound to (see [`commit_associated_data`]); empty if none
+    pub associated_data: Vec<u8>,
     /// Merkle-root commitment to all shares
     pub commitment: Vec<u8>,
     /// bottom-up Merkle authentication path
@@ -51,6 +81,63 @@ pub enum DerecVSSError {
     DecryptionFailure,
     #[error("invalid access structure")]
     InvalidAccessStructure,
+    #[error("too many channels; at most {max} are supported")]
+    TooManyChannels { max: u64 },
+    #[error("too many leaves; a Merkle tree of this depth has {max}")]
+    TooManyLeaves { max: u64 },
+    #[error("Merkle tree is not a complete binary tree of the expected depth")]
+    MalformedTree,
+    #[error("no share for the requested channel")]
+    UnknownChannel,
+    #[error("no channels supplied")]
+    NoChannels,
+    #[error("a threshold below 2 lets any single helper reconstruct the secret")]
+    TrivialThreshold,
+    #[error("malformed share")]
+    MalformedShare,
+    #[error("secret too large; at most {max} bytes can be shared directly")]
+    SecretTooLarge { max: usize },
+    #[error("weak seed; seeds must be drawn from a CSPRNG")]
+    WeakSeed,
+    #[error("reconstructed key does not match the key hash of the shares")]
+    KeyReconstructionMismatch,
+    #[error("shares were created by different share algorithms")]
+    InconsistentShareAlgorithms,
+}
+
+impl From<WeakSeedError> for DerecVSSError {
+    fn from(_: WeakSeedError) -> Self {
+        DerecVSSError::WeakSeed
+    }
+}
+
+/// Computes the hash of a shared AES key that envelope shares carry in `VSSShare::key_hash`.
+///
+/// The hash is `SHA-256("derec-vss-key-hash" || key)`. Since the key is uniformly random, the hash
+/// reveals nothing about it, but lets [`recover_key_only`] tell a wrong reconstruction apart
+/// before any decryption is attempted.
+pub fn key_hash(key: &[u8; λ]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"derec-vss-key-hash");
+    hasher.update(key);
+    hasher.finalize().into()
+}
+
+/// Computes the minimal Merkle tree depth needed to hold `n` leaves, i.e. `ceil(log2(n))`.
+///
+/// A sharing over `n` channels fits in the commitment tree only if this depth
+/// does not exceed [`MERKLE_TREE_DEPTH`].
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::min_merkle_depth;
+/// assert_eq!(min_merkle_depth(1), 0);
+/// assert_eq!(min_merkle_depth(5), 3);
+/// assert_eq!(min_merkle_depth(128), 7);
+/// ```
+pub fn min_merkle_depth(n: u64) -> u32 {
+    if n <= 1 { 0 } else { u64::BITS - (n - 1).leading_zeros() }
 }
 
 /// Generates VSS shares for a given secret using Shamir's Secret Sharing scheme,
@@ -59,9 +146,15 @@ pub enum DerecVSSError {
 /// # Arguments
 ///
 /// * `access_structure` - A tuple `(t, n)` where `t` is the threshold number of shares required to reconstruct the secret,
-///   and `n` is the total number of shares to generate. Must satisfy `2 <= t <= n` and `n <= 128`.
+///   and `n` is the total number of shares to generate. Must satisfy `2 <= t <= n` and `n <= 128`;
+///   a threshold of 1, with which any single share reveals the secret, is refused with
+///   `DerecVSSError::TrivialThreshold` (see [`ShareOptions::allow_trivial_threshold`]).
 /// * `msg` - The secret message to be shared, as a byte slice.
-/// * `rand` - A cryptographically secure random seed of length `λ` (32 bytes).
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes). All randomness used
+///   by the sharing is derived from this seed, so it must be drawn from a CSPRNG (e.g. `OsRng`);
+///   prefer [`share_os`] or [`share_with_rng`] when the caller does not need to control the seed.
+///   A seed that is obviously not random (see `seed::is_weak_seed`) is rejected with
+///   `DerecVSSError::WeakSeed`.
 ///
 /// # Returns
 ///
@@ -83,7 +176,7 @@ pub enum DerecVSSError {
 ///
 /// ```rust
 /// use derec_cryptography::vss::{share, recover, VSSShare};
-/// let shares = share((3, 5), b"my secret", &[0u8; 32]).unwrap();
+/// let shares = share((3, 5), b"my secret", &rand::random()).unwrap();
 /// assert_eq!(shares.len(), 5);
 /// ```
 pub fn share(
@@ -91,54 +184,700 @@ pub fn share(
     msg: &[u8], 
     entropy: &[u8; λ], 
 ) -> Result<Vec<VSSShare>, DerecVSSError> {
-    if (access_structure.0 > access_structure.1) || (access_structure.0 < 2) {
-        return Err(DerecVSSError::InvalidAccessStructure);
+    share_envelope(access_structure, msg, entropy, &ShareOptions::default())
+}
+
+/// Options of [`share_with_options`]; the defaults share like [`share`].
+#[derive(Debug, Clone, Copy)]
+pub struct ShareOptions<'a> {
+    /// if set, the secret is encrypted under this AES key, which is shared in place of a derived one
+    /// (see [`share_with_key`])
+    pub key: Option<&'a [u8; λ]>,
+    /// if set, the secret itself is shared, without an envelope (see [`share_direct`]); `key` is then ignored
+    pub direct: bool,
+    /// if set, the shares are placed at these `n` serialized x-coordinates, in order, rather than at random ones
+    /// (e.g. those of [`x_for_channel`]); a coordinate that does not decode, is zero, or is repeated is refused
+    /// with `DerecVSSError::MalformedShare`
+    pub xs: Option<&'a [Vec<u8>]>,
+    /// if set, a threshold of 1 is accepted; by default it is refused with `DerecVSSError::TrivialThreshold`,
+    /// since any single share then reveals the secret
+    pub allow_trivial_threshold: bool,
+    /// if unset, the shares carry no Merkle commitment or authentication path, saving `(MERKLE_TREE_DEPTH + 1) × 32`
+    /// bytes each; see [`remove_commitments`] for the security tradeoff. Set by default
+    pub commit: bool,
+}
+
+impl Default for ShareOptions<'_> {
+    fn default() -> Self {
+        ShareOptions { key: None, direct: false, xs: None, allow_trivial_threshold: false, commit: true }
     }
+}
 
-    // we can only support up to 2^7 = 128 shares
-    if access_structure.1 > 1 << MERKLE_TREE_DEPTH {
-        return Err(DerecVSSError::InvalidAccessStructure);
+/// Generates VSS shares like [`share`], [`share_with_key`] or [`share_direct`], as selected by `options`.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret message to be shared, as a byte slice.
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes).
+/// * `options` - How the secret is shared; see [`ShareOptions`].
+///
+/// # Errors
+///
+/// Returns the errors of the sharing function `options` selects.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share_with_options, recover, ShareOptions};
+/// let options = ShareOptions { allow_trivial_threshold: true, ..Default::default() };
+/// let shares = share_with_options((1, 3), b"my secret", &rand::random(), &options).unwrap();
+/// assert_eq!(recover(&shares[2..]).unwrap(), b"my secret");
+/// ```
+pub fn share_with_options(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    entropy: &[u8; λ],
+    options: &ShareOptions,
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    match (options.direct, options.key) {
+        (true, _) => share_direct_inner(access_structure, msg, entropy, options),
+        (false, Some(key)) => share_with_key_inner(access_structure, msg, key, entropy, options),
+        (false, None) => share_envelope(access_structure, msg, entropy, options),
     }
+}
+
+/// Derives the x-coordinate of the share held on a channel from the channel's identifier.
+///
+/// The identifier is hashed to a non-zero field element, so anyone can recompute which x a given
+/// helper should hold, and a share presented on the wrong channel fails its Merkle proof once its
+/// x is replaced by this value. Distinct identifiers collide only with negligible probability.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::x_for_channel;
+/// assert_eq!(x_for_channel(42), x_for_channel(42));
+/// assert_ne!(x_for_channel(42), x_for_channel(43));
+/// ```
+pub fn x_for_channel(channel_id: u64) -> Vec<u8> {
+    shamir::hash_to_point(b"derec-channel-x", &channel_id.to_be_bytes())
+}
+
+// body of `share`
+fn share_envelope(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    entropy: &[u8; λ],
+    options: &ShareOptions,
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    check_access_structure(access_structure, options.allow_trivial_threshold)?;
+    require_nonzero_seed(entropy)?;
 
     //pseudo-random key derivation
     let hash = utils::random_oracle(msg, entropy, &[]);
-    let k: [u8; λ] = hash[..1 * λ].try_into().unwrap();
-    let nonce: [u8; λ] = hash[1 * λ..2 * λ].try_into().unwrap();
+    let k: [u8; λ] = hash[..λ].try_into().unwrap();
+    let nonce: [u8; λ] = hash[λ..2 * λ].try_into().unwrap();
     let seed1: [u8; λ] = hash[2 * λ..3 * λ].try_into().unwrap();
     let seed2: [u8; λ] = hash[3 * λ..4 * λ].try_into().unwrap();
 
     //AES encrypt the message using the pseudo-random key k
     let c = encrypt_message(msg, &k, &nonce).unwrap();
 
-    // generate shares of the AES key k
-    let shamir_shares = shamir::share(
-        &k,
-        access_structure,
-        &mut rand_chacha::ChaCha8Rng::from_seed(seed1)
-    );
+    // generate committed shares of the AES key k
+    share_committed(&k, c, access_structure, options, seed1, seed2)
+}
 
+/// Generates VSS shares for a given secret like [`share`], but encrypts it under a caller-supplied AES key.
+///
+/// The key is Shamir-shared in place of a freshly derived one, so [`recover_key_only`] on the shares
+/// returns exactly `key`. This lets the key live in external key management (e.g. an HSM) while the
+/// shares serve as its backup.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret message to be shared, as a byte slice.
+/// * `key` - The 256-bit AES key to encrypt `msg` under and to share.
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes).
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share_with_key, recover, recover_key_only};
+/// let key = [5u8; 32];
+/// let shares = share_with_key((2, 3), b"my secret", &key, &rand::random()).unwrap();
+/// assert_eq!(recover_key_only(&shares[..2]).unwrap(), key);
+/// assert_eq!(recover(&shares[..2]).unwrap(), b"my secret");
+/// ```
+pub fn share_with_key(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    key: &[u8; λ],
+    entropy: &[u8; λ],
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    share_with_key_inner(access_structure, msg, key, entropy, &ShareOptions::default())
+}
+
+// body of `share_with_key`
+fn share_with_key_inner(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    key: &[u8; λ],
+    entropy: &[u8; λ],
+    options: &ShareOptions,
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    check_access_structure(access_structure, options.allow_trivial_threshold)?;
+    require_nonzero_seed(entropy)?;
+
+    let hash = utils::random_oracle(msg, entropy, b"derec-external-key-share");
+    let nonce: [u8; λ] = hash[..λ].try_into().unwrap();
+    let seed1: [u8; λ] = hash[λ..2 * λ].try_into().unwrap();
+    let seed2: [u8; λ] = hash[2 * λ..3 * λ].try_into().unwrap();
+
+    let c = encrypt_message(msg, key, &nonce).unwrap();
+
+    share_committed(key, c, access_structure, options, seed1, seed2)
+}
+
+/// Generates VSS shares of a short secret directly, without encrypting it under an AES key.
+///
+/// The secret itself (prefixed with its length and zero-padded to `λ` bytes) is placed at the
+/// constant term of the Shamir polynomial, so shares carry an empty `encrypted_secret`. This
+/// avoids the envelope for secrets that already fit in the field, such as keys and passwords,
+/// but it also gives up the authentication tag that lets [`recover`] notice a wrong
+/// reconstruction; [`recover_direct`] can only check the length prefix and padding.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret to be shared; at most [`MAX_DIRECT_SECRET_SIZE`] bytes.
+/// * `entropy` - A cryptographically secure random seed of length `λ` (32 bytes).
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::SecretTooLarge` if `msg` does not fit in a single field element,
+/// or `DerecVSSError::InvalidAccessStructure` as for [`share`].
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share_direct, recover_direct};
+/// let shares = share_direct((2, 3), b"my secret", &rand::random()).unwrap();
+/// assert!(shares[0].encrypted_secret.is_empty());
+/// assert_eq!(recover_direct(&shares[..2]).unwrap(), b"my secret");
+/// ```
+pub fn share_direct(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    entropy: &[u8; λ],
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    share_direct_inner(access_structure, msg, entropy, &ShareOptions::default())
+}
+
+// body of `share_direct`
+fn share_direct_inner(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    entropy: &[u8; λ],
+    options: &ShareOptions,
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    check_access_structure(access_structure, options.allow_trivial_threshold)?;
+    require_nonzero_seed(entropy)?;
+
+    if msg.len() > MAX_DIRECT_SECRET_SIZE {
+        return Err(DerecVSSError::SecretTooLarge { max: MAX_DIRECT_SECRET_SIZE });
+    }
+
+    let mut encoded = [0u8; λ];
+    encoded[0] = msg.len() as u8;
+    encoded[1..=msg.len()].copy_from_slice(msg);
+
+    let hash = utils::random_oracle(msg, entropy, b"derec-direct-share");
+    let seed1: [u8; λ] = hash[..λ].try_into().unwrap();
+    let seed2: [u8; λ] = hash[λ..2 * λ].try_into().unwrap();
+
+    let shares = share_committed(&encoded, Vec::new(), access_structure, options, seed1, seed2);
+    encoded.zeroize();
+    shares
+}
+
+fn check_access_structure(access_structure: (u64, u64), allow_trivial_threshold: bool) -> Result<(), DerecVSSError> {
+    if (access_structure.0 > access_structure.1) || (access_structure.0 < 1) {
+        return Err(DerecVSSError::InvalidAccessStructure);
+    }
+
+    // with a threshold of 1, every share carries the secret itself
+    if access_structure.0 == 1 && !allow_trivial_threshold {
+        return Err(DerecVSSError::TrivialThreshold);
+    }
+
+    // we can only support up to 2^7 = 128 shares
+    if access_structure.1 > MAX_SHARES {
+        return Err(DerecVSSError::InvalidAccessStructure);
+    }
+
+    Ok(())
+}
+
+// Shamir-shares `k` (at `options.xs`, if given) and, unless `options.commit` is unset, commits to the
+// resulting shares in a Merkle tree; every share carries the same `encrypted_secret`.
+fn share_committed(
+    k: &[u8; λ],
+    encrypted_secret: Vec<u8>,
+    access_structure: (u64, u64),
+    options: &ShareOptions,
+    seed1: [u8; λ],
+    seed2: [u8; λ],
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(seed1);
+    let shamir_shares = match options.xs {
+        Some(xs) => shamir::share_at(k, access_structure, xs, &mut rng)?,
+        None => shamir::share(k, access_structure, &mut rng),
+    };
+
+    let (commitment, merkle_proofs) = if options.commit {
+        let merkle_tree = utils::build_merkle_tree(
+            &shamir_shares,
+            &[],
+            MERKLE_TREE_DEPTH,
+            &mut rand_chacha::ChaCha8Rng::from_seed(seed2)
+        );
+        let merkle_proofs = utils::extract_merkle_proofs(
+            &merkle_tree,
+            MERKLE_TREE_DEPTH,
+            access_structure.1
+        )?;
+        (merkle_tree[0].clone(), merkle_proofs)
+    } else {
+        (Vec::new(), vec![Vec::new(); shamir_shares.len()])
+    };
+
+    // only an envelope key is checked against its hash; a direct secret is not uniformly random
+    let key_hash = if encrypted_secret.is_empty() { Vec::new() } else { key_hash(k).to_vec() };
+
+    let mut output = vec![];
+    for ((x, y), merkle_path) in shamir_shares.iter().zip(merkle_proofs) {
+        output.push(VSSShare {
+            x: x.to_owned(), 
+            y: y.to_owned(), 
+            encrypted_secret: encrypted_secret.clone(), 
+            key_hash: key_hash.clone(),
+            associated_data: Vec::new(),
+            commitment: commitment.clone(),
+            merkle_path
+        });
+    }
+    Ok(output)
+}
+
+/// Re-commits a complete sharing so that its commitment also covers some public associated data,
+/// such as a description of the version being shared.
+///
+/// Every share's Merkle leaf becomes `SHA-256(x || y || SHA-256(associated_data))`, and the shares
+/// are given the new commitment and authentication paths. A share whose `associated_data` is later
+/// altered no longer verifies, so [`detect_error`] reports it as `DerecVSSError::CorruptShares`.
+///
+/// # Arguments
+///
+/// * `shares` - All shares of one sharing, in the order they were generated.
+/// * `associated_data` - The data to bind to the commitment.
+/// * `entropy` - A cryptographically secure random seed of length `λ`, used to pad the Merkle tree.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::TooManyChannels` if there are more shares than the Merkle tree can hold.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, commit_associated_data, detect_error};
+/// let mut shares = share((2, 3), b"my secret", &rand::random()).unwrap();
+/// commit_associated_data(&mut shares, b"version 2: new bank", &[1u8; 32]).unwrap();
+/// assert!(detect_error(&shares).is_none());
+///
+/// shares[1].associated_data = b"version 2: attacker's bank".to_vec();
+/// assert!(detect_error(&shares).is_some());
+/// ```
+pub fn commit_associated_data(
+    shares: &mut [VSSShare],
+    associated_data: &[u8],
+    entropy: &[u8; λ],
+) -> Result<(), DerecVSSError> {
+    if shares.len() as u64 > MAX_SHARES {
+        return Err(DerecVSSError::TooManyChannels { max: MAX_SHARES });
+    }
+
+    let points: Vec<shamir::Share> = shares.iter().map(|s| (s.x.clone(), s.y.clone())).collect();
     let merkle_tree = utils::build_merkle_tree(
-        &shamir_shares,
+        &points,
+        associated_data,
         MERKLE_TREE_DEPTH,
-        &mut rand_chacha::ChaCha8Rng::from_seed(seed2)
+        &mut rand_chacha::ChaCha8Rng::from_seed(*entropy)
     );
     let merkle_proofs = utils::extract_merkle_proofs(
         &merkle_tree,
         MERKLE_TREE_DEPTH,
-        access_structure.1
+        shares.len() as u64
+    )?;
+
+    for (share, proof) in shares.iter_mut().zip(merkle_proofs) {
+        share.associated_data = associated_data.to_vec();
+        share.commitment = merkle_tree[0].clone();
+        share.merkle_path = proof;
+    }
+    Ok(())
+}
+
+/// Re-commits a complete sharing with its shares placed at pseudorandom leaves of the Merkle tree.
+///
+/// The tree is always padded to `MERKLE_TREE_DEPTH`, but a sharing over `n` channels otherwise occupies
+/// its first `n` leaves, so the authentication path of any one share bounds how many real shares exist.
+/// Here the leaf positions are drawn from `entropy` instead, so a share's path reveals nothing about `n`.
+/// The shares keep the associated data they were committed with, and verify as usual.
+///
+/// # Arguments
+///
+/// * `shares` - All shares of one sharing, in the order they were generated.
+/// * `entropy` - A cryptographically secure random seed of length `λ`, from which both the leaf
+///   positions and the Merkle tree padding are derived.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::TooManyChannels` if there are more shares than the Merkle tree can hold.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, commit_scattered, verify_share};
+/// let mut shares = share((2, 3), b"my secret", &rand::random()).unwrap();
+/// commit_scattered(&mut shares, &rand::random()).unwrap();
+/// assert!(shares.iter().all(|share| verify_share(share).is_ok()));
+/// ```
+pub fn commit_scattered(shares: &mut [VSSShare], entropy: &[u8; λ]) -> Result<(), DerecVSSError> {
+    if shares.len() as u64 > MAX_SHARES {
+        return Err(DerecVSSError::TooManyChannels { max: MAX_SHARES });
+    }
+
+    let associated_data = shares.first().map(|share| share.associated_data.clone()).unwrap_or_default();
+    let points: Vec<shamir::Share> = shares.iter().map(|s| (s.x.clone(), s.y.clone())).collect();
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(*entropy);
+    let positions = utils::scattered_leaf_positions(shares.len(), MERKLE_TREE_DEPTH, &mut rng);
+    let merkle_tree = utils::build_merkle_tree_at(
+        &points,
+        &associated_data,
+        MERKLE_TREE_DEPTH,
+        &positions,
+        &mut rng
     );
-    
-    let mut output = vec![];
-    for (i, (x, y)) in shamir_shares.iter().enumerate() {
-        output.push(VSSShare {
-            x: x.to_owned(), 
-            y: y.to_owned(), 
-            encrypted_secret: c.clone(), 
-            commitment: merkle_tree[0].clone(), 
-            merkle_path: merkle_proofs[i].to_owned()
-        });
+    let merkle_proofs = utils::extract_merkle_proofs_at(&merkle_tree, MERKLE_TREE_DEPTH, &positions)?;
+
+    for (share, proof) in shares.iter_mut().zip(merkle_proofs) {
+        share.commitment = merkle_tree[0].clone();
+        share.merkle_path = proof;
     }
-    Ok(output)
+    Ok(())
+}
+
+/// Generates VSS shares for a given secret, drawing the sharing entropy from the supplied RNG.
+///
+/// This is a convenience wrapper around [`share`] that samples the `λ`-byte seed from `rng`.
+/// The `CryptoRng` bound ensures that a non-cryptographic generator (e.g. `SmallRng` or a
+/// mock RNG) is rejected at compile time.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret message to be shared, as a byte slice.
+/// * `rng` - A cryptographically secure random number generator.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share_with_rng, recover};
+/// let shares = share_with_rng((3, 5), b"my secret", &mut rand::rngs::OsRng).unwrap();
+/// assert_eq!(recover(&shares).unwrap(), b"my secret");
+/// ```
+///
+/// A generator that is not marked `CryptoRng` does not compile:
+///
+/// ```compile_fail,E0277
+/// use derec_cryptography::vss::share_with_rng;
+/// let mut rng = rand::rngs::mock::StepRng::new(0, 1);
+/// let _ = share_with_rng((2, 3), b"my secret", &mut rng);
+/// ```
+pub fn share_with_rng<R: RngCore + CryptoRng>(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    rng: &mut R,
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    let mut entropy = [0u8; λ];
+    rng.fill_bytes(&mut entropy);
+    share(access_structure, msg, &entropy)
+}
+
+/// Generates VSS shares for a given secret, drawing the sharing entropy from the operating system.
+///
+/// This is [`share_with_rng`] with `OsRng`, matching how the library's `protect_secret` seeds its
+/// sharings, for callers that have no reason to pick the seed themselves. Use [`share`] when the
+/// sharing must be reproducible from a known seed.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret message to be shared, as a byte slice.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share_os, recover};
+/// let shares = share_os((3, 5), b"my secret").unwrap();
+/// assert_eq!(recover(&shares).unwrap(), b"my secret");
+/// ```
+pub fn share_os(access_structure: (u64, u64), msg: &[u8]) -> Result<Vec<VSSShare>, DerecVSSError> {
+    share_with_rng(access_structure, msg, &mut rand::rngs::OsRng)
+}
+
+/// Deterministically generates VSS shares for a given secret from a sealed master seed.
+///
+/// The polynomial coefficients, the x-coordinates, the AES key and the Merkle padding are all
+/// derived from `seed` (and the secret) through a PRF, so the same seed and secret always produce
+/// byte-identical shares. This lets an operator regenerate a sharing during a disaster-recovery drill
+/// without having stored the shares.
+///
+/// **Security tradeoff:** anyone holding `seed` and the secret can regenerate every share, and the seed
+/// alone fixes the AES key and polynomial used to protect the secret. The seed is therefore exactly as
+/// sensitive as the secret itself and must be stored with the same care.
+///
+/// # Arguments
+///
+/// * `access_structure` - A tuple `(t, n)`; see [`share`] for the constraints.
+/// * `msg` - The secret message to be shared, as a byte slice.
+/// * `seed` - A 32-byte master seed drawn from a CSPRNG.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::share_deterministic;
+/// let a = share_deterministic((2, 3), b"my secret", b"an example seed of 32 bytes long").unwrap();
+/// let b = share_deterministic((2, 3), b"my secret", b"an example seed of 32 bytes long").unwrap();
+/// assert_eq!(a[0].y, b[0].y);
+/// ```
+pub fn share_deterministic(
+    access_structure: (u64, u64),
+    msg: &[u8],
+    seed: &[u8; λ],
+) -> Result<Vec<VSSShare>, DerecVSSError> {
+    require_nonzero_seed(seed)?;
+
+    // domain-separate the master seed from entropy passed directly to `share`
+    let derived = utils::random_oracle(seed, &[], b"derec-deterministic-share");
+    let entropy: [u8; λ] = derived[..λ].try_into().unwrap();
+    share(access_structure, msg, &entropy)
+}
+
+/// Verifies a single VSS share against the Merkle commitment it carries.
+///
+/// This walks the share's Merkle authentication path from its `(x, y)` leaf up to the root and
+/// checks that the result equals `share.commitment`. It lets an auditor check one helper's share
+/// without access to the rest of the sharing.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::MalformedShare` if a component of the share has an unexpected length, or
+/// `DerecVSSError::CorruptShares` if the Merkle path does not lead to the commitment.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, verify_share};
+/// let shares = share((3, 5), b"my secret", &rand::random()).unwrap();
+/// assert!(verify_share(&shares[0]).is_ok());
+/// ```
+pub fn verify_share(share: &VSSShare) -> Result<(), DerecVSSError> {
+    if !utils::is_well_formed(share) {
+        return Err(DerecVSSError::MalformedShare);
+    }
+
+    if utils::verify_merkle_path(share) {
+        Ok(())
+    } else {
+        Err(DerecVSSError::CorruptShares)
+    }
+}
+
+/// Verifies a single share against a Merkle root the caller already trusts.
+///
+/// Unlike [`verify_share`], which checks the share against the commitment it carries, this walks the
+/// share's Merkle path up to the root and compares the result with `root` directly. A client that learned
+/// the authentic root out of band (e.g. published by the sharer) can thereby authenticate an incoming
+/// share on its own, without the rest of the sharing to compare commitments against.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::MalformedShare` if a component of the share has an unexpected length, or
+/// `DerecVSSError::CorruptShares` if the Merkle path does not lead to `root`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, verify_share_against_root};
+/// let shares = share((3, 5), b"my secret", &rand::random()).unwrap();
+/// let root: [u8; 32] = shares[0].commitment.as_slice().try_into().unwrap();
+/// assert!(verify_share_against_root(&shares[1], &root).is_ok());
+/// ```
+pub fn verify_share_against_root(share: &VSSShare, root: &[u8; 32]) -> Result<(), DerecVSSError> {
+    if !utils::is_well_formed(share) {
+        return Err(DerecVSSError::MalformedShare);
+    }
+
+    if utils::merkle_root(share) == root {
+        Ok(())
+    } else {
+        Err(DerecVSSError::CorruptShares)
+    }
+}
+
+/// Removes the Merkle commitment and authentication path from each share.
+///
+/// This saves `MERKLE_TREE_DEPTH` hashes plus the commitment (`(depth + 1) × 32` bytes) per share, for
+/// deployments that deliver shares over channels that already authenticate them. [`recover`] accepts a
+/// set of such shares, checking only that they agree on the ciphertext.
+///
+/// **Security tradeoff:** without a commitment, a corrupted or substituted share can no longer be
+/// identified, nor shares of different sharings told apart. The AES-GCM tag of the envelope still makes
+/// [`recover`] fail rather than return a wrong secret, but cannot say which share was bad, and
+/// [`recover_direct`] has no tag at all. [`verify_share`] rejects shares without a commitment.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, recover, remove_commitments};
+/// let mut shares = share((2, 3), b"my secret", &rand::random()).unwrap();
+/// remove_commitments(&mut shares);
+/// assert!(shares[0].commitment.is_empty() && shares[0].merkle_path.is_empty());
+/// assert_eq!(recover(&shares[..2]).unwrap(), b"my secret");
+/// ```
+pub fn remove_commitments(shares: &mut [VSSShare]) {
+    for share in shares {
+        share.commitment.clear();
+        share.merkle_path.clear();
+    }
+}
+
+/// Returns whether the share's commitment was removed with [`remove_commitments`].
+pub fn is_uncommitted(share: &VSSShare) -> bool {
+    utils::is_uncommitted(share)
+}
+
+/// Checks that a share's coordinates are canonical encodings of field elements.
+///
+/// Every field element has a single accepted encoding, so a share cannot be presented under
+/// different bytes (e.g. with trailing bytes, which deserialization alone would ignore).
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::MalformedShare` if `x` or `y` is not exactly the encoding of a field element.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, check_share_encoding};
+/// let mut shares = share((2, 3), b"my secret", &rand::random()).unwrap();
+/// assert!(check_share_encoding(&shares[0]).is_ok());
+/// shares[0].y.push(0);
+/// assert!(check_share_encoding(&shares[0]).is_err());
+/// ```
+pub fn check_share_encoding(share: &VSSShare) -> Result<(), DerecVSSError> {
+    shamir::decode_point(&share.x)?;
+    shamir::decode_point(&share.y)?;
+    Ok(())
+}
+
+/// Reconstructs only the AES key protecting the shared secret, without decrypting the secret.
+///
+/// This performs the same consistency checks as [`recover`] and the Shamir reconstruction of the
+/// key, but stops before decrypting `encrypted_secret`. Compared against a previously committed
+/// hash of the key, this lets a party confirm that the shares still reconstruct correctly without
+/// learning the secret itself.
+///
+/// # Arguments
+///
+/// * `shares` - The `VSSShare`s to reconstruct the key from.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::InsufficientShares` if `shares` is empty, the errors of `utils::detect_error`,
+/// `DerecVSSError::MalformedShare` if a share's coordinates do not decode or two shares have the same
+/// x-coordinate, or `DerecVSSError::InsufficientShares` if the interpolated value is
+/// wider than a key, which is what fewer than threshold shares almost always produce. Envelope shares
+/// carrying a `key_hash` report a reconstructed key that does not match it as
+/// `DerecVSSError::KeyReconstructionMismatch`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::{channel, vss::{share, recover_key_only}};
+/// let shares = share((3, 5), b"my secret", &rand::random()).unwrap();
+/// let key = recover_key_only(&shares[..3]).unwrap();
+/// assert_eq!(channel::decrypt_message(&shares[0].encrypted_secret, &key).unwrap(), b"my secret");
+/// ```
+pub fn recover_key_only(shares: &[VSSShare]) -> Result<[u8; λ], DerecVSSError> {
+    if shares.is_empty() {
+        return Err(DerecVSSError::InsufficientShares);
+    }
+
+    if let Some(detected_error) = utils::detect_error(shares) {
+        // some error was detected prior to attempting reconstruction
+        return Err(detected_error);
+    }
+
+    // no error detected so far, let's try shamir reconstruction
+    let shamir_shares = shares
+        .iter()
+        .map(|s| (s.x.clone(), s.y.clone()))
+        .collect();
+    let reconstructed = shamir::recover(shamir_shares);
+    if shares[0].key_hash.is_empty() {
+        return reconstructed;
+    }
+
+    // too few shares, or a wrong one that no commitment caught, reconstruct some other value
+    match reconstructed {
+        Ok(k) if key_hash(&k)[..] == shares[0].key_hash[..] => Ok(k),
+        Ok(mut k) => {
+            k.zeroize();
+            Err(DerecVSSError::KeyReconstructionMismatch)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Computes a salted commitment to a secret, independent of any sharing of it.
+///
+/// The commitment is `SHA-256(salt || secret)`. A sharer can publish it before distributing shares,
+/// so that anyone holding the salt can later confirm that recovery returned the right secret.
+/// The salt must be drawn at random and kept alongside the shares; without it, a low-entropy
+/// secret could be guessed from its commitment.
+///
+/// # Arguments
+///
+/// * `secret` - The secret being committed to.
+/// * `salt` - A random 256-bit salt.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::commit_secret;
+/// let salt = [3u8; 32];
+/// assert_eq!(commit_secret(b"my secret", &salt), commit_secret(b"my secret", &salt));
+/// assert_ne!(commit_secret(b"my secret", &salt), commit_secret(b"not my secret", &salt));
+/// ```
+pub fn commit_secret(secret: &[u8], salt: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(secret);
+    hasher.finalize().into()
 }
 
 /// Recovers the secret-shared data from a set of VSS shares.
@@ -164,40 +903,142 @@ pub fn share(
 /// - `VSSError::CorruptShares` if one or more shares are detected as corrupted.
 /// - `VSSError::InconsistentCommitments` if Merkle commitments do not match.
 /// - `VSSError::InsufficientShares` if not enough valid shares are provided for reconstruction.
+/// - `VSSError::KeyReconstructionMismatch` if the reconstructed key does not match the shares' key hash,
+///   e.g. because a wrong share carried no commitment to catch it.
 /// - `VSSError::DecryptionFailure` if the reconstructed key fails to decrypt the secret.
+/// - `VSSError::MalformedShare` if a share's coordinates do not decode to field elements, or two shares
+///   have the same x-coordinate (e.g. one share supplied twice).
 ///
 /// # Example
 ///
 /// ```rust
 /// use derec_cryptography::vss::{share, recover, VSSShare};
-/// let shares = share((3, 5), b"my secret", &[0u8; 32]).unwrap();
+/// let shares = share((3, 5), b"my secret", &rand::random()).unwrap();
 /// let secret = recover(&shares[..3].to_vec()).unwrap();
 /// assert_eq!(secret, b"my secret");
 /// ```
-pub fn recover(shares: &Vec<VSSShare>) -> Result<Vec<u8>, DerecVSSError> {
-    assert!(shares.len() > 0);
+pub fn recover(shares: &[VSSShare]) -> Result<Vec<u8>, DerecVSSError> {
+    let k = recover_key_only(shares)?;
+
+    // let's attempt to decrypt using the shamir-reconstruced key
+    let c = shares[0].encrypted_secret.clone();
 
-    let detected_error = utils::detect_error(shares);
-    if detected_error.is_none() {
-        // no error detected so far, let's try shamir reconstruction
-        let shamir_shares = shares
-            .iter()
-            .map(|s| (s.x.clone(), s.y.clone()))
-            .collect();
-        let k = shamir::recover(shamir_shares);
+    // the only recourse on decryption failure is to collect more shares
+    decrypt_message(&c, &k).map_err(|_| DerecVSSError::InsufficientShares)
+}
+
+/// Largest number of shares [`recover_with_repair`] searches for among shares that pass their Merkle check.
+pub const MAX_REPAIRED_SHARES: usize = 3;
 
-        // let's attempt to decrypt using the shamir-reconstruced key
-        let c = shares[0].encrypted_secret.clone();
-        let decryption_result = decrypt_message(&c, &k);
+/// Recovers the secret like [`recover`], setting aside corrupted shares instead of failing.
+///
+/// With more shares than the threshold, a corrupted share can be located rather than just detected:
+/// 1. shares whose Merkle path does not verify against their commitment are excluded outright;
+/// 2. if the rest still do not recover the secret (e.g. a share's ciphertext was altered, which the
+///    Merkle leaf does not cover, or the shares carry no commitment), sets of 1, 2, … up to
+///    [`MAX_REPAIRED_SHARES`] shares are left out in turn, Reed-Solomon style, until the remaining
+///    shares decrypt the secret. Since AES-GCM authenticates the secret, a wrong reconstruction is
+///    never returned, and the first set found is a smallest one.
+///
+/// Leaving out `e` shares needs at least `threshold + e` shares, and the search tries up to
+/// `C(n, e)` subsets, so it is meant for the handful of shares a recovery collects.
+///
+/// # Arguments
+///
+/// * `shares` - The shares collected, possibly including corrupted ones.
+///
+/// # Returns
+///
+/// Returns the secret, and the (ascending) indices in `shares` of the shares that were excluded.
+///
+/// # Errors
+///
+/// Returns the error [`recover`] reports for all shares that pass their Merkle check, if no set
+/// of at most [`MAX_REPAIRED_SHARES`] of them can be left out to recover the secret, or
+/// `DerecVSSError::CorruptShares` if no share passes its Merkle check. Copies of a share are
+/// excluded up front; shares with the same x-coordinate but different points are reported as
+/// `DerecVSSError::MalformedShare` unless leaving some of them out recovers the secret.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_cryptography::vss::{share, recover_with_repair};
+/// let mut shares = share((3, 5), b"my secret", &rand::random()).unwrap();
+/// shares[1].encrypted_secret[20] ^= 1;
+/// let (secret, excluded) = recover_with_repair(&shares).unwrap();
+/// assert_eq!(secret, b"my secret");
+/// assert_eq!(excluded, vec![1]);
+/// ```
+pub fn recover_with_repair(shares: &[VSSShare]) -> Result<(Vec<u8>, Vec<usize>), DerecVSSError> {
+    // a share failing its own Merkle proof needs no search to be found
+    let (mut candidates, mut excluded): (Vec<usize>, Vec<usize>) = (0..shares.len())
+        .partition(|&i| utils::is_uncommitted(&shares[i]) || utils::verify_merkle_path(&shares[i]));
+    if candidates.is_empty() {
+        return Err(DerecVSSError::CorruptShares);
+    }
 
-        if decryption_result.is_ok() {
-            return Ok(decryption_result.unwrap());
+    // a share supplied again (e.g. replayed) adds no point to interpolate; two shares on the same x
+    // with different points cannot both be right, and are left to the search below
+    let mut distinct: Vec<usize> = Vec::new();
+    for i in candidates {
+        if distinct.iter().any(|&j| shares[j].x == shares[i].x && shares[j].y == shares[i].y) {
+            excluded.push(i);
         } else {
-            // the only recourse here is to collect more shares
-            return Err(DerecVSSError::InsufficientShares);
+            distinct.push(i);
+        }
+    }
+    candidates = distinct;
+    excluded.sort_unstable();
+
+    let subset = |left_out: &[usize]| -> Vec<VSSShare> {
+        candidates.iter().filter(|i| !left_out.contains(i)).map(|&i| shares[i].clone()).collect()
+    };
+    let first_error = match recover(&subset(&[])) {
+        Ok(secret) => return Ok((secret, excluded)),
+        Err(e) => e,
+    };
+
+    // leave out ever larger sets of the remaining shares, keeping at least one
+    for e in 1..=MAX_REPAIRED_SHARES.min(candidates.len() - 1) {
+        let mut left_out: Vec<usize> = candidates[..e].to_vec();
+        let mut positions: Vec<usize> = (0..e).collect();
+        loop {
+            if let Ok(secret) = recover(&subset(&left_out)) {
+                excluded.extend_from_slice(&left_out);
+                excluded.sort_unstable();
+                return Ok((secret, excluded));
+            }
+
+            // advance to the next e-element combination of candidate positions
+            let Some(i) = (0..e).rev().find(|&i| positions[i] < candidates.len() - e + i) else { break };
+            positions[i] += 1;
+            for j in i + 1..e {
+                positions[j] = positions[j - 1] + 1;
+            }
+            left_out = positions.iter().map(|&p| candidates[p]).collect();
         }
-    } else {
-        // some error was detected prior to attempting decryption
-        return Err(detected_error.unwrap());
     }
+
+    Err(first_error)
+}
+
+/// Recovers a secret shared with [`share_direct`].
+///
+/// This performs the same consistency checks and Shamir reconstruction as [`recover`], and then
+/// strips the length prefix and padding instead of decrypting.
+///
+/// # Errors
+///
+/// Returns the errors of [`recover_key_only`], or `DerecVSSError::InsufficientShares` if the
+/// reconstructed value is not a validly encoded secret, which is what fewer than threshold
+/// shares almost always produce.
+pub fn recover_direct(shares: &[VSSShare]) -> Result<Vec<u8>, DerecVSSError> {
+    let mut encoded = recover_key_only(shares)?;
+
+    let len = encoded[0] as usize;
+    let valid = len <= MAX_DIRECT_SECRET_SIZE && encoded[len + 1..].iter().all(|b| *b == 0);
+    let secret = encoded[1..=len.min(MAX_DIRECT_SECRET_SIZE)].to_vec();
+    encoded.zeroize();
+
+    if valid { Ok(secret) } else { Err(DerecVSSError::InsufficientShares) }
 }
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A stable JSON representation of `VSSShare`, independent of the DeRec protobufs.
+//!
+//! Byte fields are encoded as standard base64 strings, and each Merkle path node as an
+//! `{ "is_left": bool, "hash": base64 }` object, so that tools without protobuf support
+//! can persist and exchange individual shares. The `key_hash` and `associated_data` fields are omitted
+//! when empty, so shares without them keep their earlier representation.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use super::VSSShare;
+
+#[derive(Serialize, Deserialize)]
+struct VSSShareRepr {
+    x: String,
+    y: String,
+    encrypted_secret: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    key_hash: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    associated_data: String,
+    commitment: String,
+    merkle_path: Vec<MerkleNodeRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MerkleNodeRepr {
+    is_left: bool,
+    hash: String,
+}
+
+impl Serialize for VSSShare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VSSShareRepr {
+            x: STANDARD.encode(&self.x),
+            y: STANDARD.encode(&self.y),
+            encrypted_secret: STANDARD.encode(&self.encrypted_secret),
+            key_hash: STANDARD.encode(&self.key_hash),
+            associated_data: STANDARD.encode(&self.associated_data),
+            commitment: STANDARD.encode(&self.commitment),
+            merkle_path: self.merkle_path
+                .iter()
+                .map(|(is_left, hash)| MerkleNodeRepr { is_left: *is_left, hash: STANDARD.encode(hash) })
+                .collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VSSShare {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = VSSShareRepr::deserialize(deserializer)?;
+        let decode = |field: &str| STANDARD.decode(field).map_err(D::Error::custom);
+
+        Ok(VSSShare {
+            x: decode(&repr.x)?,
+            y: decode(&repr.y)?,
+            encrypted_secret: decode(&repr.encrypted_secret)?,
+            key_hash: decode(&repr.key_hash)?,
+            associated_data: decode(&repr.associated_data)?,
+            commitment: decode(&repr.commitment)?,
+            merkle_path: repr.merkle_path
+                .iter()
+                .map(|node| Ok((node.is_left, decode(&node.hash)?)))
+                .collect::<Result<_, D::Error>>()?,
+        })
+    }
+}
+
+impl VSSShare {
+    /// Serializes the share to its JSON representation.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Parses a share from the JSON representation produced by [`VSSShare::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vss;
+
+    #[test]
+    fn test_json_round_trip() {
+        let shares = vss::share((2, 3), b"persist me", &[4u8; 32]).unwrap();
+        let share = &shares[1];
+
+        let json = share.to_json().unwrap();
+        let parsed = VSSShare::from_json(&json).unwrap();
+
+        assert_eq!(parsed.x, share.x);
+        assert_eq!(parsed.y, share.y);
+        assert_eq!(parsed.encrypted_secret, share.encrypted_secret);
+        assert_eq!(parsed.commitment, share.commitment);
+        assert_eq!(parsed.merkle_path, share.merkle_path);
+        assert!(vss::verify_share(&parsed).is_ok());
+
+        // byte fields must be valid base64
+        assert!(VSSShare::from_json(&json.replacen(&STANDARD.encode(&share.x), "not base64!", 1)).is_err());
+    }
+}
@@ -19,26 +19,47 @@
 //!
 //! ## Details
 //!
-//! - Each share is a tuple `(x, y)` where `x` is a random field element and `y` is the evaluation of a random
+//! - Each share is a tuple `(x, y)` where `x` is a random (or caller-chosen) field element and `y` is the evaluation of a random
 //!   degree `t-1` polynomial (with the secret as the constant term) at `x`.
-//! - Serialization and deserialization of field elements is handled using compressed representations.
+//! - Within the crate, `share_at` shares at caller-chosen x-coordinates instead (e.g. derived with
+//!   [`hash_to_point`]); it backs `vss::ShareOptions::xs`.
+//! - Field elements are serialized using compressed representations. On recovery, each coordinate is
+//!   decoded as compressed or uncompressed, as its length indicates, so shares written with either
+//!   encoding interoperate; within each encoding, only the canonical bytes of an element are accepted.
+//!   (For the scalar field the two encodings currently coincide; the fallback keeps recovery working
+//!   should share generation ever switch encodings.)
 //! - The secret is encoded as the constant term of the polynomial and is recovered by interpolating at `x = 0`.
+//!   Secrets are read and written as big-endian integers; the field modulus is wider than 256 bits, so
+//!   every secret maps to a distinct field element, and a recovered value wider than 256 bits is rejected.
+//! - Intermediate values that reveal the secret (the polynomial coefficients, the secret's bit and field
+//!   encodings, and the reconstructed field element) are zeroized once they are no longer needed.
 //!
 
 //! Implements functions for Shamir secret sharing, as adapted
 //! from the definition in Fig 7 of https://eprint.iacr.org/2020/800.pdf
 
-use ark_poly::{Polynomial, univariate::DensePolynomial};
 use ark_std::UniformRand;
-use ark_ff::{PrimeField, BigInteger};
-use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
-use rand::Rng;
+use ark_ff::{Field, PrimeField, BigInteger};
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, Compress, Validate};
+use rand::{CryptoRng, Rng};
+use std::ops::{Deref, DerefMut};
+use zeroize::Zeroize;
 
 use super::*;
 
 // we use the scalar field of the ark_bw6_761 curve so it is large enough for 256-bit secrets
 use ark_bw6_761::Fr as F;
 
+// every λ-byte secret must be strictly below the field modulus, so that encoding it
+// as a field element never wraps around
+const _: () = assert!(<F as PrimeField>::MODULUS_BIT_SIZE as usize > 8 * λ);
+
+// a Shamir share as serialized (x, y) coordinates
+pub type Share = (Vec<u8>, Vec<u8>);
+
+// the length of every serialized coordinate; the field's encodings carry no flag bits
+pub const POINT_SIZE: usize = (<F as PrimeField>::MODULUS_BIT_SIZE as usize).div_ceil(8);
+
 /// Splits a 256-bit secret into Shamir shares with a specified threshold and total number of shares.
 ///
 /// # Arguments
@@ -47,7 +68,8 @@ use ark_bw6_761::Fr as F;
 /// * `access` - A tuple `(t, n)` where:
 ///     - `t` is the reconstruction threshold (minimum number of shares required to recover the secret),
 ///     - `n` is the total number of shares to generate.
-/// * `rng` - A mutable reference to a random number generator implementing the `Rng` trait.
+/// * `rng` - A mutable reference to a cryptographically secure random number generator
+///   (`Rng + CryptoRng`), from which the polynomial coefficients and x-coordinates are drawn.
 ///
 /// # Returns
 ///
@@ -55,11 +77,80 @@ use ark_bw6_761::Fr as F;
 /// - The first element is the serialized x-coordinate (as a field element).
 /// - The second element is the serialized y-coordinate (as a field element).
 ///
-pub fn share<R: Rng>(
+pub fn share<R: Rng + CryptoRng>(
     secret: &[u8; λ], 
     access: (u64, u64),
     rng: &mut R
-) -> Vec<(Vec<u8>, Vec<u8>)> {
+) -> Vec<Share> {
+    share_inner(secret, access, None, rng)
+}
+
+/// Splits a 256-bit secret into Shamir shares like [`share`], evaluating the polynomial at the given
+/// x-coordinates instead of random ones.
+///
+/// # Arguments
+///
+/// * `secret` - A reference to a byte array of length `λ` representing the secret to be shared.
+/// * `access` - A tuple `(t, n)`; see [`share`].
+/// * `xs` - The `n` serialized x-coordinates to evaluate at, in the order of the returned shares.
+/// * `rng` - A cryptographically secure random number generator for the polynomial coefficients.
+///
+/// # Returns
+///
+/// The shares, or `DerecVSSError::InvalidAccessStructure` if `xs` does not hold `n` coordinates,
+/// or `DerecVSSError::MalformedShare` if a coordinate does not decode, is zero, or is repeated.
+///
+pub(crate) fn share_at<R: Rng + CryptoRng>(
+    secret: &[u8; λ],
+    access: (u64, u64),
+    xs: &[Vec<u8>],
+    rng: &mut R
+) -> Result<Vec<Share>, DerecVSSError> {
+    if xs.len() as u64 != access.1 {
+        return Err(DerecVSSError::InvalidAccessStructure);
+    }
+
+    let xs: Vec<F> = xs
+        .iter()
+        .map(|x| decode_point(x))
+        .collect::<Result<_, _>>()?;
+
+    // the share at x = 0 would be the secret itself, and a repeated x cannot be interpolated
+    for (i, x) in xs.iter().enumerate() {
+        if *x == F::from(0) || xs[..i].contains(x) {
+            return Err(DerecVSSError::MalformedShare);
+        }
+    }
+
+    Ok(share_inner(secret, access, Some(&xs), rng))
+}
+
+/// Hashes `data` to a non-zero field element, serialized like a share's x-coordinate.
+///
+/// The hash is SHA-256 over `tag`, a counter and `data`, reduced modulo the field order;
+/// the counter is only incremented in the (negligibly likely) event that the result is zero.
+pub fn hash_to_point(tag: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut counter: u8 = 0;
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(tag);
+        hasher.update([counter]);
+        hasher.update(data);
+        let x = F::from_be_bytes_mod_order(&hasher.finalize());
+        if x != F::from(0) {
+            return encode_point(&x);
+        }
+        counter += 1;
+    }
+}
+
+// evaluates a random polynomial through the secret at `xs`, or at n random points if none are given
+fn share_inner<R: Rng + CryptoRng>(
+    secret: &[u8; λ],
+    access: (u64, u64),
+    xs: Option<&[F]>,
+    rng: &mut R
+) -> Vec<Share> {
 
     // parse the desired access structure.
     // n is the number of shares, while
@@ -69,39 +160,29 @@ pub fn share<R: Rng>(
     // let us sample a random degree t-1 polynomial.
     // A degree t - 1 polynomial has t coefficients,
     // which we sample at random
-    let mut coeffs: Vec<F> = (0..t)
+    let mut coeffs: ZeroizeGuard<Vec<F>> = ZeroizeGuard((0..t)
         .map(|_| F::rand(rng))
-        .collect();
+        .collect());
 
     // But we don't want a completely random polynomial, 
     // but rather one whose evaluation at x=0 is the secret.
     // So, let us replace zero-th coefficient with our secret.
-    let secret_bigint = BigInteger::from_bits_be(
-        &bytes_to_bits_be(secret));
-    coeffs[0] = F::from_bigint(secret_bigint).unwrap();
-
-    // we now have all the right coefficients to define the polynomial
-    let poly = DensePolynomial { coeffs };
-
-    // let us define a function for serializing polynomial evaluations
-    let encode_point = |x: &F| -> Vec<u8> {
-        let mut buffer: Vec<u8> = Vec::new();
-        x.serialize_compressed(&mut buffer).unwrap();
-        buffer
-    };
-
-    // Shamir shares are just evaluations of our polynomial above
-    let shares = (0..n)
-        .map(|_| 
+    coeffs[0] = secret_to_field_be(secret);
+
+    // Shamir shares are just evaluations of our polynomial above;
+    // we evaluate in place (Horner's rule) so the coefficients are never copied
+    (0..n as usize)
+        .map(|i| 
             { 
-                let x = F::rand(rng);
-                let y = poly.evaluate(&x);
+                let x = match xs {
+                    Some(xs) => xs[i],
+                    None => F::rand(rng),
+                };
+                let y = coeffs.iter().rev().fold(F::from(0), |acc, c| acc * x + c);
                 (encode_point(&x), encode_point(&y))
             }
         )
-        .collect();
-
-    shares
+        .collect()
 }
 
 
@@ -115,59 +196,136 @@ pub fn share<R: Rng>(
 ///
 /// # Returns
 ///
-/// * `[u8; λ]` - The recovered secret as a byte array of length λ.
+/// * `Ok([u8; λ])` - The recovered secret as a byte array of length λ.
+/// * `Err(DerecVSSError::MalformedShare)` if a coordinate does not decode to a field element, or
+///   two shares have the same x-coordinate.
+/// * `Err(DerecVSSError::InsufficientShares)` if the interpolated value does not fit in λ bytes,
+///   as happens (with overwhelming probability) when fewer than `t` shares are supplied.
 ///
 pub fn recover(
     shares: Vec<(Vec<u8>, Vec<u8>)>
-) -> [u8; λ] {
+) -> Result<[u8; λ], DerecVSSError> {
     // let us parse all Shamir shares as field elements
     let xs: Vec<F> = shares
         .iter()
-        .map(|(x, _)| F::deserialize_compressed(&x[..]).unwrap())
-        .collect();
+        .map(|(x, _)| decode_point(x))
+        .collect::<Result<_, _>>()?;
 
     let ys: Vec<F> = shares
         .iter()
-        .map(|(_, y)| F::deserialize_compressed(&y[..]).unwrap())
-        .collect();
+        .map(|(_, y)| decode_point(y))
+        .collect::<Result<_, _>>()?;
+
+    // shares on the same x cannot be interpolated, e.g. one share supplied twice
+    for (i, x) in xs.iter().enumerate() {
+        if xs[..i].contains(x) {
+            return Err(DerecVSSError::MalformedShare);
+        }
+    }
 
     // compute lagrange coefficients w.r.t. x = 0.
     // we choose x = 0 because we encoded our secret at f(0)
-    let lagrange_coeffs = lagrange_coefficients(&xs[..], F::from(0));
+    let lagrange_coeffs = lagrange_coefficients(&xs[..], F::from(0))?;
 
     //secret f(0) as a field element
-    let secret = ys
+    let secret = ZeroizeGuard(ys
         .iter()
         .zip(lagrange_coeffs.iter())
-        .fold(F::from(0), |acc, (a,b)| acc + (a * b));
+        .fold(F::from(0), |acc, (a,b)| acc + (a * b)));
     
-    // serialize secret into big-endian representation
-    let secret_bytes = secret.into_bigint().to_bytes_be();
+    // a value wider than λ bytes cannot be a shared secret; interpolating
+    // fewer than t shares is by far the likeliest way to end up with one
+    field_to_secret_be(&secret).ok_or(DerecVSSError::InsufficientShares)
+}
+
+// Interprets a λ-byte secret as a big-endian integer and maps it to a field element.
+// This never reduces: the secret is below the modulus (see the assertion on MODULUS_BIT_SIZE).
+fn secret_to_field_be(secret: &[u8; λ]) -> F {
+    let secret_bits = ZeroizeGuard(bytes_to_bits_be(secret));
+    let secret_bigint = ZeroizeGuard(BigInteger::from_bits_be(&secret_bits));
+    F::from_bigint(*secret_bigint).expect("a λ-byte integer is below the field modulus")
+}
+
+// Inverse of `secret_to_field_be`: the big-endian encoding of `x`, provided it fits in λ bytes.
+// The field's big-endian encoding is wider than λ bytes, so all leading bytes must be zero.
+fn field_to_secret_be(x: &F) -> Option<[u8; λ]> {
+    let bytes = ZeroizeGuard(x.into_bigint().to_bytes_be());
+    let (high, low) = bytes.split_at(bytes.len().checked_sub(λ)?);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    low.try_into().ok()
+}
 
-    // our 256 bit key should be in the below slice
-    let start = secret_bytes.len() - λ;
-    secret_bytes[start..start + λ].try_into().unwrap()
+// Serializes a field element with the compressed encoding used for share coordinates.
+fn encode_point(x: &F) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::new();
+    x.serialize_compressed(&mut buffer).unwrap();
+    buffer
+}
+
+// Decodes a serialized field element, accepting either the compressed or the uncompressed encoding,
+// told apart by their length. The deserializer ignores bytes past the element, so the bytes must also
+// be exactly the element's encoding in that form; otherwise a helper could present one share under
+// many byte strings.
+pub fn decode_point(bytes: &[u8]) -> Result<F, DerecVSSError> {
+    for compress in [Compress::Yes, Compress::No] {
+        if bytes.len() != F::default().serialized_size(compress) {
+            continue;
+        }
+        let Ok(point) = F::deserialize_with_mode(bytes, compress, Validate::Yes) else { continue };
 
+        let mut canonical = Vec::new();
+        point.serialize_with_mode(&mut canonical, compress).unwrap();
+        if bytes == canonical {
+            return Ok(point);
+        }
+    }
+    Err(DerecVSSError::MalformedShare)
+}
+
+// Holds a value that reveals the secret, and zeroizes it when dropped.
+struct ZeroizeGuard<T: Zeroize>(T);
+
+impl<T: Zeroize> Deref for ZeroizeGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for ZeroizeGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Drop for ZeroizeGuard<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
 }
 
 
 // Naive lagrange interpolation over the input x-coordinates.
 // This method computes the lagrange coefficients, which should
 // be used to compute an inner product with the y-coordinates.
+// Repeated x-coordinates have no coefficients and are reported as MalformedShare.
 // reference: https://en.wikipedia.org/wiki/Lagrange_polynomial
-fn lagrange_coefficients(xs: &[F], x: F) -> Vec<F> {
+fn lagrange_coefficients(xs: &[F], x: F) -> Result<Vec<F>, DerecVSSError> {
     let mut output = Vec::new();
 
     for (i, &x_i) in xs.iter().enumerate() {
         let mut l_i = F::from(1);
         for (j, &x_j) in xs.iter().enumerate() {
             if i != j {
-                l_i *= (x - x_j) / (x_i - x_j);
+                l_i *= (x - x_j) * (x_i - x_j).inverse().ok_or(DerecVSSError::MalformedShare)?;
             }
         }
         output.push(l_i);
     }
-    output
+    Ok(output)
 }
 
  // Encodes a byte array as bit array, in a Big endian encoding.
@@ -187,11 +345,86 @@ fn bytes_to_bits_be(x: &[u8]) -> Vec<bool> {
     output
 }
 
+/// Fixed Shamir vectors for checking other DeRec implementations (e.g. the Java library) against this one.
+///
+/// The shares were produced by the Shamir sharing underlying the VSS from [`SECRET`](test_vectors::SECRET)
+/// with a `ChaCha8Rng` seeded with [`SEED`](test_vectors::SEED), so they pin the field, the (little-endian,
+/// compressed) serialization of field elements and the interpolation: any two of the
+/// [`SHARES`](test_vectors::SHARES) must recover [`SECRET`](test_vectors::SECRET).
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors {
+    use rand_chacha::rand_core::SeedableRng;
+    use super::{recover, share, Share};
+
+    /// Seed of the `ChaCha8Rng` from which the coefficients and x-coordinates were drawn.
+    pub const SEED: [u8; 32] = [0x5e; 32];
+
+    /// The shared secret.
+    pub const SECRET: &[u8; 32] = b"derec shamir test vector secret!";
+
+    /// The access structure `(t, n)` of the sharing.
+    pub const ACCESS_STRUCTURE: (u64, u64) = (2, 3);
+
+    /// The serialized `(x, y)` coordinates of each share, hex encoded.
+    pub const SHARES: [(&str, &str); 3] = [
+        (
+            "cdd3e72a936f95df7eac1f2778e53d3900ad7d28c77ccfc948a045ade1ebbb10afab52d7530caf8aadb79ae9949f4c00",
+            "872e7a68ccd95863e2c87150130ef36b860d0e7ba722c4dc65274ec6e4363f13538c0ac124e4e159cba21504d52ac900",
+        ),
+        (
+            "0b55fa70d0493868da8f78a6592440194291892344d8f48e88038bba2a54cb8ca6c627bf23e1a6d96b43dad195e33200",
+            "8f6dbdef63226aa3b1de10dd46aadbe65158d3b30b1edfc2f4c0ba9d776acec812f7c448d087acf2e9b02c3572972001",
+        ),
+        (
+            "cf49fc8b17169b13a70dbaebdad467b69efde8fda24bd1c607fb195845a60eca058a527013512de628df7f02922df200",
+            "c132cb1dad28f504a43c605f43c321a8c9ee6f7c2a6c5e01627bdc3d21e3ff518613b11da1ea4da019325205726c9f00",
+        ),
+    ];
+
+    /// Decodes [`SHARES`] into serialized `(x, y)` coordinates.
+    pub fn shares() -> Vec<Share> {
+        SHARES.iter().map(|(x, y)| (from_hex(x), from_hex(y))).collect()
+    }
+
+    /// Checks that sharing [`SECRET`] from [`SEED`] reproduces [`SHARES`] byte for byte, and that
+    /// every `t` of them recover [`SECRET`].
+    pub fn verify() -> bool {
+        let shares = shares();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed(SEED);
+        if share(SECRET, ACCESS_STRUCTURE, &mut rng) != shares {
+            return false;
+        }
+
+        (0..shares.len()).all(|i| {
+            (i + 1..shares.len()).all(|j| {
+                recover(vec![shares[i].clone(), shares[j].clone()]).is_ok_and(|secret| &secret == SECRET)
+            })
+        })
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("test vectors are valid hex"))
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #[test]
+        fn test_shamir_test_vectors_verify() {
+            assert!(super::verify());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rand::thread_rng;
     use rand_chacha::rand_core::SeedableRng;
+    use std::cell::Cell;
+    use std::rc::Rc;
 
     #[test]
     fn test_shamir_correctness() {
@@ -209,8 +442,108 @@ mod tests {
         let mut rng = rand_chacha::ChaCha8Rng::from_seed(seed);
 
         let shares = share(&secret, (3, 5), &mut rng);
-        let recovered = recover(shares);
+        let recovered = recover(shares).unwrap();
 
         assert_eq!(secret, recovered);
     }
+
+    // a value that records whether it was zeroized
+    struct Tracked(Rc<Cell<bool>>);
+
+    impl Zeroize for Tracked {
+        fn zeroize(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    #[test]
+    fn test_secret_intermediates_are_zeroized() {
+        // dropping a guard zeroizes what it holds
+        let wiped = Rc::new(Cell::new(false));
+        let guard = ZeroizeGuard(Tracked(wiped.clone()));
+        assert!(!wiped.get());
+        drop(guard);
+        assert!(wiped.get());
+
+        // and zeroizing clears the field elements and byte buffers the secret passes through
+        let mut guard = ZeroizeGuard(F::from(42u64));
+        guard.0.zeroize();
+        assert_eq!(*guard, F::from(0u64));
+        let mut guard = ZeroizeGuard(vec![F::from(1u64), F::from(2u64)]);
+        guard.0.zeroize();
+        assert!(guard.is_empty());
+    }
+
+    #[test]
+    fn test_recover_from_mixed_point_encodings() {
+        let secret = [7u8; 32];
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let mut shares = share(&secret, (2, 3), &mut rng);
+
+        // every coordinate under both encodings, as implementations choosing either would write it
+        let encode_with = |bytes: &[u8], compress: Compress| -> Vec<u8> {
+            let mut buffer = Vec::new();
+            decode_point(bytes).unwrap().serialize_with_mode(&mut buffer, compress).unwrap();
+            buffer
+        };
+        let compressed: Vec<Share> = shares
+            .iter()
+            .map(|(x, y)| (encode_with(x, Compress::Yes), encode_with(y, Compress::Yes)))
+            .collect();
+        let uncompressed: Vec<Share> = shares
+            .iter()
+            .map(|(x, y)| (encode_with(x, Compress::No), encode_with(y, Compress::No)))
+            .collect();
+
+        // any pair recovers whichever encoding each of its shares, or each coordinate, uses
+        for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+            assert_eq!(recover(vec![compressed[i].clone(), uncompressed[j].clone()]).unwrap(), secret);
+            assert_eq!(recover(vec![uncompressed[i].clone(), compressed[j].clone()]).unwrap(), secret);
+            let mixed = (compressed[i].0.clone(), uncompressed[i].1.clone());
+            assert_eq!(recover(vec![mixed, uncompressed[j].clone()]).unwrap(), secret);
+        }
+
+        // garbage coordinates are reported rather than panicking
+        shares[0].0 = vec![0xff; 3];
+        assert!(matches!(recover(shares[..2].to_vec()), Err(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_recover_requires_canonical_point_encodings() {
+        let secret = [7u8; 32];
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let shares = share(&secret, (2, 3), &mut rng);
+
+        // within either encoding, only the canonical bytes of an element decode: not bytes past
+        // the element, nor values not below the modulus
+        let mut padded = shares[0].clone();
+        padded.1.push(0);
+        assert!(matches!(recover(vec![padded, shares[1].clone()]), Err(DerecVSSError::MalformedShare)));
+        let mut unreduced = shares[0].clone();
+        unreduced.1 = vec![0xff; unreduced.1.len()];
+        assert!(matches!(recover(vec![unreduced, shares[1].clone()]), Err(DerecVSSError::MalformedShare)));
+        let mut truncated = shares[0].clone();
+        truncated.1.pop();
+        assert!(matches!(recover(vec![truncated, shares[1].clone()]), Err(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_secret_encoding_covers_full_range() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([3u8; 32]);
+
+        // the extremes of the 256-bit range, including the high bit set
+        let mut high_bit = [0u8; 32];
+        high_bit[0] = 0x80;
+        for secret in [[0u8; 32], [0xFFu8; 32], high_bit] {
+            assert_eq!(field_to_secret_be(&secret_to_field_be(&secret)), Some(secret));
+
+            let shares = share(&secret, (3, 4), &mut rng);
+            assert_eq!(recover(shares[1..].to_vec()).unwrap(), secret);
+        }
+
+        // field elements beyond 256 bits do not decode to a secret
+        let two_pow_256 = (0..256).fold(F::from(1u64), |acc, _| acc + acc);
+        assert_eq!(field_to_secret_be(&two_pow_256), None);
+        assert_eq!(field_to_secret_be(&-F::from(1u64)), None);
+    }
 }
@@ -1,14 +1,36 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use rand::Rng;
+use rand::{CryptoRng, Rng};
 use sha2::{Sha256, Digest};
 
-use super::{VSSShare, DerecVSSError, λ};
-
-// this function will be used to detect one of several possible errors:
-// 1. inconsistent ciphertexts; 2. inconsistent commitments; 3. corrupted shares
-pub fn detect_error(shares: &Vec<VSSShare>) -> Option<DerecVSSError>
+use super::{shamir, VSSShare, DerecVSSError, MERKLE_TREE_DEPTH, λ};
+
+// size in bytes of a Merkle tree node (a SHA-256 hash)
+const HASH_SIZE: usize = 32;
+
+/// Detects one of several possible errors in a set of shares, without attempting reconstruction:
+/// 1. malformed shares; 2. inconsistent ciphertexts; 3. inconsistent commitments; 4. corrupted shares.
+///
+/// A share is malformed if its commitment or any Merkle path node is not a 32-byte hash, or its
+/// Merkle path does not have `MERKLE_TREE_DEPTH` nodes; this is checked for every share before
+/// comparing them, so that an internally consistent but malformed set is still rejected.
+/// Shares from two different sharings carry different commitments, so mixing them
+/// is reported as `DerecVSSError::InconsistentCommitments`. Returns `None` if the
+/// shares are consistent, including when `shares` is empty.
+///
+/// Shares whose commitment was removed with `remove_commitments` carry no Merkle path to check;
+/// a set of such shares is only checked for consistent ciphertexts. Mixing them with committed
+/// shares is reported as `DerecVSSError::InconsistentCommitments`.
+pub fn detect_error(shares: &[VSSShare]) -> Option<DerecVSSError>
 {
+    if shares.is_empty() {
+        return None;
+    }
+
+    if !shares.iter().all(is_well_formed) {
+        return Some(DerecVSSError::MalformedShare);
+    }
+
     // let's grab the ciphertext and commitment from some share
     // and check that all other shares have the same values
     let commitment = &shares[0].commitment;
@@ -19,25 +41,13 @@ pub fn detect_error(shares: &Vec<VSSShare>) -> Option<DerecVSSError>
             return Some(DerecVSSError::InconsistentCommitments);
         }
 
-        if &share.encrypted_secret != encrypted_secret {
+        // the key hash belongs with the ciphertext the key opens
+        if &share.encrypted_secret != encrypted_secret || share.key_hash != shares[0].key_hash {
             return Some(DerecVSSError::InconsistentCiphertexts);
         }
 
-        // now verify the Merkle path
-        // first compute hash of this share
-        let mut on_path_hash = leaf_hash((&share.x, &share.y));
-
-        for (is_left, node_hash) in share.merkle_path.iter() {
-            on_path_hash = if *is_left {
-                //sibling is on the left
-                intermediate_hash(&node_hash, &on_path_hash)
-            } else {
-                intermediate_hash(&on_path_hash, &node_hash)
-            }
-        }
-        
-        //on_path_hash should equal the merkle root
-        if &on_path_hash != commitment {
+        // now verify the Merkle path, if there is one
+        if !is_uncommitted(share) && !verify_merkle_path(share) {
             return Some(DerecVSSError::CorruptShares);
         }
     }
@@ -46,18 +56,73 @@ pub fn detect_error(shares: &Vec<VSSShare>) -> Option<DerecVSSError>
     None
 }
 
+// whether the share's commitment and Merkle path were removed (see `remove_commitments`)
+pub fn is_uncommitted(share: &VSSShare) -> bool {
+    share.commitment.is_empty() && share.merkle_path.is_empty()
+}
+
+// checks the lengths of the share's coordinates, key hash, commitment and Merkle path against what we
+// produce, so that leaf hashing and the comparisons in detect_error only ever see canonical sizes
+pub fn is_well_formed(share: &VSSShare) -> bool {
+    if share.x.len() != shamir::POINT_SIZE || share.y.len() != shamir::POINT_SIZE {
+        return false;
+    }
+
+    let committed_well = share.commitment.len() == HASH_SIZE
+        && share.merkle_path.len() == MERKLE_TREE_DEPTH as usize
+        && share.merkle_path.iter().all(|(_, node_hash)| node_hash.len() == HASH_SIZE);
+    (share.key_hash.is_empty() || share.key_hash.len() == HASH_SIZE) && (is_uncommitted(share) || committed_well)
+}
+
+// checks that the share's Merkle path leads from its (x, y, associated data) leaf up to its commitment
+pub fn verify_merkle_path(share: &VSSShare) -> bool {
+    //the computed root should equal the merkle root
+    merkle_root(share) == share.commitment
+}
+
+// walks the share's Merkle path from its (x, y, associated data) leaf up to the root it leads to
+pub fn merkle_root(share: &VSSShare) -> Vec<u8> {
+    // first compute hash of this share
+    let mut on_path_hash = leaf_hash((&share.x, &share.y), &share.associated_data);
+
+    for (is_left, node_hash) in share.merkle_path.iter() {
+        on_path_hash = if *is_left {
+            //sibling is on the left
+            intermediate_hash(node_hash, &on_path_hash)
+        } else {
+            intermediate_hash(&on_path_hash, node_hash)
+        }
+    }
+
+    on_path_hash
+}
+
 // builds a 2-ary merkle tree over shares
 // we will specify a depth of the tree, even though
 // we may not have that many shares. This is to 
 // avoid leaking the number of shares to the attacker.
-pub fn build_merkle_tree<R: Rng>(
+pub fn build_merkle_tree<R: Rng + CryptoRng>(
     shares: &[(Vec<u8>, Vec<u8>)], 
+    associated_data: &[u8],
     depth: u32, 
     rng: &mut R
+) -> Vec<Vec<u8>> {
+    let positions: Vec<usize> = (0..shares.len()).collect();
+    build_merkle_tree_at(shares, associated_data, depth, &positions, rng)
+}
+
+// builds a merkle tree like build_merkle_tree, placing shares[i] at leaf positions[i]
+// (counting from 0 at the leftmost leaf) rather than in the first leaves
+pub fn build_merkle_tree_at<R: Rng + CryptoRng>(
+    shares: &[(Vec<u8>, Vec<u8>)],
+    associated_data: &[u8],
+    depth: u32,
+    positions: &[usize],
+    rng: &mut R
 ) -> Vec<Vec<u8>> {
     // merkle tree nodes are of type Vec<u8>, 
     // though we know their size to be 256 B
-    let merkle_tree_size = ((2 as u32).pow(depth + 1) - 1) as usize;
+    let merkle_tree_size = (2_u32.pow(depth + 1) - 1) as usize;
     let mut merkle_nodes: Vec<Vec<u8>> = Vec::new();
     //allocate space up front
     merkle_nodes.resize(merkle_tree_size, Vec::new());
@@ -65,13 +130,17 @@ pub fn build_merkle_tree<R: Rng>(
     // let us compute the leaf nodes first
     // note that we want a complete binary tree, 
     // so we pad with dummy (garbage) elements
-    let num_leaf_nodes = (2 as u32).pow(depth) as usize;
-    for i in 0..num_leaf_nodes {
+    let num_leaf_nodes = 2_u32.pow(depth) as usize;
+    let mut leaf_shares: Vec<Option<&(Vec<u8>, Vec<u8>)>> = vec![None; num_leaf_nodes];
+    for (share, &position) in shares.iter().zip(positions) {
+        leaf_shares[position] = Some(share);
+    }
+    for (i, leaf_share) in leaf_shares.into_iter().enumerate() {
         // root node is labelled 1; so, node labels go from 1 to 2^(depth + 1) - 1
         let node_label = num_leaf_nodes + i;
-        if i < shares.len() {
+        if let Some((x, y)) = leaf_share {
             // hash the share's (x,y); node root's label starts at 1
-            merkle_nodes[node_label - 1] = leaf_hash((&shares[i].0, &shares[i].1));
+            merkle_nodes[node_label - 1] = leaf_hash((x, y), associated_data);
         } else {
             // generate a garbage values for non-existent leaf nodes
             let mut rand = [0u8; 32];
@@ -83,8 +152,8 @@ pub fn build_merkle_tree<R: Rng>(
 
     //let us now compute the intermediate nodes of the merkle tree
     for height in (0..depth).rev() { //from depth - 1 down to 0
-        let lo = (2 as u32).pow(height) as usize;
-        let hi = ((2 as u32).pow(height + 1) - 1) as usize;
+        let lo = 2_u32.pow(height) as usize;
+        let hi = (2_u32.pow(height + 1) - 1) as usize;
 
         for node_label in lo..(hi+1) { // from lo to hi
             let left_child_label = node_label * 2;
@@ -102,48 +171,86 @@ pub fn build_merkle_tree<R: Rng>(
 
 }
 
-// extract merkle proofs for first n leaves in a merkle tree of input depth
+// a bottom-up merkle path: (is the sibling a left child, sibling hash) per level
+pub type MerkleProof = Vec<(bool, Vec<u8>)>;
+
+// extract merkle proofs for first n leaves in a merkle tree of input depth;
+// fails with TooManyLeaves rather than walk past the 2^depth leaves
 pub fn extract_merkle_proofs(
-    tree: &Vec<Vec<u8>>,
+    tree: &[Vec<u8>],
     depth: u32, 
     n: u64
-) -> Vec<Vec<(bool, Vec<u8>)>> {
-    assert!((tree.len() + 1) > 2 && 
-        ((tree.len() + 1) & (tree.len())) == 0, 
-        "merkle tree not a complete binary tree");
+) -> Result<Vec<MerkleProof>, DerecVSSError> {
+    let leaves = 2_u64.pow(depth);
+    if n > leaves {
+        return Err(DerecVSSError::TooManyLeaves { max: leaves });
+    }
+
+    (0..n as usize)
+        .map(|leaf_index| extract_merkle_proof(tree, depth, leaf_index))
+        .collect()
+}
+
+// extract merkle proofs for the leaves at the given positions in a merkle tree of input depth
+pub fn extract_merkle_proofs_at(
+    tree: &[Vec<u8>],
+    depth: u32,
+    positions: &[usize]
+) -> Result<Vec<MerkleProof>, DerecVSSError> {
+    positions
+        .iter()
+        .map(|&leaf_index| extract_merkle_proof(tree, depth, leaf_index))
+        .collect()
+}
+
+// samples n distinct leaf positions of a merkle tree of input depth, in random order
+pub fn scattered_leaf_positions<R: Rng + CryptoRng>(n: usize, depth: u32, rng: &mut R) -> Vec<usize> {
+    rand::seq::index::sample(rng, 2_usize.pow(depth), n).into_vec()
+}
+
+// extract the bottom-up merkle proof for the leaf at position leaf_index
+// (counting from 0 at the leftmost leaf) in a merkle tree of input depth;
+// the tree may come off the wire, so a malformed one is an error rather than a panic
+pub fn extract_merkle_proof(
+    tree: &[Vec<u8>],
+    depth: u32,
+    leaf_index: usize
+) -> Result<MerkleProof, DerecVSSError> {
+    // a complete binary tree of input depth has 2^(depth+1) - 1 nodes
+    let complete_size = 2_usize
+        .checked_pow(depth + 1)
+        .ok_or(DerecVSSError::MalformedTree)?;
+    if depth == 0 || tree.len() + 1 != complete_size {
+        return Err(DerecVSSError::MalformedTree);
+    }
+    if leaf_index >= complete_size / 2 {
+        return Err(DerecVSSError::TooManyLeaves { max: (complete_size / 2) as u64 });
+    }
 
     // even nodes' siblings are odd nodes, and vice versa
     let other_label = |x: usize| -> usize {
-        if x % 2 == 0 { x + 1 } else { x - 1 }
+        if x.is_multiple_of(2) { x + 1 } else { x - 1 }
     };
     let is_left = |x: usize| -> bool {
-        if x % 2 == 0 { true } else { false }
+        x.is_multiple_of(2)
     };
 
-    let mut output: Vec<Vec<(bool, Vec<u8>)>> = Vec::new();
-
     let lo = tree.len() / 2 + 1; //label of lo node (e.g. 8)
-    let hi = lo + (n as usize) - 1; // label of lo node (e.g. 15 if n = 8)
-
-    // rust ranges are exclusive on the hi end
-    for label in lo..(hi+1) {
-        // the merkle path should have depth number of nodes
-        let mut current_label = label;
-        let mut merkle_path: Vec<(bool, Vec<u8>)> = Vec::new();
-        
-        for _ in 0..depth {
-            let sibling_label = other_label(current_label);
-            merkle_path.push((
-                is_left(sibling_label),
-                tree[sibling_label - 1].clone()
-            ));
-            current_label = current_label / 2;
-        }
 
-        output.push(merkle_path);
+    // the merkle path should have depth number of nodes
+    let mut current_label = lo + leaf_index;
+    let mut merkle_path: Vec<(bool, Vec<u8>)> = Vec::new();
+
+    for _ in 0..depth {
+        let sibling_label = other_label(current_label);
+        merkle_path.push((
+            is_left(sibling_label),
+            tree[sibling_label - 1].clone()
+        ));
+        current_label /= 2;
     }
 
-    output
+    Ok(merkle_path)
 }
 
 // produces 4λ bits, where λ = 256
@@ -167,11 +274,15 @@ pub fn random_oracle(msg: &[u8], rand: &[u8], tag: &[u8]) -> [u8; 4 * λ] {
     output
 }
 
-// A share's hash is SHA256(x || y).
-fn leaf_hash(share: (&Vec<u8>, &Vec<u8>)) -> Vec<u8> {
+// A share's hash is SHA256(x || y), or SHA256(x || y || SHA256(associated_data))
+// when the sharing commits to associated data.
+fn leaf_hash(share: (&Vec<u8>, &Vec<u8>), associated_data: &[u8]) -> Vec<u8> {
     let mut hasher_input = Vec::new();
-    hasher_input.extend_from_slice(&share.0);
-    hasher_input.extend_from_slice(&share.1);
+    hasher_input.extend_from_slice(share.0);
+    hasher_input.extend_from_slice(share.1);
+    if !associated_data.is_empty() {
+        hasher_input.extend_from_slice(&compute_sha256_hash(associated_data));
+    }
 
     compute_sha256_hash(&hasher_input)
 }
@@ -217,6 +328,28 @@ mod tests {
         assert_eq!(msg, recovered[..]);
     }
 
+    #[test]
+    fn test_share_deterministic_is_reproducible() {
+        let seed = [42u8; 32];
+        let msg = b"disaster recovery drill";
+
+        let first = vss::share_deterministic((3, 5), msg, &seed).unwrap();
+        let second = vss::share_deterministic((3, 5), msg, &seed).unwrap();
+
+        for (a, b) in first.iter().zip(second.iter()) {
+            assert_eq!(a.x, b.x);
+            assert_eq!(a.y, b.y);
+            assert_eq!(a.encrypted_secret, b.encrypted_secret);
+            assert_eq!(a.commitment, b.commitment);
+            assert_eq!(a.merkle_path, b.merkle_path);
+        }
+        assert_eq!(vss::recover(&first).unwrap(), msg);
+
+        // a different seed yields a different sharing
+        let other = vss::share_deterministic((3, 5), msg, &[43u8; 32]).unwrap();
+        assert_ne!(first[0].y, other[0].y);
+    }
+
     #[test]
     fn test_merkle_tree_correctness() {
         let mut rng = thread_rng();
@@ -235,16 +368,159 @@ mod tests {
             .iter()
             .map(|s| (s.x.clone(), s.y.clone()))
             .collect();
-        let merkle_tree = build_merkle_tree(&share_points, 3, &mut thread_rng());
+        let merkle_tree = build_merkle_tree(&share_points, &[], 3, &mut thread_rng());
         assert_merkle_tree_wff(&merkle_tree);
     }
 
-    fn assert_merkle_tree_wff(tree: &Vec<Vec<u8>>) {
+    #[test]
+    fn test_extract_merkle_proof_for_arbitrary_leaf() {
+        let mut rng = thread_rng();
+
+        let share_points: Vec<(Vec<u8>, Vec<u8>)> = (0..8)
+            .map(|_| {
+                let mut x = [0u8; 48];
+                let mut y = [0u8; 48];
+                rng.fill(&mut x[..]);
+                rng.fill(&mut y[..]);
+                (x.to_vec(), y.to_vec())
+            })
+            .collect();
+        let tree = build_merkle_tree(&share_points, &[], 3, &mut thread_rng());
+
+        let proof = extract_merkle_proof(&tree, 3, 5).unwrap();
+        assert_eq!(proof.len(), 3);
+
+        let share = VSSShare {
+            x: share_points[5].0.clone(),
+            y: share_points[5].1.clone(),
+            encrypted_secret: vec![],
+            key_hash: vec![],
+            associated_data: vec![],
+            commitment: tree[0].clone(),
+            merkle_path: proof,
+        };
+        assert!(verify_merkle_path(&share));
+
+        // the batch version agrees with the single-leaf version
+        assert_eq!(extract_merkle_proofs(&tree, 3, 8).unwrap()[5], share.merkle_path);
+    }
+
+    #[test]
+    fn test_detect_error_rejects_malformed_shares() {
+        let shares = vss::share((2, 3), b"secret", &[5u8; 32]).unwrap();
+        assert!(detect_error(&shares).is_none());
+
+        // a consistent set of shares with a truncated commitment
+        let mut truncated = shares.clone();
+        for share in truncated.iter_mut() {
+            share.commitment.truncate(16);
+        }
+        assert!(matches!(detect_error(&truncated), Some(DerecVSSError::MalformedShare)));
+
+        // a Merkle path that is too short
+        let mut short_path = shares.clone();
+        short_path[1].merkle_path.pop();
+        assert!(matches!(detect_error(&short_path), Some(DerecVSSError::MalformedShare)));
+
+        // a Merkle path node that is not a hash
+        let mut bad_node = shares;
+        bad_node[2].merkle_path[0].1.push(0);
+        assert!(matches!(detect_error(&bad_node), Some(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_detect_error_without_commitments() {
+        let mut shares = vss::share((2, 3), b"secret", &[5u8; 32]).unwrap();
+        let committed = shares[0].clone();
+        vss::remove_commitments(&mut shares);
+        assert!(detect_error(&shares).is_none());
+        assert!(vss::verify_share(&shares[0]).is_err());
+
+        // ciphertexts are still compared
+        let mut tampered = shares.clone();
+        tampered[1].encrypted_secret[0] ^= 1;
+        assert!(matches!(detect_error(&tampered), Some(DerecVSSError::InconsistentCiphertexts)));
+
+        // a committed share next to uncommitted ones
+        shares[0] = committed;
+        assert!(matches!(detect_error(&shares), Some(DerecVSSError::InconsistentCommitments)));
+
+        // shares can also be produced without a commitment in the first place
+        let options = vss::ShareOptions { commit: false, ..Default::default() };
+        let uncommitted = vss::share_with_options((2, 3), b"secret", &[5u8; 32], &options).unwrap();
+        assert!(uncommitted.iter().all(vss::is_uncommitted));
+        assert_eq!(vss::recover(&uncommitted[1..]).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_recover_key_only_decrypts_secret() {
+        let msg = b"health check without disclosure";
+        let shares = vss::share((3, 5), msg, &[8u8; 32]).unwrap();
+
+        let key = vss::recover_key_only(&shares[1..4]).unwrap();
+        assert_eq!(vss::recover_key_only(&shares[2..5]).unwrap(), key);
+
+        let decrypted = crate::channel::decrypt_message(&shares[0].encrypted_secret, &key).unwrap();
+        assert_eq!(decrypted, msg);
+
+        assert!(matches!(vss::recover_key_only(&[]), Err(DerecVSSError::InsufficientShares)));
+
+        // a share supplied twice cannot be interpolated, and is reported rather than panicking
+        let duplicated = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert!(matches!(vss::recover_key_only(&duplicated), Err(DerecVSSError::MalformedShare)));
+        assert!(matches!(vss::recover(&duplicated), Err(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_direct_sharing_round_trip() {
+        for msg in [&b""[..], &b"hunter2"[..], &[0xFFu8; vss::MAX_DIRECT_SECRET_SIZE][..]] {
+            let shares = vss::share_direct((3, 5), msg, &[9u8; 32]).unwrap();
+            assert!(shares.iter().all(|s| s.encrypted_secret.is_empty()));
+            assert_eq!(vss::recover_direct(&shares[..3]).unwrap(), msg);
+            assert_eq!(vss::recover_direct(&shares[2..]).unwrap(), msg);
+        }
+
+        let shares = vss::share_direct((3, 5), b"hunter2", &[9u8; 32]).unwrap();
+        assert!(matches!(vss::recover_direct(&shares[..2]), Err(DerecVSSError::InsufficientShares)));
+
+        let too_long = [1u8; vss::MAX_DIRECT_SECRET_SIZE + 1];
+        assert!(matches!(
+            vss::share_direct((3, 5), &too_long, &[9u8; 32]),
+            Err(DerecVSSError::SecretTooLarge { max: vss::MAX_DIRECT_SECRET_SIZE })
+        ));
+    }
+
+    #[test]
+    fn test_x_for_channel_is_deterministic_and_distinct() {
+        assert_eq!(vss::x_for_channel(7), vss::x_for_channel(7));
+
+        let xs: std::collections::HashSet<Vec<u8>> = (0..1000u64).map(vss::x_for_channel).collect();
+        assert_eq!(xs.len(), 1000);
+
+        // shares land on the channels' x-coordinates and recover as usual
+        let channels = [11u64, 22, 33];
+        let xs: Vec<Vec<u8>> = channels.iter().map(|c| vss::x_for_channel(*c)).collect();
+        let options = vss::ShareOptions { xs: Some(&xs), ..Default::default() };
+        let shares = vss::share_with_options((2, 3), b"channel-bound", &[4u8; 32], &options).unwrap();
+        for (share, x) in shares.iter().zip(xs.iter()) {
+            assert_eq!(&share.x, x);
+        }
+        assert_eq!(vss::recover(&shares[1..]).unwrap(), b"channel-bound");
+
+        // a channel listed twice would put two shares on the same x
+        let repeated = [xs[0].clone(), xs[1].clone(), xs[0].clone()];
+        assert!(matches!(
+            vss::share_with_options((2, 3), b"channel-bound", &[4u8; 32], &vss::ShareOptions { xs: Some(&repeated), ..Default::default() }),
+            Err(DerecVSSError::MalformedShare)
+        ));
+    }
+
+    fn assert_merkle_tree_wff(tree: &[Vec<u8>]) {
         let n = tree.len() + 1; // n must be a power of 2
         assert!(n > 2 && (n & (n - 1)) == 0, 
             "merkle tree not a complete binary tree");
         let mut hi = n / 2 - 1; //label of hi node (e.g. 7)
-        let mut lo = (hi + 1) / 2; // label of lo node (e.g. 4)
+        let mut lo = hi.div_ceil(2); // label of lo node (e.g. 4)
 
         loop {
             for node_label in lo..(hi+1) {
@@ -260,11 +536,188 @@ mod tests {
             }
 
             //set the new lo and hi
-            lo = lo / 2;
-            hi = hi / 2;
+            lo /= 2;
+            hi /= 2;
 
             if lo == hi { return; } // we got to the root node
         }
     }
 
+    #[test]
+    fn test_trivial_threshold_is_opt_in() {
+        let entropy = [6u8; 32];
+        assert!(matches!(vss::share((1, 3), b"secret", &entropy), Err(DerecVSSError::TrivialThreshold)));
+        assert!(matches!(vss::share_direct((1, 3), b"secret", &entropy), Err(DerecVSSError::TrivialThreshold)));
+        assert!(matches!(vss::share_with_key((1, 3), b"secret", &[5u8; 32], &entropy), Err(DerecVSSError::TrivialThreshold)));
+        assert!(matches!(vss::share((0, 3), b"secret", &entropy), Err(DerecVSSError::InvalidAccessStructure)));
+
+        let options = vss::ShareOptions { allow_trivial_threshold: true, ..Default::default() };
+        let shares = vss::share_with_options((1, 3), b"secret", &entropy, &options).unwrap();
+        assert_eq!(vss::recover(&shares[1..2]).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_recover_with_repair_locates_a_corrupted_share() {
+        let shares = vss::share((3, 5), b"secret", &[9u8; 32]).unwrap();
+
+        // a flipped bit in a share's y-coordinate breaks its Merkle proof
+        let mut corrupted = shares.clone();
+        corrupted[3].y[5] ^= 0x10;
+        assert!(vss::recover(&corrupted).is_err());
+        let (secret, excluded) = vss::recover_with_repair(&corrupted).unwrap();
+        assert_eq!(secret, b"secret");
+        assert_eq!(excluded, vec![3]);
+
+        // without commitments, the corrupted share is found by leaving shares out in turn
+        let mut uncommitted = corrupted.clone();
+        vss::remove_commitments(&mut uncommitted);
+        let (secret, excluded) = vss::recover_with_repair(&uncommitted).unwrap();
+        assert_eq!(secret, b"secret");
+        assert_eq!(excluded, vec![3]);
+
+        // with only the threshold left, there is nothing to cross-check against
+        assert!(vss::recover_with_repair(&uncommitted[1..4]).is_err());
+
+        // a replayed share is set aside rather than interpolated twice
+        let replayed = [shares[0].clone(), shares[0].clone(), shares[1].clone(), shares[2].clone()];
+        let (secret, excluded) = vss::recover_with_repair(&replayed).unwrap();
+        assert_eq!(secret, b"secret");
+        assert_eq!(excluded, vec![1]);
+
+        // as is a share on the same x with a different point, when the rest recover the secret
+        let mut forged = shares[0].clone();
+        forged.y = shares[4].y.clone();
+        vss::remove_commitments(std::slice::from_mut(&mut forged));
+        let mut clashing = uncommitted[..3].to_vec();
+        clashing.insert(1, forged.clone());
+        let (secret, excluded) = vss::recover_with_repair(&clashing).unwrap();
+        assert_eq!(secret, b"secret");
+        assert_eq!(excluded, vec![1]);
+
+        // and with too few distinct shares, the clash is reported rather than panicking
+        let clashing = [uncommitted[0].clone(), forged, uncommitted[1].clone()];
+        assert!(matches!(vss::recover_with_repair(&clashing), Err(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_wrong_key_reconstruction_is_reported() {
+        let shares = vss::share((3, 5), b"secret", &[4u8; 32]).unwrap();
+        assert_eq!(shares[0].key_hash, vss::key_hash(&vss::recover_key_only(&shares[..3]).unwrap()));
+
+        // two shares of a threshold-3 sharing interpolate to a value too wide to be a key
+        assert!(matches!(vss::recover_key_only(&shares[..2]), Err(DerecVSSError::InsufficientShares)));
+        assert!(matches!(vss::recover(&shares[1..3]), Err(DerecVSSError::InsufficientShares)));
+
+        // shares that no commitment ties to the key hash they carry reconstruct some other key
+        let other = vss::share((3, 5), b"secret", &[5u8; 32]).unwrap();
+        let mut uncommitted = other[..3].to_vec();
+        vss::remove_commitments(&mut uncommitted);
+        for share in uncommitted.iter_mut() {
+            share.key_hash = shares[0].key_hash.clone();
+        }
+        assert!(matches!(vss::recover_key_only(&uncommitted), Err(DerecVSSError::KeyReconstructionMismatch)));
+        assert!(matches!(vss::recover(&uncommitted), Err(DerecVSSError::KeyReconstructionMismatch)));
+
+        // directly shared secrets carry no key hash
+        assert!(vss::share_direct((2, 3), b"secret", &[4u8; 32]).unwrap()[0].key_hash.is_empty());
+    }
+
+    #[test]
+    fn test_scattered_shares_verify_against_the_root() {
+        let mut shares = vss::share((3, 5), b"secret", &[7u8; 32]).unwrap();
+        vss::commit_scattered(&mut shares, &[8u8; 32]).unwrap();
+
+        // a share's leaf position can be read off the sides of its authentication path
+        let leaf_position = |share: &VSSShare| -> usize {
+            share.merkle_path.iter().enumerate().map(|(level, (is_left, _))| (*is_left as usize) << level).sum()
+        };
+        let positions: Vec<usize> = shares.iter().map(leaf_position).collect();
+        assert_ne!(positions, vec![0, 1, 2, 3, 4]);
+        assert_eq!(positions.iter().collect::<std::collections::HashSet<_>>().len(), 5);
+
+        assert!(shares.iter().all(verify_merkle_path));
+        assert!(shares.iter().all(|share| share.commitment == shares[0].commitment));
+        assert!(detect_error(&shares).is_none());
+        assert_eq!(vss::recover(&shares[1..4]).unwrap(), b"secret");
+
+        // the positions are fixed by the entropy
+        let mut again = vss::share((3, 5), b"secret", &[7u8; 32]).unwrap();
+        vss::commit_scattered(&mut again, &[8u8; 32]).unwrap();
+        assert_eq!(again.iter().map(leaf_position).collect::<Vec<_>>(), positions);
+
+        // and a tampered share still fails its proof
+        shares[2].y[0] ^= 1;
+        assert!(!verify_merkle_path(&shares[2]));
+    }
+
+    #[test]
+    fn test_extract_merkle_proofs_rejects_more_leaves_than_the_tree_has() {
+        let mut rng = rand::thread_rng();
+        let shares = vss::shamir::share(&[7u8; λ], (2, 3), &mut rng);
+        let tree = build_merkle_tree(&shares, &[], 3, &mut rng);
+
+        assert!(matches!(
+            extract_merkle_proofs(&tree, 3, 10),
+            Err(DerecVSSError::TooManyLeaves { max: 8 })
+        ));
+        assert_eq!(extract_merkle_proofs(&tree, 3, 8).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_verify_share_against_a_known_root() {
+        let shares = vss::share((2, 3), b"published root", &[3u8; λ]).unwrap();
+        let root: [u8; 32] = shares[0].commitment.as_slice().try_into().unwrap();
+        assert!(vss::verify_share_against_root(&shares[2], &root).is_ok());
+
+        let mut wrong_root = root;
+        wrong_root[0] ^= 1;
+        assert!(matches!(
+            vss::verify_share_against_root(&shares[2], &wrong_root),
+            Err(DerecVSSError::CorruptShares)
+        ));
+
+        // the share's own commitment field plays no part
+        let mut share = shares[2].clone();
+        share.commitment = wrong_root.to_vec();
+        assert!(vss::verify_share_against_root(&share, &root).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_tree_is_an_error() {
+        let tree = vec![vec![0u8; HASH_SIZE]; 10];
+        assert!(matches!(extract_merkle_proof(&tree, 3, 0), Err(DerecVSSError::MalformedTree)));
+        assert!(matches!(extract_merkle_proofs(&tree, 3, 4), Err(DerecVSSError::MalformedTree)));
+        assert!(matches!(extract_merkle_proofs_at(&tree, 3, &[1, 2]), Err(DerecVSSError::MalformedTree)));
+
+        // a complete tree of another depth is just as unusable
+        let tree = vec![vec![0u8; HASH_SIZE]; 7];
+        assert!(matches!(extract_merkle_proof(&tree, 3, 0), Err(DerecVSSError::MalformedTree)));
+        assert!(extract_merkle_proof(&tree, 2, 3).is_ok());
+        assert!(matches!(extract_merkle_proof(&tree, 2, 4), Err(DerecVSSError::TooManyLeaves { max: 4 })));
+    }
+
+    #[test]
+    fn test_padded_share_is_rejected_as_malformed() {
+        let mut shares = vss::share((2, 3), b"fixed size shares", &[4u8; λ]).unwrap();
+        assert!(shares.iter().all(|share| share.x.len() == shamir::POINT_SIZE && share.y.len() == shamir::POINT_SIZE));
+
+        // trailing zeros leave the field element's value unchanged, but not its length
+        shares[1].y.extend_from_slice(&[0u8; 16]);
+        assert!(matches!(detect_error(&shares), Some(DerecVSSError::MalformedShare)));
+        assert!(matches!(vss::verify_share(&shares[1]), Err(DerecVSSError::MalformedShare)));
+
+        let root: [u8; 32] = shares[0].commitment.as_slice().try_into().unwrap();
+        assert!(matches!(vss::verify_share_against_root(&shares[1], &root), Err(DerecVSSError::MalformedShare)));
+    }
+
+    #[test]
+    fn test_share_os_draws_fresh_entropy() {
+        let first = vss::share_os((2, 3), b"seeded by the os").unwrap();
+        let second = vss::share_os((2, 3), b"seeded by the os").unwrap();
+        assert_eq!(vss::recover(&first[1..]).unwrap(), b"seeded by the os");
+        assert_eq!(vss::recover(&second[..2]).unwrap(), b"seeded by the os");
+
+        assert_ne!(first[0].commitment, second[0].commitment);
+        assert_ne!(first[0].encrypted_secret, second[0].encrypted_secret);
+    }
 }
@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// integration tests link the crate without `cfg(test)`, so the seed guard is active here
+
+use derec_cryptography::{pairing, vss};
+
+#[test]
+fn all_zero_seed_is_rejected() {
+    assert!(matches!(pairing::contact_message([0u8; 32]), Err(pairing::DerecPairingError::WeakSeed)));
+    assert!(matches!(vss::share((2, 3), b"secret", &[0u8; 32]), Err(vss::DerecVSSError::WeakSeed)));
+    assert!(matches!(vss::share_direct((2, 3), b"secret", &[0xffu8; 32]), Err(vss::DerecVSSError::WeakSeed)));
+
+    let seed = *b"an example seed of 32 bytes long";
+    let (contact, _) = pairing::contact_message(seed).unwrap();
+    assert!(matches!(pairing::pairing_request_message([9u8; 32], &contact), Err(pairing::DerecPairingError::WeakSeed)));
+    assert!(pairing::pairing_request_message(seed, &contact).is_ok());
+    assert!(vss::share((2, 3), b"secret", &seed).is_ok());
+}
@@ -0,0 +1,101 @@
+/// Number of bytes in the big-endian length prefix of every frame.
+pub const FRAME_HEADER_SIZE: usize = 4;
+
+/// Default upper bound on the payload size accepted by a `Deframer` (1 MiB).
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1 << 20;
+
+/// Frames a serialized DeRec message for transmission over a byte stream.
+///
+/// The frame consists of a 4-byte big-endian length prefix followed by the payload,
+/// so that several messages can be sent back-to-back over a single socket.
+///
+/// # Arguments
+///
+/// * `msg_bytes` - The serialized message (e.g. the protobuf encoding of a DeRec message).
+///
+/// # Returns
+///
+/// Returns the framed bytes, ready to be written to the stream.
+///
+/// # Panics
+///
+/// Panics if the payload is longer than `u32::MAX` bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::framing::frame;
+/// let framed = frame(b"hello");
+/// assert_eq!(framed, [0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o']);
+/// ```
+pub fn frame(msg_bytes: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(msg_bytes.len()).expect("Message too large to frame");
+
+    let mut framed = Vec::with_capacity(FRAME_HEADER_SIZE + msg_bytes.len());
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(msg_bytes);
+    framed
+}
+
+/// Reassembles framed messages from a byte stream.
+///
+/// Bytes read from the stream are pushed into the `Deframer` as they arrive, in chunks
+/// of any size; complete messages are emitted only once their whole payload has been
+/// received. Frames announcing a payload larger than the configured maximum are rejected
+/// before any of the payload is buffered.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::framing::{frame, Deframer, DEFAULT_MAX_FRAME_SIZE};
+/// let mut deframer = Deframer::new(DEFAULT_MAX_FRAME_SIZE);
+/// let framed = frame(b"hello");
+/// assert!(deframer.push(&framed[..3]).unwrap().is_empty());
+/// assert_eq!(deframer.push(&framed[3..]).unwrap(), vec![b"hello".to_vec()]);
+/// ```
+pub struct Deframer {
+    buffer: Vec<u8>,
+    max_frame_size: usize,
+}
+
+impl Deframer {
+    /// Creates a `Deframer` that rejects frames whose payload exceeds `max_frame_size` bytes.
+    pub fn new(max_frame_size: usize) -> Self {
+        Deframer { buffer: Vec::new(), max_frame_size }
+    }
+
+    /// Buffers `bytes` and returns every message that is now complete, in stream order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a frame header announces a payload larger than the maximum
+    /// frame size. The stream cannot be resynchronized after such an error.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<Vec<u8>>, &'static str> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut messages = Vec::new();
+        while self.buffer.len() >= FRAME_HEADER_SIZE {
+            let header: [u8; FRAME_HEADER_SIZE] = self.buffer[..FRAME_HEADER_SIZE].try_into().unwrap();
+            let len = u32::from_be_bytes(header) as usize;
+            if len > self.max_frame_size {
+                return Err("Frame exceeds the maximum frame size");
+            }
+
+            // wait for the rest of the payload
+            if self.buffer.len() < FRAME_HEADER_SIZE + len {
+                break;
+            }
+
+            let rest = self.buffer.split_off(FRAME_HEADER_SIZE + len);
+            messages.push(self.buffer[FRAME_HEADER_SIZE..].to_vec());
+            self.buffer = rest;
+        }
+
+        Ok(messages)
+    }
+
+    /// Returns the number of bytes buffered that do not yet form a complete message.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
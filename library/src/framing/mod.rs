@@ -0,0 +1,8 @@
+#[allow(clippy::module_inception)]
+pub mod framing;
+pub use framing::frame;
+pub use framing::Deframer;
+pub use framing::DEFAULT_MAX_FRAME_SIZE;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use crate::framing::{frame, Deframer, DEFAULT_MAX_FRAME_SIZE};
+
+    #[test]
+    fn test_deframer_emits_only_complete_messages() {
+        let mut stream = frame(b"first message");
+        stream.extend(frame(b""));
+        stream.extend(frame(b"second"));
+
+        let mut deframer = Deframer::new(DEFAULT_MAX_FRAME_SIZE);
+        let mut received = Vec::new();
+        let mut emitted_at = Vec::new();
+
+        // feed the stream one byte at a time
+        for (i, byte) in stream.iter().enumerate() {
+            for msg in deframer.push(&[*byte]).unwrap() {
+                received.push(msg);
+                emitted_at.push(i + 1);
+            }
+        }
+
+        assert_eq!(received, vec![b"first message".to_vec(), Vec::new(), b"second".to_vec()]);
+        // each message appears exactly when its last byte arrives
+        assert_eq!(emitted_at, vec![4 + 13, 4 + 13 + 4, stream.len()]);
+        assert_eq!(deframer.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_deframer_rejects_oversized_frames() {
+        let mut deframer = Deframer::new(8);
+        assert!(deframer.push(&frame(b"12345678")).is_ok());
+        assert!(deframer.push(&frame(b"123456789")).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+
+/// Minimum number of salt bytes accepted by `derive_from_passphrase`.
+pub const MIN_SALT_SIZE: usize = argon2::MIN_SALT_LEN;
+
+/// Work factor of the Argon2id key derivation in `derive_from_passphrase`.
+///
+/// The defaults (19 MiB of memory, 2 passes, 1 lane) follow the OWASP recommendation for Argon2id;
+/// raising `memory_kib` or `iterations` makes every passphrase guess proportionally more expensive.
+/// The parameters are not secret, but must be the same when the key is derived again, so callers
+/// that deviate from the defaults need to store them with the ciphertext, as they do the salt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// memory used by each derivation, in KiB
+    pub memory_kib: u32,
+    /// number of passes over the memory
+    pub iterations: u32,
+    /// degree of parallelism (number of lanes)
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Argon2Params {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Derives a 256-bit key from a human passphrase with the memory-hard Argon2id.
+///
+/// The salt must be chosen at random for every key (at least `MIN_SALT_SIZE` bytes, 16 are recommended)
+/// and stored alongside the ciphertext, so that the key can be derived again from the passphrase.
+///
+/// # Arguments
+///
+/// * `passphrase` - The passphrase entered by the user.
+/// * `salt` - The random salt stored with the ciphertext.
+/// * `params` - The work factor; see `Argon2Params`.
+///
+/// # Returns
+///
+/// Returns the derived key.
+///
+/// # Panics
+///
+/// Panics if the salt is shorter than `MIN_SALT_SIZE` bytes, or `params` are outside the ranges Argon2
+/// accepts (e.g. fewer than 8 KiB of memory per lane, or no passes).
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::kdf::{derive_from_passphrase, Argon2Params};
+/// let params = Argon2Params { memory_kib: 1024, iterations: 1, parallelism: 1 };
+/// let key = derive_from_passphrase("correct horse battery staple", b"per-ciphertext salt", params);
+/// assert_eq!(key, derive_from_passphrase("correct horse battery staple", b"per-ciphertext salt", params));
+/// ```
+pub fn derive_from_passphrase(passphrase: &str, salt: &[u8], params: Argon2Params) -> [u8; 32] {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+        .expect("Invalid Argon2 parameters");
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("Argon2 salt is too short");
+    key
+}
@@ -0,0 +1,8 @@
+#[allow(clippy::module_inception)]
+pub mod kdf;
+pub use kdf::derive_from_passphrase;
+pub use kdf::Argon2Params;
+pub use kdf::MIN_SALT_SIZE;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use crate::kdf::{derive_from_passphrase, Argon2Params};
+
+    #[test]
+    fn test_derive_from_passphrase_is_deterministic_per_salt() {
+        let params = Argon2Params { memory_kib: 1024, iterations: 1, parallelism: 1 };
+        let key = derive_from_passphrase("correct horse battery staple", b"first salt value", params);
+
+        assert_eq!(key, derive_from_passphrase("correct horse battery staple", b"first salt value", params));
+        assert_ne!(key, derive_from_passphrase("correct horse battery staple", b"other salt value", params));
+        assert_ne!(key, derive_from_passphrase("correct horse battery stable", b"first salt value", params));
+
+        // the work factor is part of the derivation
+        let slower = Argon2Params { iterations: 2, ..params };
+        assert_ne!(key, derive_from_passphrase("correct horse battery staple", b"first salt value", slower));
+    }
+
+    #[test]
+    fn test_default_params_match_argon2_defaults() {
+        let key = derive_from_passphrase("passphrase", b"sixteen byte slt", Argon2Params::default());
+        let mut expected = [0u8; 32];
+        argon2::Argon2::default().hash_password_into(b"passphrase", b"sixteen byte slt", &mut expected).unwrap();
+        assert_eq!(key, expected);
+    }
+}
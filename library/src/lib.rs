@@ -3,4 +3,7 @@ pub mod sharing;
 pub mod verification;
 pub mod recovery;
 pub mod protos;
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod framing;
+pub mod kdf;
+pub mod transport;
@@ -1,9 +1,38 @@
+#[allow(clippy::module_inception)]
 pub mod pairing;
 
 pub use pairing::create_contact_message;
+pub use pairing::create_contact_messages;
+pub use pairing::create_contact_message_with_key_exchange;
 pub use pairing::produce_pairing_request_message;
+pub use pairing::produce_pairing_request_message_with_code;
+pub use pairing::bind_contact_keys;
+pub use pairing::generate_binding_code;
+pub use pairing::MIN_BINDING_CODE_LEN;
+pub use pairing::BINDING_CODE_GROUPS;
 pub use pairing::produce_pairing_response_message;
+pub use pairing::produce_pairing_response_message_with_limiter;
+pub use pairing::produce_pairing_rejection_message;
 pub use pairing::process_pairing_response_message;
+pub use pairing::validate_contact_message;
+pub use pairing::requestor_sender_kind;
+pub use pairing::build_transcript;
+pub use pairing::transcript_hash;
+pub use pairing::canonical_encode;
+pub use pairing::channel_id_from_contact;
+pub use pairing::ChannelRegistry;
+pub use pairing::safety_number;
+pub use pairing::SAFETY_NUMBER_GROUPS;
+pub use pairing::wrap_shared_key;
+pub use pairing::unwrap_shared_key;
+pub use pairing::ContactValidationError;
+pub use pairing::run_pairing_as_contactor;
+pub use pairing::run_pairing_as_requestor;
+pub use pairing::PairingFlowError;
+pub use pairing::PairingResponseError;
+pub use pairing::PairingSession;
+pub use pairing::PairingSessionError;
+pub use pairing::PairingRole;
 
 use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
 use prost::Message;
@@ -43,7 +72,7 @@ pub fn ts_create_contact_message(
 ) -> JsValue {
     let lib_result = pairing::create_contact_message(
         channel_id,
-        &transport_uri.to_string()
+        transport_uri
     );
 
     let wrapper = TsCreateContactMessageResult {
@@ -62,8 +91,8 @@ pub fn ts_produce_pairing_request_message(
     channel_id: u64,
     kind: u32,
     contact_message: &[u8]
-) -> JsValue {
-    let contact_msg = ContactMessage::decode(contact_message).unwrap();
+) -> Result<JsValue, String> {
+    let contact_msg = ContactMessage::decode(contact_message).map_err(|e| e.to_string())?;
     let lib_result = pairing::produce_pairing_request_message(
         channel_id,
         match kind {
@@ -73,7 +102,7 @@ pub fn ts_produce_pairing_request_message(
             _ => panic!("Invalid sender kind"),
         },
         &contact_msg
-    );
+    ).map_err(|e| e.to_string())?;
 
     let wrapper = TsProducePairingRequestMessage {
         pair_request_message: lib_result.0.encode_to_vec(),
@@ -84,19 +113,21 @@ pub fn ts_produce_pairing_request_message(
         }
     };
 
-    serde_wasm_bindgen::to_value(&wrapper).unwrap()
+    Ok(serde_wasm_bindgen::to_value(&wrapper).unwrap())
 }
 
 #[wasm_bindgen]
 pub fn ts_produce_pairing_response_message(
     kind: u32,
+    contact_message: &[u8],
     pair_request_message: &[u8],
     pairing_secret_key_material: &[u8]
-) -> JsValue {
-    let pair_request_msg = PairRequestMessage::decode(pair_request_message).unwrap();
+) -> Result<JsValue, String> {
+    let contact_msg = ContactMessage::decode(contact_message).map_err(|e| e.to_string())?;
+    let pair_request_msg = PairRequestMessage::decode(pair_request_message).map_err(|e| e.to_string())?;
     let pairing_sk = PairingSecretKeyMaterial::deserialize_uncompressed(
         &mut &pairing_secret_key_material[..]
-    ).unwrap();
+    ).map_err(|e| e.to_string())?;
 
     let lib_result = pairing::produce_pairing_response_message(
         match kind {
@@ -105,41 +136,45 @@ pub fn ts_produce_pairing_response_message(
             2 => SenderKind::Helper,
             _ => panic!("Invalid sender kind"),
         },
+        &contact_msg,
         &pair_request_msg,
         &pairing_sk
-    );
+    ).map_err(|e| e.to_string())?;
 
     let wrapper = TsProducePairingResponseMessage {
         pair_response_message: lib_result.0.encode_to_vec(),
         pairing_shared_key: lib_result.1.to_vec(),
     };
 
-    serde_wasm_bindgen::to_value(&wrapper).unwrap()
+    Ok(serde_wasm_bindgen::to_value(&wrapper).unwrap())
 }
 
 #[wasm_bindgen]
 pub fn ts_process_pairing_response_message(
     contact_message: &[u8],
+    pair_request_message: &[u8],
     pair_response_message: &[u8],
     pairing_secret_key_material: &[u8]
-) -> JsValue {
-    let contact_msg = ContactMessage::decode(contact_message).unwrap();
-    let pair_response_msg = PairResponseMessage::decode(pair_response_message).unwrap();
+) -> Result<JsValue, String> {
+    let contact_msg = ContactMessage::decode(contact_message).map_err(|e| e.to_string())?;
+    let pair_request_msg = PairRequestMessage::decode(pair_request_message).map_err(|e| e.to_string())?;
+    let pair_response_msg = PairResponseMessage::decode(pair_response_message).map_err(|e| e.to_string())?;
     let pairing_sk = PairingSecretKeyMaterial::deserialize_uncompressed(
         &mut &pairing_secret_key_material[..]
-    ).unwrap();
+    ).map_err(|e| e.to_string())?;
 
     let lib_result = pairing::process_pairing_response_message(
         &contact_msg,
+        &pair_request_msg,
         &pair_response_msg,
         &pairing_sk
-    );
+    ).map_err(|e| e.to_string())?;
 
     let wrapper = TsProcessPairingResponseMessage {
         pairing_shared_key: lib_result.to_vec(),
     };
 
-    serde_wasm_bindgen::to_value(&wrapper).unwrap()
+    Ok(serde_wasm_bindgen::to_value(&wrapper).unwrap())
 }
 
 #[cfg(test)]
@@ -1,37 +1,171 @@
 use rand::RngCore;
-use derec_cryptography::pairing;
+use thiserror::Error;
+use derec_cryptography::{channel, pairing, suite};
+use derec_cryptography::pairing::{pairing_ecies, pairing_mlkem, KeyExchange};
+use derec_cryptography::pairing::pairing_ecies::Secp256k1Ecies;
+use derec_cryptography::pairing::pairing_mlkem::MlKem;
+use derec_cryptography::pairing::pairing_x25519::X25519;
 use crate::protos::derec_proto;
+use crate::transport::Transport;
+use prost::Message;
+use sha2::{Digest, Sha256};
+use hmac::{Hmac, Mac};
+use crate::types::ChannelId;
+use std::collections::HashMap;
+
+/// Reasons a `ContactMessage` can fail validation, identifying the offending field.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ContactValidationError {
+    #[error("invalid ML-KEM encapsulation key length: expected {expected} bytes, got {actual}")]
+    InvalidMlKemKeyLength { expected: usize, actual: usize },
+    #[error("ECIES public key is not a valid curve point")]
+    InvalidEciesPublicKey,
+    #[error("transport URI is empty")]
+    EmptyTransportUri,
+    #[error("unsupported key exchange {0}")]
+    UnsupportedKeyExchange(i32),
+    #[error("unsupported message encoding {0}")]
+    UnsupportedMessageEncoding(i32),
+    #[error("public keys are not bound to the shared code")]
+    KeyBindingMismatch,
+    #[error("nonce is zero, which is reserved as unset")]
+    ZeroNonce,
+    #[error("key binding code is too short: expected at least {min} bytes, got {actual}")]
+    BindingCodeTooShort { min: usize, actual: usize },
+}
+
+/// Validates the fields of a received `ContactMessage` before it is used for pairing.
+///
+/// # Arguments
+///
+/// * `contact_message` - The `ContactMessage` received from the contactor.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the message is well-formed, or the `ContactValidationError` naming the first field that failed:
+/// - the ML-KEM encapsulation key must be exactly `ENCAPSULATION_KEY_SIZE` (1184) bytes,
+/// - the key exchange must be supported, and the ECIES public key must be valid for it (e.g. a point on secp256k1),
+/// - the transport URI must be non-empty,
+/// - the message encoding must be one this library speaks (see `contact_message::MessageEncodingEnum`),
+/// - the nonce must not be zero, which is reserved as "unset" so that it cannot stand in for every pairing.
+pub fn validate_contact_message(
+    contact_message: &derec_proto::ContactMessage
+) -> Result<(), ContactValidationError> {
+    let mlkem_key_len = contact_message.mlkem_encapsulation_key.len();
+    if mlkem_key_len != pairing_mlkem::ENCAPSULATION_KEY_SIZE {
+        return Err(ContactValidationError::InvalidMlKemKeyLength {
+            expected: pairing_mlkem::ENCAPSULATION_KEY_SIZE,
+            actual: mlkem_key_len,
+        });
+    }
+
+    let key_exchange = derec_proto::KeyExchangeEnum::try_from(contact_message.key_exchange)
+        .map_err(|_| ContactValidationError::UnsupportedKeyExchange(contact_message.key_exchange))?;
+    match key_exchange {
+        derec_proto::KeyExchangeEnum::Secp256k1 => pairing_ecies::validate_public_key(&contact_message.ecies_public_key),
+        derec_proto::KeyExchangeEnum::X25519 => X25519::validate_public_key(&contact_message.ecies_public_key),
+    }.map_err(|_| ContactValidationError::InvalidEciesPublicKey)?;
+
+    if contact_message.transport_uri.is_empty() {
+        return Err(ContactValidationError::EmptyTransportUri);
+    }
+
+    derec_proto::contact_message::MessageEncodingEnum::try_from(contact_message.message_encoding_type)
+        .map_err(|_| ContactValidationError::UnsupportedMessageEncoding(contact_message.message_encoding_type))?;
+
+    if contact_message.nonce == 0 {
+        return Err(ContactValidationError::ZeroNonce);
+    }
+
+    Ok(())
+}
 
 
 pub fn create_contact_message(
     channel_id: u64,
-    transport_uri: &String
+    transport_uri: &str
+) -> (derec_proto::ContactMessage, pairing::PairingSecretKeyMaterial) {
+    create_contact_message_with_key_exchange(channel_id, transport_uri, derec_proto::KeyExchangeEnum::Secp256k1)
+}
+
+/// Creates a contact message like `create_contact_message`, using the given classical key exchange.
+///
+/// The key exchange is recorded in the contact message, and the peer's pairing request
+/// copies it, so that both sides derive their keys with the same curve.
+///
+/// # Arguments
+///
+/// * `channel_id` - The identifier of the channel being paired.
+/// * `transport_uri` - The URI at which the contactor can be reached.
+/// * `key_exchange` - The classical key exchange to combine with ML-KEM.
+pub fn create_contact_message_with_key_exchange(
+    channel_id: u64,
+    transport_uri: &str,
+    key_exchange: derec_proto::KeyExchangeEnum
 ) -> (derec_proto::ContactMessage, pairing::PairingSecretKeyMaterial) {
     let mut rng = rand::rngs::OsRng;
 
     // generate the public key material
     let mut seed = [0u8; 32];
     rng.fill_bytes(&mut seed);
-    let (pk, sk) = pairing::contact_message(seed)
-        .expect("Failed to generate contact message");
+    let (pk, sk) = match key_exchange {
+        derec_proto::KeyExchangeEnum::Secp256k1 => pairing::contact_message_with::<MlKem, Secp256k1Ecies>(seed),
+        derec_proto::KeyExchangeEnum::X25519 => pairing::contact_message_with::<MlKem, X25519>(seed),
+    }.expect("Failed to generate contact message");
+
+    // zero is reserved as an unset nonce, so draw again in the (unlikely) event we get it
+    let nonce = loop {
+        match rng.next_u64() {
+            0 => continue,
+            nonce => break nonce,
+        }
+    };
 
     let contact_msg = derec_proto::ContactMessage {
         public_key_id: channel_id,
-        transport_uri: transport_uri.clone(),
+        transport_uri: transport_uri.to_string(),
         mlkem_encapsulation_key: pk.mlkem_encapsulation_key,
         ecies_public_key: pk.ecies_public_key,
-        nonce: rng.next_u64(),
-        message_encoding_type: 0,
+        nonce,
+        message_encoding_type: derec_proto::contact_message::MessageEncodingEnum::Protobuf.into(),
+        key_exchange: key_exchange.into(),
+        supported_suites: supported_suite_ids(key_exchange),
+        key_binding_mac: Vec::new(),
     };
 
     (contact_msg, sk)
 }
 
+/// Creates contact messages for several channels at once, e.g. when onboarding many helpers.
+///
+/// Each channel gets its own fresh key material, exactly as from `create_contact_message`.
+/// Key generation runs sequentially, since WebAssembly targets have no threads to spread it over.
+///
+/// # Arguments
+///
+/// * `channel_ids` - The identifiers of the channels being paired.
+/// * `transport_uri` - The URI at which the contactor can be reached, shared by all channels.
+///
+/// # Returns
+///
+/// Returns the contact message and secret key material of each channel, in the order of `channel_ids`.
+pub fn create_contact_messages(
+    channel_ids: &[u64],
+    transport_uri: &str
+) -> Vec<(derec_proto::ContactMessage, pairing::PairingSecretKeyMaterial)> {
+    channel_ids
+        .iter()
+        .map(|channel_id| create_contact_message(*channel_id, transport_uri))
+        .collect()
+}
+
 pub fn produce_pairing_request_message(
     channel_id: u64,
     kind: derec_proto::SenderKind,
     contact_message: &derec_proto::ContactMessage
-) -> (derec_proto::PairRequestMessage, pairing::PairingSecretKeyMaterial) {
+) -> Result<(derec_proto::PairRequestMessage, pairing::PairingSecretKeyMaterial), ContactValidationError> {
+    validate_contact_message(contact_message)?;
+
     // extract the PairingContactMessageMaterial from the contact message
     let pk = pairing::PairingContactMessageMaterial {
         mlkem_encapsulation_key: contact_message.mlkem_encapsulation_key.clone(),
@@ -43,8 +177,12 @@ pub fn produce_pairing_request_message(
     // generate the public key material
     let mut seed = [0u8; 32];
     rng.fill_bytes(&mut seed);
-    let (pk, sk) = pairing::pairing_request_message(seed, &pk)
-        .expect("Failed to generate pairing request message");
+    let key_exchange = key_exchange_of(contact_message.key_exchange)
+        .ok_or(ContactValidationError::UnsupportedKeyExchange(contact_message.key_exchange))?;
+    let (pk, sk) = match key_exchange {
+        derec_proto::KeyExchangeEnum::Secp256k1 => pairing::pairing_request_message_with::<MlKem, Secp256k1Ecies>(seed, &pk),
+        derec_proto::KeyExchangeEnum::X25519 => pairing::pairing_request_message_with::<MlKem, X25519>(seed, &pk),
+    }.expect("Failed to generate pairing request message");
 
     let request_msg = derec_proto::PairRequestMessage {
         sender_kind: kind.into(),
@@ -54,16 +192,166 @@ pub fn produce_pairing_request_message(
         nonce: contact_message.nonce,
         communication_info: None,
         parameter_range: None,
+        key_exchange: key_exchange.into(),
     };
 
-    (request_msg, sk)
+    Ok((request_msg, sk))
+}
+
+/// Produces a pairing request like `produce_pairing_request_message`, first checking that the contact
+/// message's public keys were bound together with `code` (see `bind_contact_keys`).
+///
+/// Without the binding, a relay could splice its own ECIES key into a contactor's contact message while
+/// keeping the contactor's ML-KEM key, leaving half of the hybrid pairing key under its control. With a code
+/// the two parties exchanged out of band (e.g. read aloud next to a QR code), both keys are known to come
+/// from the party holding the code.
+///
+/// # Errors
+///
+/// Returns `ContactValidationError::BindingCodeTooShort` if `code` is shorter than `MIN_BINDING_CODE_LEN`,
+/// `ContactValidationError::KeyBindingMismatch` if the contact message carries no key binding, or one that
+/// does not verify under `code`, besides the errors of `validate_contact_message`.
+pub fn produce_pairing_request_message_with_code(
+    channel_id: u64,
+    kind: derec_proto::SenderKind,
+    contact_message: &derec_proto::ContactMessage,
+    code: &[u8]
+) -> Result<(derec_proto::PairRequestMessage, pairing::PairingSecretKeyMaterial), ContactValidationError> {
+    check_binding_code(code)?;
+    validate_contact_message(contact_message)?;
+    key_binding_mac(contact_message, code)
+        .verify_slice(&contact_message.key_binding_mac)
+        .map_err(|_| ContactValidationError::KeyBindingMismatch)?;
+
+    produce_pairing_request_message(channel_id, kind, contact_message)
+}
+
+/// Domain separator of the MAC binding a contact message's public keys to each other.
+const KEY_BINDING_TAG: &[u8] = b"derec-contact-key-binding-v1";
+
+/// The fewest bytes a key binding code may have.
+///
+/// The key binding is public, so anyone who sees a contact message can test guesses of the code
+/// offline; a short code such as a 9-digit number would be found in seconds. Codes from
+/// `generate_binding_code` are longer than this and carry about 130 bits of entropy.
+pub const MIN_BINDING_CODE_LEN: usize = 32;
+
+/// The number of 5-digit groups in a code from `generate_binding_code`.
+pub const BINDING_CODE_GROUPS: usize = 8;
+
+/// Generates a fresh code for `bind_contact_keys`, to be shared out of band with the peer.
+///
+/// The code is `BINDING_CODE_GROUPS` groups of 5 uniformly random digits drawn from the OS RNG,
+/// separated by single spaces, e.g. `"04217 88301 ..."`, about 130 bits in total.
+pub fn generate_binding_code() -> String {
+    let mut rng = rand::rngs::OsRng;
+    (0..BINDING_CODE_GROUPS)
+        .map(|_| format!("{:05}", rand::Rng::gen_range(&mut rng, 0..100_000u32)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn check_binding_code(code: &[u8]) -> Result<(), ContactValidationError> {
+    if code.len() < MIN_BINDING_CODE_LEN {
+        return Err(ContactValidationError::BindingCodeTooShort { min: MIN_BINDING_CODE_LEN, actual: code.len() });
+    }
+    Ok(())
+}
+
+/// Binds a contact message's ML-KEM and ECIES public keys to a code shared out of band with the peer.
+///
+/// The binding is HMAC-SHA256 under `code` of both public keys, recorded in `key_binding_mac`; the peer
+/// checks it with `produce_pairing_request_message_with_code`. The code should be fresh for each pairing
+/// and hard to guess, since anyone holding it can produce a binding for keys of their own, and anyone
+/// seeing the binding can test guesses offline; `generate_binding_code` produces a suitable one.
+///
+/// # Errors
+///
+/// Returns `ContactValidationError::BindingCodeTooShort` if `code` is shorter than `MIN_BINDING_CODE_LEN`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::pairing::*;
+/// use derec_library::protos::derec_proto::SenderKind;
+///
+/// let (mut contact, _) = create_contact_message(1, "https://derec.example");
+/// let code = generate_binding_code();
+/// bind_contact_keys(&mut contact, code.as_bytes()).unwrap();
+/// assert!(produce_pairing_request_message_with_code(1, SenderKind::Helper, &contact, code.as_bytes()).is_ok());
+/// assert!(produce_pairing_request_message_with_code(1, SenderKind::Helper, &contact, &[b'0'; 32]).is_err());
+/// assert!(bind_contact_keys(&mut contact, b"492-113-870").is_err());
+/// ```
+pub fn bind_contact_keys(contact_message: &mut derec_proto::ContactMessage, code: &[u8]) -> Result<(), ContactValidationError> {
+    check_binding_code(code)?;
+    contact_message.key_binding_mac = key_binding_mac(contact_message, code).finalize().into_bytes().to_vec();
+    Ok(())
+}
+
+// the MAC over both public keys of a contact message, keyed by the out-of-band code
+fn key_binding_mac(contact_message: &derec_proto::ContactMessage, code: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(code).expect("HMAC accepts keys of any length");
+    mac.update(KEY_BINDING_TAG);
+    mac.update(&(contact_message.mlkem_encapsulation_key.len() as u32).to_be_bytes());
+    mac.update(&contact_message.mlkem_encapsulation_key);
+    mac.update(&contact_message.ecies_public_key);
+    mac
 }
 
+/// Produces the contactor's `PairResponseMessage` and derives the pairing key.
+///
+/// The key is bound to the pairing transcript (see `build_transcript`), which includes the contact
+/// message's transport URI, so a requestor that was shown a rewritten URI, or any other altered
+/// message, derives a different key.
+///
+/// # Arguments
+///
+/// * `kind` - The `SenderKind` of the contactor.
+/// * `contact_message` - The `ContactMessage` this contactor created for the pairing.
+/// * `pair_request_message` - The `PairRequestMessage` received from the requestor.
+/// * `pairing_secret_key_material` - The secret key material returned with the contact message.
+///
+/// # Errors
+///
+/// Returns `DerecPairingError::PairingStateError` if the contact message names a key exchange this
+/// library does not support, or any error from deriving the shared key, e.g. when the request carries
+/// a malformed ML-KEM ciphertext or ECIES public key.
 pub fn produce_pairing_response_message(
     kind: derec_proto::SenderKind,
+    contact_message: &derec_proto::ContactMessage,
     pair_request_message: &derec_proto::PairRequestMessage,
     pairing_secret_key_material: &pairing::PairingSecretKeyMaterial
-) -> (derec_proto::PairResponseMessage, pairing::PairingSharedKey) {
+) -> Result<(derec_proto::PairResponseMessage, pairing::PairingSharedKey), pairing::DerecPairingError> {
+    produce_response(kind, contact_message, pair_request_message, pairing_secret_key_material, None)
+}
+
+/// Produces a `PairResponseMessage` like `produce_pairing_response_message`, first recording the attempt
+/// with the contactor's decapsulation key in `limiter`.
+///
+/// A contactor answering pairing requests from the network should share one `DecapsulationLimiter`
+/// between all calls, so that no contact message is answered more often than the limiter allows.
+///
+/// # Errors
+///
+/// Returns `DerecPairingError::RateLimited` if the limiter allows no further attempts with the key,
+/// besides the errors of `produce_pairing_response_message`.
+pub fn produce_pairing_response_message_with_limiter(
+    kind: derec_proto::SenderKind,
+    contact_message: &derec_proto::ContactMessage,
+    pair_request_message: &derec_proto::PairRequestMessage,
+    pairing_secret_key_material: &pairing::PairingSecretKeyMaterial,
+    limiter: &pairing::DecapsulationLimiter
+) -> Result<(derec_proto::PairResponseMessage, pairing::PairingSharedKey), pairing::DerecPairingError> {
+    produce_response(kind, contact_message, pair_request_message, pairing_secret_key_material, Some(limiter))
+}
+
+fn produce_response(
+    kind: derec_proto::SenderKind,
+    contact_message: &derec_proto::ContactMessage,
+    pair_request_message: &derec_proto::PairRequestMessage,
+    pairing_secret_key_material: &pairing::PairingSecretKeyMaterial,
+    limiter: Option<&pairing::DecapsulationLimiter>
+) -> Result<(derec_proto::PairResponseMessage, pairing::PairingSharedKey), pairing::DerecPairingError> {
     // extract the PairingContactMessageMaterial from the contact message
     let pairing_request = pairing::PairingRequestMessageMaterial {
         mlkem_ciphertext: pair_request_message.mlkem_ciphertext.clone(),
@@ -78,29 +366,812 @@ pub fn produce_pairing_response_message(
         parameter_range: None,
     };
 
-    // generate the shared key material
-    let sk = pairing::finish_pairing_contactor(
-        &pairing_secret_key_material,
-        &pairing_request
-    ).expect("Failed to finish pairing contactor");
+    // generate the shared key material, bound to everything exchanged (including the transport URI we advertised)
+    let context = build_transcript(contact_message, pair_request_message, &response_msg);
+    let key_exchange = key_exchange_of(contact_message.key_exchange).ok_or(pairing::DerecPairingError::PairingStateError)?;
+    let sk = match (key_exchange, limiter) {
+        (derec_proto::KeyExchangeEnum::Secp256k1, None) => pairing::finish_pairing_contactor_with::<MlKem, Secp256k1Ecies>(
+            pairing_secret_key_material,
+            &pairing_request,
+            Some(&context)
+        ),
+        (derec_proto::KeyExchangeEnum::Secp256k1, Some(limiter)) => pairing::finish_pairing_contactor_with_limiter::<MlKem, Secp256k1Ecies>(
+            pairing_secret_key_material,
+            &pairing_request,
+            Some(&context),
+            limiter
+        ),
+        (derec_proto::KeyExchangeEnum::X25519, None) => pairing::finish_pairing_contactor_with::<MlKem, X25519>(
+            pairing_secret_key_material,
+            &pairing_request,
+            Some(&context)
+        ),
+        (derec_proto::KeyExchangeEnum::X25519, Some(limiter)) => pairing::finish_pairing_contactor_with_limiter::<MlKem, X25519>(
+            pairing_secret_key_material,
+            &pairing_request,
+            Some(&context),
+            limiter
+        ),
+    }?;
+
+    Ok((response_msg, sk))
+}
 
-    (response_msg, sk)
+/// Produces a `PairResponseMessage` declining a pairing request, with a human-readable reason.
+///
+/// The requestor derives no key from such a response; `run_pairing_as_requestor` and
+/// `PairingSession::advance_with_response` report the memo as the reason the pairing was rejected.
+///
+/// # Arguments
+///
+/// * `kind` - The `SenderKind` of the contactor.
+/// * `pair_request_message` - The `PairRequestMessage` being declined.
+/// * `memo` - Why the request is declined, e.g. "channel already paired".
+pub fn produce_pairing_rejection_message(
+    kind: derec_proto::SenderKind,
+    pair_request_message: &derec_proto::PairRequestMessage,
+    memo: &str
+) -> derec_proto::PairResponseMessage {
+    derec_proto::PairResponseMessage {
+        sender_kind: kind.into(),
+        result: Some(derec_proto::Result { status: derec_proto::StatusEnum::Fail as i32, memo: memo.to_string() }),
+        nonce: pair_request_message.nonce,
+        communication_info: None,
+        parameter_range: None,
+    }
 }
 
+// the contactor's memo if it declined the pairing, i.e. answered with a status other than `Ok`
+fn rejection_memo(pair_response_message: &derec_proto::PairResponseMessage) -> Option<String> {
+    pair_response_message
+        .result
+        .as_ref()
+        .filter(|result| result.status != derec_proto::StatusEnum::Ok as i32)
+        .map(|result| result.memo.clone())
+}
+
+/// Returns the `SenderKind` the peer declared when it requested pairing.
+///
+/// A helper accepting a pairing request should record this alongside the channel, since it
+/// decides whether the peer may later retrieve shares (see `recovery::generate_share_response`).
+/// Unknown values are treated as `SharerNonRecovery`, the least privileged sharer kind.
+///
+/// # Arguments
+///
+/// * `pair_request_message` - The `PairRequestMessage` received from the requestor.
+pub fn requestor_sender_kind(pair_request_message: &derec_proto::PairRequestMessage) -> derec_proto::SenderKind {
+    derec_proto::SenderKind::try_from(pair_request_message.sender_kind)
+        .unwrap_or(derec_proto::SenderKind::SharerNonRecovery)
+}
+
+/// Processes the contactor's `PairResponseMessage` and derives the requestor's pairing key.
+///
+/// The response must carry the nonce of the contact message this pairing started from; a
+/// response taken from a different pairing session is rejected rather than yielding a key
+/// that silently disagrees with the contactor's.
+///
+/// Like the contactor's, the key is bound to the pairing transcript (see `build_transcript`).
+///
+/// # Arguments
+///
+/// * `contact_message` - The `ContactMessage` the requestor paired against.
+/// * `pair_request_message` - The `PairRequestMessage` the requestor sent.
+/// * `pair_response_message` - The `PairResponseMessage` received from the contactor.
+/// * `pairing_secret_key_material` - The secret key material returned with the pairing request.
+///
+/// # Errors
+///
+/// Returns `PairingResponseError::NonceMismatch` if the response's nonce does not match the contact
+/// message's, `PairingResponseError::Rejected` with the contactor's memo if it declined the pairing
+/// (see `produce_pairing_rejection_message`), or `PairingResponseError::Pairing` if the key cannot be
+/// derived, e.g. because the contact message names an unsupported key exchange.
 pub fn process_pairing_response_message(
     contact_message: &derec_proto::ContactMessage,
-    _pair_response_message: &derec_proto::PairResponseMessage,
+    pair_request_message: &derec_proto::PairRequestMessage,
+    pair_response_message: &derec_proto::PairResponseMessage,
     pairing_secret_key_material: &pairing::PairingSecretKeyMaterial
-) -> pairing::PairingSharedKey {
+) -> Result<pairing::PairingSharedKey, PairingResponseError> {
+    // the response echoes the nonce of the request, which echoes the contact message's
+    if pair_response_message.nonce != contact_message.nonce {
+        return Err(PairingResponseError::NonceMismatch);
+    }
+    if let Some(memo) = rejection_memo(pair_response_message) {
+        return Err(PairingResponseError::Rejected(memo));
+    }
+
     let pk = pairing::PairingContactMessageMaterial {
         mlkem_encapsulation_key: contact_message.mlkem_encapsulation_key.clone(),
         ecies_public_key: contact_message.ecies_public_key.clone(),
     };
 
-    let sk = pairing::finish_pairing_requestor(
-        &pairing_secret_key_material,
-        &pk
-    ).expect("Failed to finish pairing helper");
+    // bind the key to everything exchanged, including the transport URI we were shown
+    let context = build_transcript(contact_message, pair_request_message, pair_response_message);
+    let key_exchange = key_exchange_of(contact_message.key_exchange)
+        .ok_or(PairingResponseError::Pairing(pairing::DerecPairingError::PairingStateError))?;
+    match key_exchange {
+        derec_proto::KeyExchangeEnum::Secp256k1 => pairing::finish_pairing_requestor_with::<Secp256k1Ecies>(
+            pairing_secret_key_material,
+            &pk,
+            Some(&context)
+        ),
+        derec_proto::KeyExchangeEnum::X25519 => pairing::finish_pairing_requestor_with::<X25519>(
+            pairing_secret_key_material,
+            &pk,
+            Some(&context)
+        ),
+    }.map_err(PairingResponseError::Pairing)
+}
+
+/// Reasons `process_pairing_response_message` derives no pairing key.
+#[derive(Error, Debug)]
+pub enum PairingResponseError {
+    #[error("pairing message nonce does not match the contact message")]
+    NonceMismatch,
+    #[error("pairing rejected: {0}")]
+    Rejected(String),
+    #[error("failed to derive the pairing key")]
+    Pairing(pairing::DerecPairingError),
+}
+
+impl<E> From<PairingResponseError> for PairingFlowError<E> {
+    fn from(error: PairingResponseError) -> Self {
+        match error {
+            PairingResponseError::NonceMismatch => PairingFlowError::NonceMismatch,
+            PairingResponseError::Rejected(memo) => PairingFlowError::Rejected(memo),
+            PairingResponseError::Pairing(error) => PairingFlowError::Pairing(error),
+        }
+    }
+}
+
+impl From<PairingResponseError> for PairingSessionError {
+    fn from(error: PairingResponseError) -> Self {
+        match error {
+            PairingResponseError::NonceMismatch => PairingSessionError::NonceMismatch,
+            PairingResponseError::Rejected(memo) => PairingSessionError::Rejected(memo),
+            PairingResponseError::Pairing(error) => PairingSessionError::Pairing(error),
+        }
+    }
+}
+
+/// Reasons a pairing run over a `Transport` can fail.
+#[derive(Error, Debug)]
+pub enum PairingFlowError<E> {
+    #[error("transport error")]
+    Transport(E),
+    #[error("failed to decode pairing message: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("invalid contact message: {0}")]
+    InvalidContact(#[from] ContactValidationError),
+    #[error("pairing message nonce does not match the contact message")]
+    NonceMismatch,
+    #[error("pairing rejected: {0}")]
+    Rejected(String),
+    #[error("failed to derive the pairing key")]
+    Pairing(pairing::DerecPairingError),
+}
+
+/// Runs the contactor's side of pairing over a transport, returning the derived shared key.
+///
+/// This creates a contact message and sends it to the peer. It then waits for the peer's
+/// `PairRequestMessage`, answers with a `PairResponseMessage`, and derives the key. In a real
+/// deployment the contact message is often shown out of band (e.g. as a QR code); here it is
+/// sent over `transport` as the first message.
+///
+/// # Arguments
+///
+/// * `channel_id` - The identifier of the channel being paired.
+/// * `transport_uri` - The URI at which the contactor can be reached, placed in the contact message.
+/// * `kind` - The `SenderKind` of the contactor.
+/// * `transport` - The transport to the peer.
+///
+/// # Errors
+///
+/// Returns `PairingFlowError` if the transport fails, the request cannot be decoded, its nonce
+/// does not match the contact message, or the pairing key cannot be derived from it.
+pub async fn run_pairing_as_contactor<T: Transport>(
+    channel_id: u64,
+    transport_uri: &str,
+    kind: derec_proto::SenderKind,
+    transport: &mut T
+) -> Result<pairing::PairingSharedKey, PairingFlowError<T::Error>> {
+    let (contact_msg, sk) = create_contact_message(channel_id, transport_uri);
+    transport.send(&contact_msg.encode_to_vec()).await.map_err(PairingFlowError::Transport)?;
+
+    let request_bytes = transport.recv().await.map_err(PairingFlowError::Transport)?;
+    let request_msg = derec_proto::PairRequestMessage::decode(request_bytes.as_slice())?;
+    if request_msg.nonce != contact_msg.nonce {
+        return Err(PairingFlowError::NonceMismatch);
+    }
+
+    let (response_msg, shared_key) =
+        produce_pairing_response_message(kind, &contact_msg, &request_msg, &sk).map_err(PairingFlowError::Pairing)?;
+    transport.send(&response_msg.encode_to_vec()).await.map_err(PairingFlowError::Transport)?;
+
+    Ok(shared_key)
+}
+
+/// Runs the requestor's side of pairing over a transport, returning the derived shared key.
+///
+/// This is the counterpart of `run_pairing_as_contactor`. It waits for the contact message,
+/// sends a `PairRequestMessage`, and derives the key once the `PairResponseMessage` arrives.
+///
+/// # Arguments
+///
+/// * `channel_id` - The identifier of the channel being paired.
+/// * `kind` - The `SenderKind` of the requestor.
+/// * `transport` - The transport to the peer.
+///
+/// # Errors
+///
+/// Returns `PairingFlowError` if the transport fails, a message cannot be decoded, or the
+/// contact message is invalid.
+pub async fn run_pairing_as_requestor<T: Transport>(
+    channel_id: u64,
+    kind: derec_proto::SenderKind,
+    transport: &mut T
+) -> Result<pairing::PairingSharedKey, PairingFlowError<T::Error>> {
+    let contact_bytes = transport.recv().await.map_err(PairingFlowError::Transport)?;
+    let contact_msg = derec_proto::ContactMessage::decode(contact_bytes.as_slice())?;
+
+    let (request_msg, sk) = produce_pairing_request_message(channel_id, kind, &contact_msg)?;
+    transport.send(&request_msg.encode_to_vec()).await.map_err(PairingFlowError::Transport)?;
+
+    let response_bytes = transport.recv().await.map_err(PairingFlowError::Transport)?;
+    let response_msg = derec_proto::PairResponseMessage::decode(response_bytes.as_slice())?;
+
+    Ok(process_pairing_response_message(&contact_msg, &request_msg, &response_msg, &sk)?)
+}
+
+/// Which side of a pairing a `PairingSession` plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairingRole {
+    /// creates the contact message and answers the pairing request
+    Contactor,
+    /// answers the contact message and processes the pairing response
+    Requestor,
+}
+
+/// Reasons a `PairingSession` cannot advance or be restored.
+#[derive(Error, Debug)]
+pub enum PairingSessionError {
+    #[error("message does not fit the session's role or current step")]
+    UnexpectedMessage,
+    #[error("invalid contact message: {0}")]
+    InvalidContact(#[from] ContactValidationError),
+    #[error("pairing message nonce does not match the contact message")]
+    NonceMismatch,
+    #[error("pairing rejected: {0}")]
+    Rejected(String),
+    #[error("failed to derive the pairing key")]
+    Pairing(pairing::DerecPairingError),
+    #[error("malformed pairing session")]
+    Malformed,
+}
+
+/// Format version leading every serialized `PairingSession`.
+const PAIRING_SESSION_FORMAT: u8 = 1;
+
+/// The state of one side of a pairing, kept across its messages and across restarts.
+///
+/// A contactor starts with `PairingSession::contactor`, sends the returned contact message and
+/// calls `advance_with_request` once the pairing request arrives. A requestor starts with
+/// `PairingSession::requestor`, then calls `advance_with_contact` and `advance_with_response`.
+/// Between steps, `serialize` packs everything the session needs (including its secret key
+/// material) into one blob, which `deserialize` restores; the blob must be stored as securely
+/// as the pairing key itself.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::pairing::pairing::PairingSession;
+/// use derec_library::protos::derec_proto::SenderKind;
+///
+/// let (mut contactor, contact) = PairingSession::contactor(1, "https://derec.example", SenderKind::Helper);
+/// let mut requestor = PairingSession::requestor(1, SenderKind::SharerRecovery);
+/// let request = requestor.advance_with_contact(&contact).unwrap();
+///
+/// // the requestor restarts while waiting for the response
+/// let mut requestor = PairingSession::deserialize(&requestor.serialize()).unwrap();
+/// let response = contactor.advance_with_request(&request).unwrap();
+/// let key = requestor.advance_with_response(&response).unwrap();
+/// assert_eq!(contactor.shared_key(), Some(key));
+/// ```
+pub struct PairingSession {
+    role: PairingRole,
+    channel_id: ChannelId,
+    kind: derec_proto::SenderKind,
+    transport_uri: String,
+    secret_key_material: Option<pairing::PairingSecretKeyMaterial>,
+    contact_message: Option<derec_proto::ContactMessage>,
+    pair_request_message: Option<derec_proto::PairRequestMessage>,
+    shared_key: Option<pairing::PairingSharedKey>,
+}
+
+// the bincode form of a `PairingSession`; protocol messages are kept in their protobuf encoding
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PairingSessionBlob {
+    format: u8,
+    is_contactor: bool,
+    channel_id: u64,
+    kind: i32,
+    transport_uri: String,
+    secret_key_material: Option<SecretKeyMaterialBlob>,
+    contact_message: Option<Vec<u8>>,
+    pair_request_message: Option<Vec<u8>>,
+    shared_key: Option<[u8; 32]>,
+}
+
+// the fields of a `PairingSecretKeyMaterial`
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SecretKeyMaterialBlob {
+    mlkem_decapsulation_key: Option<Vec<u8>>,
+    mlkem_shared_secret: Option<[u8; 32]>,
+    ecies_secret_key: Vec<u8>,
+    mlkem_seed: Option<Vec<u8>>,
+}
+
+impl PairingSession {
+    /// Starts a pairing as the contactor, returning the session and the contact message to show the peer.
+    pub fn contactor(
+        channel_id: ChannelId,
+        transport_uri: &str,
+        kind: derec_proto::SenderKind
+    ) -> (Self, derec_proto::ContactMessage) {
+        let (contact_message, secret_key_material) = create_contact_message(channel_id, transport_uri);
+        let session = PairingSession {
+            role: PairingRole::Contactor,
+            channel_id,
+            kind,
+            transport_uri: transport_uri.to_string(),
+            secret_key_material: Some(secret_key_material),
+            contact_message: Some(contact_message.clone()),
+            pair_request_message: None,
+            shared_key: None,
+        };
+        (session, contact_message)
+    }
+
+    /// Starts a pairing as the requestor, to be advanced once the contact message is received.
+    pub fn requestor(channel_id: ChannelId, kind: derec_proto::SenderKind) -> Self {
+        PairingSession {
+            role: PairingRole::Requestor,
+            channel_id,
+            kind,
+            transport_uri: String::new(),
+            secret_key_material: None,
+            contact_message: None,
+            pair_request_message: None,
+            shared_key: None,
+        }
+    }
+
+    /// The side of the pairing this session plays.
+    pub fn role(&self) -> PairingRole {
+        self.role
+    }
+
+    /// The identifier of the channel being paired.
+    pub fn channel_id(&self) -> ChannelId {
+        self.channel_id
+    }
+
+    /// The transport URI of the contactor; empty until a requestor has seen the contact message.
+    pub fn transport_uri(&self) -> &str {
+        &self.transport_uri
+    }
+
+    /// The pairing key, once the session has completed.
+    pub fn shared_key(&self) -> Option<pairing::PairingSharedKey> {
+        self.shared_key
+    }
+
+    /// Answers the contactor's contact message, returning the `PairRequestMessage` to send back.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PairingSessionError::UnexpectedMessage` unless this is a requestor that has not yet
+    /// seen a contact message, or `PairingSessionError::InvalidContact` if the contact message is invalid.
+    pub fn advance_with_contact(
+        &mut self,
+        contact_message: &derec_proto::ContactMessage
+    ) -> Result<derec_proto::PairRequestMessage, PairingSessionError> {
+        if self.role != PairingRole::Requestor || self.contact_message.is_some() {
+            return Err(PairingSessionError::UnexpectedMessage);
+        }
+
+        let (request, secret_key_material) = produce_pairing_request_message(self.channel_id, self.kind, contact_message)?;
+        self.transport_uri = contact_message.transport_uri.clone();
+        self.secret_key_material = Some(secret_key_material);
+        self.contact_message = Some(contact_message.clone());
+        self.pair_request_message = Some(request.clone());
+        Ok(request)
+    }
+
+    /// Answers the requestor's pairing request, completing the contactor's side of the pairing.
+    ///
+    /// Returns the `PairResponseMessage` to send back; the pairing key is then available from `shared_key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PairingSessionError::UnexpectedMessage` unless this is a contactor still waiting for the
+    /// request, `PairingSessionError::NonceMismatch` if the request belongs to another contact message,
+    /// or `PairingSessionError::Pairing` if the key cannot be derived from the request.
+    pub fn advance_with_request(
+        &mut self,
+        pair_request_message: &derec_proto::PairRequestMessage
+    ) -> Result<derec_proto::PairResponseMessage, PairingSessionError> {
+        if self.role != PairingRole::Contactor || self.shared_key.is_some() {
+            return Err(PairingSessionError::UnexpectedMessage);
+        }
+        let (Some(contact_message), Some(secret_key_material)) = (&self.contact_message, &self.secret_key_material) else {
+            return Err(PairingSessionError::UnexpectedMessage);
+        };
+        if pair_request_message.nonce != contact_message.nonce {
+            return Err(PairingSessionError::NonceMismatch);
+        }
+
+        let (response, shared_key) = produce_pairing_response_message(
+            self.kind,
+            contact_message,
+            pair_request_message,
+            secret_key_material
+        ).map_err(PairingSessionError::Pairing)?;
+        self.pair_request_message = Some(pair_request_message.clone());
+        self.shared_key = Some(shared_key);
+        Ok(response)
+    }
+
+    /// Processes the contactor's pairing response, completing the requestor's side of the pairing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PairingSessionError::UnexpectedMessage` unless this is a requestor that has sent its
+    /// request and not yet completed, `PairingSessionError::NonceMismatch` if the response belongs to
+    /// another pairing, `PairingSessionError::Rejected` with the contactor's memo if it declined the
+    /// pairing, or `PairingSessionError::Pairing` if the key cannot be derived.
+    pub fn advance_with_response(
+        &mut self,
+        pair_response_message: &derec_proto::PairResponseMessage
+    ) -> Result<pairing::PairingSharedKey, PairingSessionError> {
+        if self.role != PairingRole::Requestor || self.shared_key.is_some() {
+            return Err(PairingSessionError::UnexpectedMessage);
+        }
+        let (Some(contact_message), Some(request), Some(secret_key_material)) =
+            (&self.contact_message, &self.pair_request_message, &self.secret_key_material) else {
+            return Err(PairingSessionError::UnexpectedMessage);
+        };
+        let shared_key = process_pairing_response_message(contact_message, request, pair_response_message, secret_key_material)?;
+        self.shared_key = Some(shared_key);
+        Ok(shared_key)
+    }
+
+    /// Packs the session into a single blob, from which `deserialize` restores it.
+    pub fn serialize(&self) -> Vec<u8> {
+        let blob = PairingSessionBlob {
+            format: PAIRING_SESSION_FORMAT,
+            is_contactor: self.role == PairingRole::Contactor,
+            channel_id: self.channel_id,
+            kind: self.kind as i32,
+            transport_uri: self.transport_uri.clone(),
+            secret_key_material: self.secret_key_material.as_ref().map(|sk| SecretKeyMaterialBlob {
+                mlkem_decapsulation_key: sk.mlkem_decapsulation_key.clone(),
+                mlkem_shared_secret: sk.mlkem_shared_secret,
+                ecies_secret_key: sk.ecies_secret_key.clone(),
+                mlkem_seed: sk.mlkem_seed.clone(),
+            }),
+            contact_message: self.contact_message.as_ref().map(|msg| msg.encode_to_vec()),
+            pair_request_message: self.pair_request_message.as_ref().map(|msg| msg.encode_to_vec()),
+            shared_key: self.shared_key,
+        };
+
+        bincode::serialize(&blob).expect("serializing into a Vec cannot fail")
+    }
+
+    /// Restores a session packed by `serialize`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PairingSessionError::Malformed` if the blob does not decode to a session.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, PairingSessionError> {
+        let blob: PairingSessionBlob = bincode::deserialize(bytes).map_err(|_| PairingSessionError::Malformed)?;
+        if blob.format != PAIRING_SESSION_FORMAT {
+            return Err(PairingSessionError::Malformed);
+        }
+
+        let kind = derec_proto::SenderKind::try_from(blob.kind).map_err(|_| PairingSessionError::Malformed)?;
+        let contact_message = blob.contact_message
+            .map(|bytes| derec_proto::ContactMessage::decode(bytes.as_slice()))
+            .transpose()
+            .map_err(|_| PairingSessionError::Malformed)?;
+        let pair_request_message = blob.pair_request_message
+            .map(|bytes| derec_proto::PairRequestMessage::decode(bytes.as_slice()))
+            .transpose()
+            .map_err(|_| PairingSessionError::Malformed)?;
+
+        Ok(PairingSession {
+            role: if blob.is_contactor { PairingRole::Contactor } else { PairingRole::Requestor },
+            channel_id: blob.channel_id,
+            kind,
+            transport_uri: blob.transport_uri,
+            secret_key_material: blob.secret_key_material.map(|sk| pairing::PairingSecretKeyMaterial {
+                mlkem_decapsulation_key: sk.mlkem_decapsulation_key,
+                mlkem_shared_secret: sk.mlkem_shared_secret,
+                ecies_secret_key: sk.ecies_secret_key,
+                mlkem_seed: sk.mlkem_seed,
+            }),
+            contact_message,
+            pair_request_message,
+            shared_key: blob.shared_key,
+        })
+    }
+}
+
+/// Serializes a `ContactMessage` into a fixed, canonical byte layout.
+///
+/// Protobuf admits several encodings of the same message (field order, unknown fields, non-minimal
+/// varints), so hashing the wire bytes could give two parties different results for the same contact.
+/// This layout depends only on the field values: every field is written in field-number order,
+/// integers as fixed-width big-endian, and byte strings with a 4-byte big-endian length prefix.
+/// The one field left out is `key_binding_mac` (see `bind_contact_keys`), which authenticates the
+/// public keys rather than describing the contact, so binding a message does not change its encoding
+/// (nor the channel id derived from it).
+///
+/// # Arguments
+///
+/// * `contact_message` - The `ContactMessage` to encode.
+pub fn canonical_encode(contact_message: &derec_proto::ContactMessage) -> Vec<u8> {
+    fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(bytes);
+    }
+
+    let mut out = b"derec-contact-v1".to_vec();
+    put_bytes(&mut out, &contact_message.mlkem_encapsulation_key);
+    put_bytes(&mut out, &contact_message.ecies_public_key);
+    out.extend_from_slice(&contact_message.public_key_id.to_be_bytes());
+    out.extend_from_slice(&contact_message.nonce.to_be_bytes());
+    put_bytes(&mut out, contact_message.transport_uri.as_bytes());
+    out.extend_from_slice(&contact_message.message_encoding_type.to_be_bytes());
+    out.extend_from_slice(&contact_message.key_exchange.to_be_bytes());
+    out.extend_from_slice(&(contact_message.supported_suites.len() as u32).to_be_bytes());
+    for suite in &contact_message.supported_suites {
+        out.extend_from_slice(&suite.to_be_bytes());
+    }
+    out
+}
+
+/// Serializes the public messages of a pairing into a canonical transcript, for audit logs.
+///
+/// The transcript holds everything the two parties exchanged (public keys, the ML-KEM ciphertext,
+/// nonces and the negotiated parameters) and nothing secret. It starts with `canonical_encode` of the
+/// contact message; the request and response fields follow in field-number order, with the same
+/// layout. Nested messages are written as their protobuf encoding, which is deterministic for
+/// messages decoded and re-encoded by this library, prefixed with a presence byte.
+///
+/// Both parties feed this transcript into the pairing key derivation, so a logged transcript is
+/// exactly what the key was bound to, and altering any message in transit makes the keys disagree.
+///
+/// # Arguments
+///
+/// * `contact` - The `ContactMessage` the pairing was started from.
+/// * `request` - The requestor's `PairRequestMessage`.
+/// * `response` - The contactor's `PairResponseMessage`.
+pub fn build_transcript(
+    contact: &derec_proto::ContactMessage,
+    request: &derec_proto::PairRequestMessage,
+    response: &derec_proto::PairResponseMessage
+) -> Vec<u8> {
+    fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(bytes);
+    }
+    fn put_message<M: Message>(out: &mut Vec<u8>, message: Option<&M>) {
+        match message {
+            None => out.push(0),
+            Some(message) => {
+                out.push(1);
+                put_bytes(out, &message.encode_to_vec());
+            }
+        }
+    }
+
+    let mut out = b"derec-pairing-transcript-v1".to_vec();
+    put_bytes(&mut out, &canonical_encode(contact));
+
+    out.extend_from_slice(&request.sender_kind.to_be_bytes());
+    put_bytes(&mut out, &request.mlkem_ciphertext);
+    put_bytes(&mut out, &request.ecies_public_key);
+    out.extend_from_slice(&request.public_key_id.to_be_bytes());
+    put_message(&mut out, request.communication_info.as_ref());
+    out.extend_from_slice(&request.nonce.to_be_bytes());
+    put_message(&mut out, request.parameter_range.as_ref());
+    out.extend_from_slice(&request.key_exchange.to_be_bytes());
+
+    put_message(&mut out, response.result.as_ref());
+    out.extend_from_slice(&response.sender_kind.to_be_bytes());
+    put_message(&mut out, response.communication_info.as_ref());
+    out.extend_from_slice(&response.nonce.to_be_bytes());
+    put_message(&mut out, response.parameter_range.as_ref());
+    out
+}
+
+/// Hashes a transcript from `build_transcript` with SHA-256, e.g. to log a compact fingerprint of a pairing.
+///
+/// # Arguments
+///
+/// * `transcript` - The transcript to hash.
+pub fn transcript_hash(transcript: &[u8]) -> [u8; 32] {
+    Sha256::digest(transcript).into()
+}
+
+/// Derives the `ChannelId` of a pairing from its contact message.
+///
+/// The identifier is the first 8 bytes (big-endian) of the SHA-256 hash of `canonical_encode`,
+/// so both parties arrive at the same `ChannelId` however the contact message was encoded in transit.
+///
+/// # Arguments
+///
+/// * `contact_message` - The `ContactMessage` the pairing was started from.
+pub fn channel_id_from_contact(contact_message: &derec_proto::ContactMessage) -> ChannelId {
+    let digest = Sha256::digest(canonical_encode(contact_message));
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Maps the `ChannelId`s derived by `channel_id_from_contact` back to the contact messages they came from.
+///
+/// An application that identifies channels by `channel_id_from_contact` can register each contact message
+/// as it pairs, and later look up the pairing's metadata (transport URI, key exchange, ...) by channel.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::pairing::*;
+/// let (contact, _) = create_contact_message(1, "https://derec.example");
+/// let mut registry = ChannelRegistry::new();
+/// let channel = registry.register(&contact);
+/// assert_eq!(registry.lookup(channel).unwrap().transport_uri, "https://derec.example");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChannelRegistry {
+    contacts: HashMap<ChannelId, derec_proto::ContactMessage>,
+}
+
+impl ChannelRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a contact message, returning the `ChannelId` it derives.
+    ///
+    /// Registering the same contact message again (in any encoding) replaces the earlier copy.
+    pub fn register(&mut self, contact_message: &derec_proto::ContactMessage) -> ChannelId {
+        let channel = channel_id_from_contact(contact_message);
+        self.contacts.insert(channel, contact_message.clone());
+        channel
+    }
+
+    /// Returns the contact message registered for `channel`, if any.
+    pub fn lookup(&self, channel: ChannelId) -> Option<&derec_proto::ContactMessage> {
+        self.contacts.get(&channel)
+    }
+}
+
+const SAFETY_NUMBER_TAG: &[u8] = b"derec-safety-number-v1";
+
+/// Number of 5-digit groups in a `safety_number`.
+pub const SAFETY_NUMBER_GROUPS: usize = 6;
+
+/// Derives a short numeric code from a pairing key, for the two parties to compare out of band.
+///
+/// After pairing, each party displays the code and they compare it, e.g. by reading it aloud over a call.
+/// A man in the middle ends up with a different key with each party, so the codes differ. The code is
+/// the SHA-256 hash of a domain tag and the key, split into `SAFETY_NUMBER_GROUPS` 5-byte chunks, each
+/// reduced modulo 100000 and printed as a zero-padded group of 5 digits (about 100 bits in total).
+/// The hash reveals nothing usable about the key itself.
+///
+/// # Arguments
+///
+/// * `shared` - The shared key both parties derived from the pairing.
+///
+/// # Returns
+///
+/// Returns the groups separated by single spaces, e.g. `"04217 88301 ..."`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::pairing::safety_number;
+/// let code = safety_number(&[7u8; 32]);
+/// assert_eq!(code.len(), 6 * 5 + 5);
+/// assert_eq!(code, safety_number(&[7u8; 32]));
+/// ```
+pub fn safety_number(shared: &pairing::PairingSharedKey) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(SAFETY_NUMBER_TAG);
+    hasher.update(shared);
+    let digest = hasher.finalize();
+
+    digest
+        .chunks_exact(5)
+        .take(SAFETY_NUMBER_GROUPS)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Encrypts a `PairingSharedKey` under a device-held key, for storage at rest.
+///
+/// The key is encrypted with AES-256-GCM (`channel::encrypt_message`) under a fresh random nonce,
+/// so any modification of the output is detected by `unwrap_shared_key`.
+///
+/// # Arguments
+///
+/// * `shared` - The pairing key to protect.
+/// * `wrapping_key` - The 256-bit device key to encrypt it under.
+///
+/// # Returns
+///
+/// Returns `nonce || ciphertext || tag`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::pairing::{wrap_shared_key, unwrap_shared_key};
+/// let device_key = [9u8; 32];
+/// let wrapped = wrap_shared_key(&[1u8; 32], &device_key);
+/// assert_eq!(unwrap_shared_key(&wrapped, &device_key), Ok([1u8; 32]));
+/// ```
+pub fn wrap_shared_key(
+    shared: &pairing::PairingSharedKey,
+    wrapping_key: &[u8; 32]
+) -> Vec<u8> {
+    let mut nonce = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+    channel::encrypt_message(shared, wrapping_key, &nonce)
+        .expect("Failed to wrap pairing shared key")
+}
+
+/// Decrypts a `PairingSharedKey` wrapped by `wrap_shared_key`.
+///
+/// # Arguments
+///
+/// * `wrapped` - The output of `wrap_shared_key`.
+/// * `wrapping_key` - The device key the pairing key was wrapped under.
+///
+/// # Returns
+///
+/// Returns the pairing key, or an error string if authentication fails (wrong key or
+/// tampered data) or the decrypted key has the wrong length.
+pub fn unwrap_shared_key(
+    wrapped: &[u8],
+    wrapping_key: &[u8; 32]
+) -> Result<pairing::PairingSharedKey, &'static str> {
+    let shared = channel::decrypt_message(wrapped, wrapping_key)
+        .map_err(|_| "Failed to unwrap pairing shared key: wrong key or tampered data")?;
+
+    shared.try_into().map_err(|_| "Unwrapped pairing shared key has the wrong length")
+}
+
+// the suites usable with contact keys generated for `key_exchange`
+fn supported_suite_ids(key_exchange: derec_proto::KeyExchangeEnum) -> Vec<u32> {
+    let kex = match key_exchange {
+        derec_proto::KeyExchangeEnum::Secp256k1 => suite::KexAlgorithm::Secp256k1,
+        derec_proto::KeyExchangeEnum::X25519 => suite::KexAlgorithm::X25519,
+    };
+
+    suite::SUPPORTED_SUITES
+        .iter()
+        .filter(|s| s.kex == kex)
+        .map(|s| s.id as u32)
+        .collect()
+}
 
-    sk
+// decodes a key exchange tag, or None for a value the proto does not define
+fn key_exchange_of(tag: i32) -> Option<derec_proto::KeyExchangeEnum> {
+    derec_proto::KeyExchangeEnum::try_from(tag).ok()
 }
\ No newline at end of file
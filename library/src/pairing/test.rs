@@ -2,10 +2,38 @@
 mod tests {
     use crate::pairing::pairing::{
         create_contact_message,
+        create_contact_messages,
+        create_contact_message_with_key_exchange,
         produce_pairing_request_message,
+        produce_pairing_request_message_with_code,
+        bind_contact_keys,
+        generate_binding_code,
+        MIN_BINDING_CODE_LEN,
         produce_pairing_response_message,
-        process_pairing_response_message
+        produce_pairing_response_message_with_limiter,
+        produce_pairing_rejection_message,
+        process_pairing_response_message,
+        validate_contact_message,
+        run_pairing_as_contactor,
+        run_pairing_as_requestor,
+        PairingFlowError,
+        PairingResponseError,
+        wrap_shared_key,
+        canonical_encode,
+        build_transcript,
+        transcript_hash,
+        channel_id_from_contact,
+        ChannelRegistry,
+        safety_number,
+        unwrap_shared_key,
+        ContactValidationError,
+        PairingRole,
+        PairingSession,
+        PairingSessionError
     };
+    use crate::transport::Transport;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
     use crate::protos::derec_proto;
 
     #[test]
@@ -26,19 +54,21 @@ mod tests {
             bob_channel_id,
             bob_kind,
             &alice_contact_msg,
-        );
+        ).unwrap();
 
         let (alice_pair_resp_msg, alice_shared_key) = produce_pairing_response_message(
             alice_kind,
+            &alice_contact_msg,
             &bob_pair_req_msg,
             &alice_sk_state
-        );
+        ).unwrap();
 
         let bob_shared_key = process_pairing_response_message(
             &alice_contact_msg,
+            &bob_pair_req_msg,
             &alice_pair_resp_msg,
             &bob_sk_state
-        );
+        ).unwrap();
 
         // check nonces match
         assert_eq!(alice_contact_msg.nonce, bob_pair_req_msg.nonce);
@@ -56,7 +86,7 @@ mod tests {
         
         assert_eq!(contact_msg.public_key_id, channel_id);
         assert_eq!(contact_msg.transport_uri, transport_uri);
-        assert_eq!(contact_msg.message_encoding_type, 0);
+        assert_eq!(contact_msg.message_encoding_type, i32::from(derec_proto::contact_message::MessageEncodingEnum::Protobuf));
     }
 
     #[test]
@@ -69,9 +99,503 @@ mod tests {
             channel_id,
             derec_proto::SenderKind::SharerNonRecovery,
             &contact_msg
-        );
+        ).unwrap();
         
         assert_eq!(request_msg.public_key_id, channel_id);
         assert_eq!(request_msg.nonce, contact_msg.nonce);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_contact_message_with_short_mlkem_key_is_rejected() {
+        let (mut contact_msg, _) = create_contact_message(1, "test://transport");
+        contact_msg.mlkem_encapsulation_key.truncate(1000);
+
+        assert_eq!(
+            validate_contact_message(&contact_msg),
+            Err(ContactValidationError::InvalidMlKemKeyLength { expected: 1184, actual: 1000 })
+        );
+        assert!(produce_pairing_request_message(1, derec_proto::SenderKind::Helper, &contact_msg).is_err());
+    }
+
+    #[test]
+    fn test_contact_message_with_invalid_ecies_key_is_rejected() {
+        let (mut contact_msg, _) = create_contact_message(1, "test://transport");
+        contact_msg.ecies_public_key[1] ^= 0xFF;
+
+        assert_eq!(
+            validate_contact_message(&contact_msg),
+            Err(ContactValidationError::InvalidEciesPublicKey)
+        );
+    }
+
+    #[test]
+    fn test_contact_message_with_empty_transport_uri_is_rejected() {
+        let (contact_msg, _) = create_contact_message(1, "");
+
+        assert_eq!(
+            validate_contact_message(&contact_msg),
+            Err(ContactValidationError::EmptyTransportUri)
+        );
+    }
+
+    #[test]
+    fn test_contact_message_with_zero_nonce_is_rejected() {
+        let (mut contact_msg, _) = create_contact_message(1, "test://transport");
+        assert_ne!(contact_msg.nonce, 0);
+        contact_msg.nonce = 0;
+
+        assert_eq!(
+            validate_contact_message(&contact_msg),
+            Err(ContactValidationError::ZeroNonce)
+        );
+        assert_eq!(
+            produce_pairing_request_message(2, derec_proto::SenderKind::Helper, &contact_msg).err(),
+            Some(ContactValidationError::ZeroNonce)
+        );
+    }
+
+    #[test]
+    fn test_contact_message_with_unsupported_encoding_is_rejected() {
+        let (mut contact_msg, _) = create_contact_message(1, "test://transport");
+        contact_msg.message_encoding_type = 7;
+
+        assert_eq!(
+            produce_pairing_request_message(1, derec_proto::SenderKind::SharerNonRecovery, &contact_msg).err(),
+            Some(ContactValidationError::UnsupportedMessageEncoding(7))
+        );
+    }
+
+    #[test]
+    fn test_pairing_flow_over_x25519() {
+        let (contact_msg, contactor_sk) = create_contact_message_with_key_exchange(
+            7,
+            "x25519://transport",
+            derec_proto::KeyExchangeEnum::X25519
+        );
+        assert_eq!(contact_msg.key_exchange, derec_proto::KeyExchangeEnum::X25519 as i32);
+        assert!(validate_contact_message(&contact_msg).is_ok());
+
+        let (pair_req_msg, requestor_sk) = produce_pairing_request_message(
+            8,
+            derec_proto::SenderKind::Helper,
+            &contact_msg
+        ).unwrap();
+        assert_eq!(pair_req_msg.key_exchange, contact_msg.key_exchange);
+
+        let (pair_resp_msg, contactor_key) = produce_pairing_response_message(
+            derec_proto::SenderKind::SharerNonRecovery,
+            &contact_msg,
+            &pair_req_msg,
+            &contactor_sk
+        ).unwrap();
+        let requestor_key = process_pairing_response_message(&contact_msg, &pair_req_msg, &pair_resp_msg, &requestor_sk).unwrap();
+        assert_eq!(contactor_key, requestor_key);
+
+        // a contact message with an unknown key exchange is rejected
+        let mut unknown = contact_msg.clone();
+        unknown.key_exchange = 42;
+        assert_eq!(validate_contact_message(&unknown), Err(ContactValidationError::UnsupportedKeyExchange(42)));
+    }
+
+    // one end of an in-memory duplex channel
+    struct DuplexTransport {
+        tx: mpsc::UnboundedSender<Vec<u8>>,
+        rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    }
+
+    fn duplex() -> (DuplexTransport, DuplexTransport) {
+        let (a_tx, b_rx) = mpsc::unbounded();
+        let (b_tx, a_rx) = mpsc::unbounded();
+        (DuplexTransport { tx: a_tx, rx: a_rx }, DuplexTransport { tx: b_tx, rx: b_rx })
+    }
+
+    impl Transport for DuplexTransport {
+        type Error = &'static str;
+
+        async fn send(&mut self, msg: &[u8]) -> Result<(), Self::Error> {
+            self.tx.unbounded_send(msg.to_vec()).map_err(|_| "peer hung up")
+        }
+
+        async fn recv(&mut self) -> Result<Vec<u8>, Self::Error> {
+            self.rx.next().await.ok_or("peer hung up")
+        }
+    }
+
+    #[test]
+    fn test_pairing_over_in_memory_transport() {
+        let (mut alice, mut bob) = duplex();
+
+        let (alice_key, bob_key) = futures::executor::block_on(async {
+            futures::join!(
+                run_pairing_as_contactor(1, "alice://transport", derec_proto::SenderKind::SharerNonRecovery, &mut alice),
+                run_pairing_as_requestor(2, derec_proto::SenderKind::Helper, &mut bob),
+            )
+        });
+
+        assert_eq!(alice_key.unwrap(), bob_key.unwrap());
+    }
+
+    #[test]
+    fn test_tampered_transport_uri_yields_different_keys() {
+        let (contact_msg, contactor_sk) = create_contact_message(3, "https://helper.example");
+
+        // a man-in-the-middle redirects the requestor to another endpoint
+        let mut tampered = contact_msg.clone();
+        tampered.transport_uri = String::from("https://attacker.example");
+
+        let (pair_req_msg, requestor_sk) = produce_pairing_request_message(
+            4,
+            derec_proto::SenderKind::SharerNonRecovery,
+            &tampered
+        ).unwrap();
+        let (pair_resp_msg, contactor_key) = produce_pairing_response_message(
+            derec_proto::SenderKind::Helper,
+            &contact_msg,
+            &pair_req_msg,
+            &contactor_sk
+        ).unwrap();
+        let requestor_key = process_pairing_response_message(&tampered, &pair_req_msg, &pair_resp_msg, &requestor_sk).unwrap();
+        assert_ne!(contactor_key, requestor_key);
+
+        // without tampering the keys agree
+        let (pair_req_msg, requestor_sk) = produce_pairing_request_message(
+            4,
+            derec_proto::SenderKind::SharerNonRecovery,
+            &contact_msg
+        ).unwrap();
+        let (pair_resp_msg, contactor_key) = produce_pairing_response_message(
+            derec_proto::SenderKind::Helper,
+            &contact_msg,
+            &pair_req_msg,
+            &contactor_sk
+        ).unwrap();
+        assert_eq!(contactor_key, process_pairing_response_message(&contact_msg, &pair_req_msg, &pair_resp_msg, &requestor_sk).unwrap());
+    }
+
+    #[test]
+    fn test_wrap_unwrap_shared_key() {
+        let shared_key = [0x11; 32];
+        let device_key = [0x42; 32];
+
+        let wrapped = wrap_shared_key(&shared_key, &device_key);
+        assert_ne!(&wrapped[..], &shared_key[..]);
+        assert_eq!(unwrap_shared_key(&wrapped, &device_key), Ok(shared_key));
+        assert!(unwrap_shared_key(&wrapped, &[0x43; 32]).is_err());
+    }
+
+    #[test]
+    fn test_unwrap_tampered_shared_key_fails() {
+        let device_key = [0x42; 32];
+        let mut wrapped = wrap_shared_key(&[7u8; 32], &device_key);
+
+        let last = wrapped.len() - 1;
+        wrapped[last] ^= 1;
+        assert!(unwrap_shared_key(&wrapped, &device_key).is_err());
+    }
+
+    #[test]
+    fn test_contact_message_advertises_suites() {
+        use derec_cryptography::suite::{negotiate, SUPPORTED_SUITES, SUITE_MLKEM768_SECP256K1, SUITE_MLKEM768_X25519};
+
+        let (contact, _) = create_contact_message(1, "https://example.com/derec");
+        let remote: Vec<u16> = contact.supported_suites.iter().map(|id| *id as u16).collect();
+        assert_eq!(negotiate(SUPPORTED_SUITES, &remote), Some(SUITE_MLKEM768_SECP256K1));
+
+        let (contact, _) = create_contact_message_with_key_exchange(
+            1, "https://example.com/derec", derec_proto::KeyExchangeEnum::X25519);
+        let remote: Vec<u16> = contact.supported_suites.iter().map(|id| *id as u16).collect();
+        assert_eq!(negotiate(SUPPORTED_SUITES, &remote), Some(SUITE_MLKEM768_X25519));
+        assert_eq!(negotiate(&[SUITE_MLKEM768_SECP256K1], &remote), None);
+    }
+
+    #[test]
+    fn test_response_from_another_session_is_rejected() {
+        let (contact_msg, contactor_sk) = create_contact_message(1, "https://example.com/derec");
+        let (pair_req_msg, requestor_sk) = produce_pairing_request_message(
+            2,
+            derec_proto::SenderKind::SharerRecovery,
+            &contact_msg
+        ).unwrap();
+        let (mut pair_resp_msg, _) = produce_pairing_response_message(
+            derec_proto::SenderKind::Helper,
+            &contact_msg,
+            &pair_req_msg,
+            &contactor_sk
+        ).unwrap();
+
+        pair_resp_msg.nonce ^= 1;
+        assert!(matches!(
+            process_pairing_response_message(&contact_msg, &pair_req_msg, &pair_resp_msg, &requestor_sk),
+            Err(PairingResponseError::NonceMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_channel_id_survives_reencoding() {
+        use prost::Message;
+
+        let (contact_msg, _) = create_contact_message(3, "https://example.com/derec");
+        let wire = contact_msg.encode_to_vec();
+
+        // the same message with an unknown field appended and its first field repeated:
+        // a different byte string that decodes to an equal message
+        let mut noisy = wire.clone();
+        noisy.extend_from_slice(&[0xF8, 0x06, 0x2A]); // field 111, varint 42
+        let mut first_field = Vec::new();
+        prost::encoding::bytes::encode(1, &contact_msg.mlkem_encapsulation_key, &mut first_field);
+        noisy.extend_from_slice(&first_field);
+        assert_ne!(noisy, wire);
+
+        let decoded = derec_proto::ContactMessage::decode(noisy.as_slice()).unwrap();
+        assert_eq!(canonical_encode(&decoded), canonical_encode(&contact_msg));
+        assert_eq!(channel_id_from_contact(&decoded), channel_id_from_contact(&contact_msg));
+
+        // re-encoding is stable
+        let reencoded = derec_proto::ContactMessage::decode(decoded.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(canonical_encode(&reencoded), canonical_encode(&contact_msg));
+
+        // while any change of content changes the identifier
+        let mut other = contact_msg.clone();
+        other.nonce ^= 1;
+        assert_ne!(channel_id_from_contact(&other), channel_id_from_contact(&contact_msg));
+    }
+
+    #[test]
+    fn test_transcript_is_deterministic_and_secret_free() {
+        let (contact_msg, contactor_sk) = create_contact_message(5, "https://example.com/derec");
+        let (pair_req_msg, requestor_sk) = produce_pairing_request_message(
+            6,
+            derec_proto::SenderKind::SharerRecovery,
+            &contact_msg
+        ).unwrap();
+        let (pair_resp_msg, contactor_key) = produce_pairing_response_message(
+            derec_proto::SenderKind::Helper,
+            &contact_msg,
+            &pair_req_msg,
+            &contactor_sk
+        ).unwrap();
+
+        let transcript = build_transcript(&contact_msg, &pair_req_msg, &pair_resp_msg);
+        assert_eq!(transcript, build_transcript(&contact_msg, &pair_req_msg, &pair_resp_msg));
+        assert_eq!(transcript_hash(&transcript), transcript_hash(&transcript));
+
+        // the public keys are logged, but none of the secrets nor the derived key
+        let contains = |needle: &[u8]| transcript.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(&contact_msg.ecies_public_key));
+        assert!(contains(&pair_req_msg.mlkem_ciphertext));
+        assert!(!contains(&contactor_sk.ecies_secret_key));
+        assert!(!contains(contactor_sk.mlkem_decapsulation_key.as_ref().unwrap()));
+        assert!(!contains(&requestor_sk.ecies_secret_key));
+        assert!(!contains(&requestor_sk.mlkem_shared_secret.unwrap()));
+        assert!(!contains(&contactor_key));
+
+        // the key is bound to the transcript: a response altered in transit yields another key
+        let mut altered = pair_resp_msg.clone();
+        altered.sender_kind = derec_proto::SenderKind::SharerRecovery.into();
+        assert_ne!(build_transcript(&contact_msg, &pair_req_msg, &altered), transcript);
+        assert_ne!(process_pairing_response_message(&contact_msg, &pair_req_msg, &altered, &requestor_sk).unwrap(), contactor_key);
+        assert_eq!(process_pairing_response_message(&contact_msg, &pair_req_msg, &pair_resp_msg, &requestor_sk).unwrap(), contactor_key);
+    }
+
+    #[test]
+    fn test_safety_numbers_match_only_for_the_same_key() {
+        let (contact, contactor_sk) = create_contact_message(1, "test://transport");
+        let (request, requestor_sk) =
+            produce_pairing_request_message(2, derec_proto::SenderKind::Helper, &contact).unwrap();
+        let (response, contactor_key) =
+            produce_pairing_response_message(derec_proto::SenderKind::SharerNonRecovery, &contact, &request, &contactor_sk).unwrap();
+        let requestor_key = process_pairing_response_message(&contact, &request, &response, &requestor_sk).unwrap();
+
+        let code = safety_number(&contactor_key);
+        assert_eq!(code, safety_number(&requestor_key));
+        assert_eq!(code.split(' ').count(), 6);
+        assert!(code.split(' ').all(|group| group.len() == 5 && group.bytes().all(|b| b.is_ascii_digit())));
+
+        let mut flipped = requestor_key;
+        flipped[17] ^= 0x04;
+        assert_ne!(safety_number(&flipped), code);
+    }
+
+    #[test]
+    fn test_create_contact_messages_for_many_channels() {
+        let channel_ids: Vec<u64> = (100..110).collect();
+        let contacts = create_contact_messages(&channel_ids, "test://transport");
+
+        assert_eq!(contacts.len(), channel_ids.len());
+        for ((contact, _), channel_id) in contacts.iter().zip(&channel_ids) {
+            assert_eq!(contact.public_key_id, *channel_id);
+            assert_eq!(contact.transport_uri, "test://transport");
+            assert_eq!(validate_contact_message(contact), Ok(()));
+        }
+
+        let mlkem_keys: std::collections::HashSet<_> =
+            contacts.iter().map(|(contact, _)| contact.mlkem_encapsulation_key.clone()).collect();
+        assert_eq!(mlkem_keys.len(), channel_ids.len());
+    }
+
+    #[test]
+    fn test_pairing_session_resumes_from_serialized_state() {
+        let (contactor, contact_msg) = PairingSession::contactor(7, "helper://transport", derec_proto::SenderKind::Helper);
+        let mut requestor = PairingSession::requestor(7, derec_proto::SenderKind::SharerRecovery);
+        let request_msg = requestor.advance_with_contact(&contact_msg).unwrap();
+
+        // both sides persist their half-completed sessions and restart
+        let mut contactor = PairingSession::deserialize(&contactor.serialize()).unwrap();
+        let mut requestor = PairingSession::deserialize(&requestor.serialize()).unwrap();
+        assert_eq!(contactor.role(), PairingRole::Contactor);
+        assert_eq!(requestor.role(), PairingRole::Requestor);
+        assert_eq!(requestor.channel_id(), 7);
+        assert_eq!(requestor.transport_uri(), "helper://transport");
+        assert!(requestor.shared_key().is_none());
+
+        let response_msg = contactor.advance_with_request(&request_msg).unwrap();
+        let requestor_key = requestor.advance_with_response(&response_msg).unwrap();
+        assert_eq!(contactor.shared_key(), Some(requestor_key));
+
+        // a completed session keeps its key across a restart, and takes no further messages
+        let mut contactor = PairingSession::deserialize(&contactor.serialize()).unwrap();
+        assert_eq!(contactor.shared_key(), Some(requestor_key));
+        assert!(matches!(contactor.advance_with_request(&request_msg), Err(PairingSessionError::UnexpectedMessage)));
+        assert!(matches!(PairingSession::deserialize(&[1, 2, 3]), Err(PairingSessionError::Malformed)));
+    }
+
+    #[test]
+    fn test_declined_pairing_reports_the_memo() {
+        let (contact_msg, _) = create_contact_message(3, "helper://transport");
+        let mut requestor = PairingSession::requestor(3, derec_proto::SenderKind::SharerRecovery);
+        let request_msg = requestor.advance_with_contact(&contact_msg).unwrap();
+
+        let response_msg = produce_pairing_rejection_message(derec_proto::SenderKind::Helper, &request_msg, "channel already paired");
+        let error = requestor.advance_with_response(&response_msg).unwrap_err();
+        assert!(matches!(&error, PairingSessionError::Rejected(memo) if memo == "channel already paired"));
+        assert_eq!(error.to_string(), "pairing rejected: channel already paired");
+        assert!(requestor.shared_key().is_none());
+
+        // without a session, the memo is reported just the same
+        let (request_msg, requestor_sk) = produce_pairing_request_message(3, derec_proto::SenderKind::SharerRecovery, &contact_msg).unwrap();
+        let response_msg = produce_pairing_rejection_message(derec_proto::SenderKind::Helper, &request_msg, "too many channels");
+        assert!(matches!(
+            process_pairing_response_message(&contact_msg, &request_msg, &response_msg, &requestor_sk),
+            Err(PairingResponseError::Rejected(memo)) if memo == "too many channels"
+        ));
+    }
+
+    #[test]
+    fn test_spliced_ecies_key_fails_the_key_binding() {
+        let code = generate_binding_code();
+        let code = code.as_bytes();
+        let (mut contact_msg, _) = create_contact_message(5, "helper://transport");
+        bind_contact_keys(&mut contact_msg, code).unwrap();
+        assert!(produce_pairing_request_message_with_code(5, derec_proto::SenderKind::SharerRecovery, &contact_msg, code).is_ok());
+
+        // a relay keeps the victim's ML-KEM key but substitutes its own ECIES key
+        let (attacker_msg, _) = create_contact_message(5, "helper://transport");
+        let spliced = derec_proto::ContactMessage { ecies_public_key: attacker_msg.ecies_public_key, ..contact_msg.clone() };
+        assert_eq!(validate_contact_message(&spliced), Ok(()));
+        assert_eq!(
+            produce_pairing_request_message_with_code(5, derec_proto::SenderKind::SharerRecovery, &spliced, code).err(),
+            Some(ContactValidationError::KeyBindingMismatch)
+        );
+
+        // an unbound contact message is refused when a code is expected
+        let unbound = derec_proto::ContactMessage { key_binding_mac: Vec::new(), ..contact_msg };
+        assert_eq!(
+            produce_pairing_request_message_with_code(5, derec_proto::SenderKind::SharerRecovery, &unbound, code).err(),
+            Some(ContactValidationError::KeyBindingMismatch)
+        );
+    }
+
+    #[test]
+    fn test_short_binding_codes_are_refused() {
+        let (mut contact_msg, _) = create_contact_message(5, "helper://transport");
+        let short = || ContactValidationError::BindingCodeTooShort { min: MIN_BINDING_CODE_LEN, actual: 11 };
+        assert_eq!(bind_contact_keys(&mut contact_msg, b"492-113-870"), Err(short()));
+        assert!(contact_msg.key_binding_mac.is_empty());
+        assert_eq!(
+            produce_pairing_request_message_with_code(5, derec_proto::SenderKind::SharerRecovery, &contact_msg, b"492-113-870").err(),
+            Some(short())
+        );
+
+        let code = generate_binding_code();
+        assert!(code.len() >= MIN_BINDING_CODE_LEN);
+        assert_ne!(code, generate_binding_code());
+    }
+
+    #[test]
+    fn test_channel_registry_looks_up_contacts_by_derived_id() {
+        let (alice, _) = create_contact_message(1, "alice://transport");
+        let (bob, _) = create_contact_message(2, "bob://transport");
+
+        let mut registry = ChannelRegistry::new();
+        let alice_channel = registry.register(&alice);
+        let bob_channel = registry.register(&bob);
+
+        assert_eq!(alice_channel, channel_id_from_contact(&alice));
+        assert_eq!(bob_channel, channel_id_from_contact(&bob));
+        assert_ne!(alice_channel, bob_channel);
+        assert_eq!(registry.lookup(alice_channel), Some(&alice));
+        assert_eq!(registry.lookup(bob_channel), Some(&bob));
+        assert_eq!(registry.lookup(alice_channel ^ bob_channel ^ 1), None);
+    }
+
+    #[test]
+    fn test_requestor_flow_reports_a_response_nonce_mismatch() {
+        use prost::Message;
+        let (mut contactor, mut bob) = duplex();
+
+        let (_, result) = futures::executor::block_on(async {
+            futures::join!(
+                async {
+                    let (contact_msg, sk) = create_contact_message(1, "alice://transport");
+                    contactor.send(&contact_msg.encode_to_vec()).await.unwrap();
+                    let request_bytes = contactor.recv().await.unwrap();
+                    let request_msg = derec_proto::PairRequestMessage::decode(request_bytes.as_slice()).unwrap();
+                    let (mut response_msg, _) = produce_pairing_response_message(
+                        derec_proto::SenderKind::SharerNonRecovery,
+                        &contact_msg,
+                        &request_msg,
+                        &sk
+                    ).unwrap();
+                    response_msg.nonce ^= 1;
+                    contactor.send(&response_msg.encode_to_vec()).await.unwrap();
+                },
+                run_pairing_as_requestor(2, derec_proto::SenderKind::Helper, &mut bob),
+            )
+        });
+
+        assert!(matches!(result, Err(PairingFlowError::NonceMismatch)));
+    }
+
+    #[test]
+    fn test_malformed_pairing_request_is_an_error() {
+        let (mut contactor, contact_msg) = PairingSession::contactor(5, "helper://transport", derec_proto::SenderKind::Helper);
+        let mut requestor = PairingSession::requestor(5, derec_proto::SenderKind::SharerRecovery);
+        let mut request_msg = requestor.advance_with_contact(&contact_msg).unwrap();
+        request_msg.mlkem_ciphertext.truncate(16);
+
+        assert!(matches!(contactor.advance_with_request(&request_msg), Err(PairingSessionError::Pairing(_))));
+        assert!(contactor.shared_key().is_none());
+
+        let (contact_msg, contactor_sk) = create_contact_message(6, "helper://transport");
+        let mut unsupported = contact_msg.clone();
+        unsupported.key_exchange = 99;
+        let (request_msg, _) = produce_pairing_request_message(6, derec_proto::SenderKind::Helper, &contact_msg).unwrap();
+        assert!(produce_pairing_response_message(derec_proto::SenderKind::Helper, &unsupported, &request_msg, &contactor_sk).is_err());
+    }
+
+    #[test]
+    fn test_response_producer_can_be_rate_limited() {
+        let limiter = derec_cryptography::pairing::DecapsulationLimiter::new(1);
+        let (contact_msg, contactor_sk) = create_contact_message(7, "helper://transport");
+        let (request_msg, _) = produce_pairing_request_message(7, derec_proto::SenderKind::Helper, &contact_msg).unwrap();
+
+        let produce = || produce_pairing_response_message_with_limiter(
+            derec_proto::SenderKind::Helper, &contact_msg, &request_msg, &contactor_sk, &limiter
+        );
+        assert!(produce().is_ok());
+        assert!(matches!(produce(), Err(derec_cryptography::pairing::DerecPairingError::RateLimited)));
+
+        // the unlimited producer is unaffected
+        assert!(produce_pairing_response_message(derec_proto::SenderKind::Helper, &contact_msg, &request_msg, &contactor_sk).is_ok());
+    }
+}
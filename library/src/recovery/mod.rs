@@ -1,10 +1,28 @@
+#[allow(clippy::module_inception)]
 pub mod recovery;
 pub use recovery::generate_share_request;
 pub use recovery::generate_share_response;
+pub use recovery::generate_share_failure_response;
 pub use recovery::recover_from_share_responses;
+pub use recovery::recover_from_channel_responses;
+pub use recovery::recover_from_channel_map;
+pub use recovery::recover_from_share_responses_with_key;
+pub use recovery::recover_from_share_responses_with_commitment;
+pub use recovery::recover_any_version;
+pub use recovery::unpack_secrets;
+pub use recovery::recovery_progress;
+pub use recovery::detect_inconsistent_shares;
+pub use recovery::outstanding_channels;
+pub use recovery::RecoveryProgress;
+pub use recovery::OutstandingChannels;
+pub use recovery::RecoveryError;
+pub use recovery::RecoveredSecret;
+pub use recovery::ChannelRecoveredSecret;
+pub use recovery::RejectedResponse;
 
 use prost::Message;
-use crate::protos::derec_proto::{GetShareRequestMessage, GetShareResponseMessage, StoreShareRequestMessage};
+use derec_cryptography::vss::DerecVSSError;
+use crate::protos::derec_proto::{GetShareRequestMessage, GetShareResponseMessage, SenderKind, StoreShareRequestMessage};
 
 use wasm_bindgen::prelude::*;
 
@@ -28,33 +46,77 @@ pub fn ts_generate_share_response(
     channel_id: u64,
     share_content: &[u8],
     request: &[u8],
+    peer_kind: i32,
 ) -> Vec<u8> {
     let request = GetShareRequestMessage::decode(request).unwrap();
     let share_content = StoreShareRequestMessage::decode(share_content).unwrap();
-    recovery::generate_share_response(&channel_id, secret_id, &request, &share_content).encode_to_vec()
+    // unknown kinds are refused like a non-recovery sharer
+    let peer_kind = SenderKind::try_from(peer_kind).unwrap_or(SenderKind::SharerNonRecovery);
+    recovery::generate_share_response(&channel_id, secret_id, &request, &share_content, peer_kind).encode_to_vec()
 }
 
 #[wasm_bindgen]
 pub fn ts_recover_from_share_responses(
     responses: JsValue,
     secret_id: &[u8],
-    version: i32
+    version: i32,
+    threshold: u32,
 ) -> Result<Vec<u8>, String> {
-    let responses: TsRecoverShareResponses = serde_wasm_bindgen::from_value(responses).unwrap();
-    let mut parsed_responses = Vec::new();
-    for (_channel_id, bytes) in responses.value {
-        let response = GetShareResponseMessage::decode(&*bytes);
-        if response.is_err() {
-            return Err(response.unwrap_err().to_string());
-        } else {
-            parsed_responses.push(response.unwrap());
-        }
+    let responses: TsRecoverShareResponses = serde_wasm_bindgen::from_value(responses).map_err(|e| e.to_string())?;
+    let mut parsed_responses = std::collections::HashMap::new();
+    for (channel_id, bytes) in responses.value {
+        let response = GetShareResponseMessage::decode(&*bytes).map_err(|e| e.to_string())?;
+        parsed_responses.insert(channel_id, response);
+    }
+    recovery::recover_from_channel_map(&parsed_responses, secret_id, version, threshold as usize)
+        .map(|recovered| recovered.data)
+        .map_err(|e| e.to_string())
+}
+
+/// Checks that the responses (a map of channel id to encoded `GetShareResponseMessage`, as for
+/// `ts_recover_from_share_responses`) carry shares of one sharing, before attempting recovery.
+///
+/// Returns `Ok(())` if they do. If they do not, returns `Err` with the name of the inconsistency
+/// (e.g. `"InconsistentCommitments"`, `"InconsistentCiphertexts"` or `"CorruptShares"`); responses that
+/// cannot be decoded also yield `Err`, with a description of the problem.
+#[wasm_bindgen]
+pub fn ts_check_shares_consistent(
+    responses: JsValue,
+    secret_id: &[u8],
+    version: i32,
+) -> Result<(), String> {
+    let responses: TsRecoverShareResponses = serde_wasm_bindgen::from_value(responses).map_err(|e| e.to_string())?;
+    let mut parsed_responses = Vec::with_capacity(responses.value.len());
+    for bytes in responses.value.values() {
+        parsed_responses.push(GetShareResponseMessage::decode(&**bytes).map_err(|e| e.to_string())?);
     }
-    let secret = recovery::recover_from_share_responses(&parsed_responses, secret_id, version);
-    if secret.is_err() {
-        return Err(secret.unwrap_err().to_string());
+    match recovery::detect_inconsistent_shares(&parsed_responses, secret_id, version).map_err(|e| e.to_string())? {
+        None => Ok(()),
+        Some(error) => Err(inconsistency_name(&error)),
     }
-    return Ok(secret.unwrap());
+}
+
+// the variant name of a VSS error, e.g. "InconsistentCommitments"
+fn inconsistency_name(error: &DerecVSSError) -> String {
+    match error {
+        DerecVSSError::InconsistentCiphertexts => "InconsistentCiphertexts",
+        DerecVSSError::InconsistentCommitments => "InconsistentCommitments",
+        DerecVSSError::CorruptShares => "CorruptShares",
+        DerecVSSError::InsufficientShares => "InsufficientShares",
+        DerecVSSError::DecryptionFailure => "DecryptionFailure",
+        DerecVSSError::InvalidAccessStructure => "InvalidAccessStructure",
+        DerecVSSError::TooManyChannels { .. } => "TooManyChannels",
+        DerecVSSError::TooManyLeaves { .. } => "TooManyLeaves",
+        DerecVSSError::MalformedTree => "MalformedTree",
+        DerecVSSError::UnknownChannel => "UnknownChannel",
+        DerecVSSError::NoChannels => "NoChannels",
+        DerecVSSError::TrivialThreshold => "TrivialThreshold",
+        DerecVSSError::MalformedShare => "MalformedShare",
+        DerecVSSError::SecretTooLarge { .. } => "SecretTooLarge",
+        DerecVSSError::WeakSeed => "WeakSeed",
+        DerecVSSError::KeyReconstructionMismatch => "KeyReconstructionMismatch",
+        DerecVSSError::InconsistentShareAlgorithms => "InconsistentShareAlgorithms",
+    }.to_string()
 }
 
 #[cfg(test)]
@@ -1,4 +1,7 @@
 use prost::Message;
+use thiserror::Error;
+use subtle::ConstantTimeEq;
+use std::collections::{HashMap, HashSet};
 use derec_cryptography::vss::*;
 use crate::{protos::derec_proto::{
     CommittedDeRecShare,
@@ -7,8 +10,9 @@ use crate::{protos::derec_proto::{
     GetShareRequestMessage,
     GetShareResponseMessage,
     Result as DerecResult,
+    SenderKind,
     StatusEnum
-}, types::ChannelId};
+}, sharing::{blind_secret_id, sharing::{decode_share_of, reconstruct_secret, ReconstructionError}, ShareAlgorithm}, types::ChannelId};
 
 /// Generates a `GetShareRequestMessage` for requesting a secret share.
 ///
@@ -35,11 +39,19 @@ pub fn generate_share_request(
 
 /// Generates a `GetShareResponseMessage` containing a secret share in response to a share request.
 ///
+/// Shares are only served to a peer that paired as `SenderKind::SharerRecovery`; a sharer in normal
+/// operation still holds its secret and has no reason to retrieve shares. For any other `peer_kind`
+/// the response carries no share and a `Fail` status. The same holds for a request whose `secret_id`
+/// or `share_version` differs from the stored share's, so that a helper never answers with a share
+/// other than the one asked for.
+///
 /// # Arguments
 ///
 /// * `_channel_id` - The identifier of the channel (currently unused).
-/// * `_request` - The original `GetShareRequestMessage` for which this response is generated (currently unused).
+/// * `secret_id` - The identifier under which the helper stores the share.
+/// * `request` - The original `GetShareRequestMessage` for which this response is generated.
 /// * `share_content` - The content of the share to be included in the response. This should be a byte slice or any type that can be referenced as a byte slice.
+/// * `peer_kind` - The `SenderKind` the requester paired as, as recorded by `pairing::requestor_sender_kind`.
 ///
 /// # Returns
 ///
@@ -47,98 +59,729 @@ pub fn generate_share_request(
 /// or an error string if the response could not be generated.
 pub fn generate_share_response(
     _channel_id: &ChannelId,
-    _secret_id: impl AsRef<[u8]>,
-    _request: &GetShareRequestMessage,
+    secret_id: impl AsRef<[u8]>,
+    request: &GetShareRequestMessage,
     share_content: &StoreShareRequestMessage,
+    peer_kind: SenderKind,
 ) -> GetShareResponseMessage {
+    if peer_kind != SenderKind::SharerRecovery {
+        return share_failure_response("Shares are only served to a sharer paired for recovery");
+    }
+    if request.secret_id != secret_id.as_ref() {
+        return share_failure_response("Requested secret id does not match the stored share");
+    }
+    if request.share_version != share_content.version {
+        return share_failure_response("Requested version does not match the stored share");
+    }
+
     // share_content is of type StoreShareRequestMessage
     GetShareResponseMessage {
-        share_algorithm: 0,
+        share_algorithm: share_content.share_algorithm,
         committed_de_rec_share: share_content.share.to_vec(),
+        keep_list: share_content.keep_list.clone(),
+        version_description: share_content.version_description.clone(),
         result: Some(DerecResult { status: StatusEnum::Ok as i32, memo: String::new() }),
     }
 }
 
+/// Generates a `GetShareResponseMessage` refusing a share request, with a human-readable reason.
+///
+/// A helper uses this when it has no share to serve, e.g. because it never stored the secret or has pruned
+/// the requested version. The `memo` reaches the sharer as `RecoveryError::HelperFailed`.
+///
+/// # Arguments
+///
+/// * `memo` - Why the request is refused, e.g. "share not found" or "version pruned".
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::recovery::generate_share_failure_response;
+/// let response = generate_share_failure_response("version pruned");
+/// assert_eq!(response.result.unwrap().memo, "version pruned");
+/// ```
+pub fn generate_share_failure_response(memo: &str) -> GetShareResponseMessage {
+    share_failure_response(memo)
+}
+
+fn share_failure_response(memo: &str) -> GetShareResponseMessage {
+    GetShareResponseMessage {
+        share_algorithm: 0,
+        committed_de_rec_share: Vec::new(),
+        keep_list: Vec::new(),
+        version_description: String::new(),
+        result: Some(DerecResult { status: StatusEnum::Fail as i32, memo: memo.to_string() }),
+    }
+}
+
+/// Reasons a secret cannot be recovered from a set of share responses.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryError {
+    #[error("invalid share response: {0}")]
+    InvalidResponse(&'static str),
+    #[error("shares come from different sharings of the secret")]
+    MixedSharings,
+    #[error("shares are corrupt or inconsistent")]
+    CorruptShares,
+    #[error("insufficient shares: have {have} distinct valid shares, need {need}")]
+    InsufficientShares { have: usize, need: usize },
+    #[error("failed to reconstruct secret from shares")]
+    ReconstructionFailed,
+    #[error("recovered secret does not match its commitment")]
+    CommitmentMismatch,
+    #[error("share response carries an empty share")]
+    EmptyShare,
+    #[error("share coordinates are not canonical field element encodings")]
+    MalformedShare,
+    #[error("recovered secret is not a list of packed secrets")]
+    InvalidPacking,
+    #[error("Merkle path has {actual} nodes, but the Merkle tree has a depth of {expected}")]
+    IncompleteMerklePath { expected: u32, actual: usize },
+    #[error("helper refused the request: {memo}")]
+    HelperFailed { memo: String },
+    #[error("share is for secret id {got:02x?}, but {expected:02x?} was requested")]
+    SecretIdMismatch { expected: Vec<u8>, got: Vec<u8> },
+    #[error("share was protected with threshold {got}, but {expected} was requested")]
+    ThresholdMismatch { expected: usize, got: usize },
+}
+
+/// A response that was set aside during recovery, as reported in `RecoveredSecret::rejected`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedResponse {
+    /// position of the response in the slice passed to recovery
+    pub index: usize,
+    /// why its share could not be used
+    pub error: RecoveryError,
+}
+
+/// A secret reconstructed from share responses, with a report of the responses that could not be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredSecret {
+    /// the reconstructed secret
+    pub data: Vec<u8>,
+    /// the responses whose shares were rejected, in the order they were passed
+    pub rejected: Vec<RejectedResponse>,
+    /// positions of the responses whose shares went into the reconstruction; a share received
+    /// more than once is counted at its first position only
+    pub contributors: Vec<usize>,
+    /// the version that was recovered
+    pub version: i32,
+    /// the description of the version; taken from the shares if the sharer committed it there,
+    /// and otherwise as reported by the first contributing helper
+    pub description: String,
+    /// the versions the sharer asked helpers to keep alongside this one, as reported by the first
+    /// contributing helper
+    pub keep_list: Vec<i32>,
+}
+
+/// A secret reconstructed by `recover_from_channel_map`, reporting responses by channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRecoveredSecret {
+    /// the reconstructed secret
+    pub data: Vec<u8>,
+    /// the channels whose shares went into the reconstruction, in ascending order
+    pub contributors: Vec<ChannelId>,
+    /// the channels whose shares were rejected, in ascending order, with the reason
+    pub rejected: Vec<(ChannelId, RecoveryError)>,
+    /// the version that was recovered; see `RecoveredSecret::version`
+    pub version: i32,
+    /// the description of the version; see `RecoveredSecret::description`
+    pub description: String,
+    /// the versions to keep alongside this one; see `RecoveredSecret::keep_list`
+    pub keep_list: Vec<i32>,
+}
+
 /// Attempts to reconstruct the original secret from a collection of `GetShareResponseMessage` responses.
 ///
 /// This function processes each response, extracting the contained share and verifying that it matches
-/// the requested `secret_id` and `version`. If all shares are valid, it attempts to reconstruct the secret
-/// using the underlying verifiable secret sharing (VSS) recovery mechanism.
+/// the requested `secret_id` and `version` and that its Merkle path verifies. A response that fails these
+/// checks is set aside and reported in `RecoveredSecret::rejected`, rather than aborting the attempt, so one
+/// bad helper in a large set does not prevent recovery. If at least `threshold` of the remaining shares are
+/// distinct, it attempts to reconstruct the secret using the underlying verifiable secret sharing (VSS)
+/// recovery mechanism. Below the threshold, interpolation would silently yield an unrelated value, so the
+/// shares are counted before any field arithmetic is performed.
+///
+/// The shares are reconstructed according to the `share_algorithm` tag of the responses (see
+/// `sharing::ShareAlgorithm`), which must be the same for all of them.
+///
+/// Shares record the threshold they were protected under, so a secret re-protected with a new threshold
+/// (under the same version) can be recovered from a mix of old and new shares: only the shares recorded
+/// with `threshold` are reconstructed together, and the others are rejected. Shares that record no
+/// threshold are assumed to match.
 ///
 /// # Arguments
 ///
 /// * `response` - A slice of `GetShareResponseMessage` objects, each containing a share to be used in reconstruction.
 /// * `secret_id` - The identifier of the secret being recovered. Used to validate that each share corresponds to the correct secret.
 /// * `version` - The version of the secret to recover. Used to validate that each share is for the correct version.
+/// * `threshold` - The number of shares required to reconstruct the secret.
 ///
 /// # Returns
 ///
-/// Returns `Ok(Vec<u8>)` containing the reconstructed secret if successful, or an error string if recovery fails
-/// (e.g., due to invalid shares, mismatched secret IDs or versions, or insufficient shares).
+/// Returns `Ok(RecoveredSecret)` containing the reconstructed secret and the rejected responses if successful,
+/// or a `RecoveryError` if recovery fails (e.g., due to mixed sharings or insufficient valid shares).
 ///
 /// # Errors
 ///
-/// Returns an error if:
-/// - Any response does not contain a valid result or indicates an error status.
-/// - Any share cannot be decoded or does not match the requested secret ID or version.
-/// - The secret cannot be reconstructed from the provided shares.
+/// Responses are rejected individually with:
+/// - `RecoveryError::HelperFailed`, with the helper's memo, if the response indicates an error status.
+/// - `RecoveryError::InvalidResponse` if the response does not contain a valid result, cannot be decoded,
+///   names an unknown share algorithm, or does not match the requested version.
+/// - `RecoveryError::SecretIdMismatch` if the share is for another secret ID.
+/// - `RecoveryError::EmptyShare` if the response carries an empty committed share, or one without coordinates.
+/// - `RecoveryError::ThresholdMismatch` if its share was protected under another threshold.
+/// - `RecoveryError::CorruptShares` if its share fails its Merkle proof.
+///
+/// If every response is rejected, the first rejection's error is returned. Otherwise, returns:
+/// - `RecoveryError::MixedSharings` if the shares carry different commitments or share algorithms, i.e. come from
+///   different sharings.
+/// - `RecoveryError::CorruptShares` if the shares disagree on the ciphertext.
+/// - `RecoveryError::InsufficientShares` if fewer than `threshold` distinct valid shares of the sharing with that
+///   threshold are present.
+/// - `RecoveryError::ReconstructionFailed` if the secret cannot be reconstructed from the provided shares.
 pub fn recover_from_share_responses(
     responses: &[GetShareResponseMessage],
     secret_id: impl AsRef<[u8]>,
     version: i32,
-) -> Result<Vec<u8>, &'static str> {
-    let mut shares = Vec::new();
-    for res in responses {
-        match extract_share_from_response(res, &secret_id.as_ref().to_vec(), version) {
-            Ok(share) => shares.push(share),
-            Err(e) => return Err(e),
+    threshold: usize,
+) -> Result<RecoveredSecret, RecoveryError> {
+    recover_shares(responses.iter().map(|res| (None, res)), secret_id, version, threshold)
+}
+
+/// Reconstructs a secret like `recover_from_share_responses`, taking each share's x-coordinate from
+/// the channel it was received on.
+///
+/// `protect_secret` places the share of each channel at `vss::x_for_channel(channel)`. Here the
+/// x-coordinate recorded in each share is replaced by that value, so a share replayed on another
+/// channel (or one whose x was altered) fails its Merkle proof instead of being interpolated.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s to reconstruct the secret from, each paired with
+///   the channel it was received on.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `version` - The version of the secret to recover.
+/// * `threshold` - The number of shares required to reconstruct the secret.
+///
+/// # Errors
+///
+/// Returns the same errors as `recover_from_share_responses`; a share on the wrong channel is rejected
+/// as `RecoveryError::CorruptShares`.
+pub fn recover_from_channel_responses(
+    responses: &[(ChannelId, GetShareResponseMessage)],
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+    threshold: usize,
+) -> Result<RecoveredSecret, RecoveryError> {
+    recover_shares(responses.iter().map(|(channel, res)| (Some(*channel), res)), secret_id, version, threshold)
+}
+
+/// Reconstructs a secret like `recover_from_channel_responses`, from responses keyed by the channel
+/// they were received on.
+///
+/// Keying by channel keeps at most one response per helper, and lets the result report which helpers
+/// contributed to the secret and which were rejected, by channel rather than by position.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage` received on each channel.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `version` - The version of the secret to recover.
+/// * `threshold` - The number of shares required to reconstruct the secret.
+///
+/// # Errors
+///
+/// Returns the same errors as `recover_from_channel_responses`.
+pub fn recover_from_channel_map(
+    responses: &HashMap<ChannelId, GetShareResponseMessage>,
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+    threshold: usize,
+) -> Result<ChannelRecoveredSecret, RecoveryError> {
+    let mut channels: Vec<ChannelId> = responses.keys().copied().collect();
+    channels.sort_unstable();
+
+    let recovered = recover_shares(
+        channels.iter().map(|channel| (Some(*channel), &responses[channel])),
+        secret_id,
+        version,
+        threshold,
+    )?;
+    Ok(ChannelRecoveredSecret {
+        data: recovered.data,
+        contributors: recovered.contributors.into_iter().map(|index| channels[index]).collect(),
+        rejected: recovered.rejected.into_iter().map(|r| (channels[r.index], r.error)).collect(),
+        version: recovered.version,
+        description: recovered.description,
+        keep_list: recovered.keep_list,
+    })
+}
+
+// body of the recover_from_*_responses variants; a share's x-coordinate is
+// derived from its channel whenever the channel is known
+fn recover_shares<'a>(
+    responses: impl Iterator<Item = (Option<ChannelId>, &'a GetShareResponseMessage)>,
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+    threshold: usize,
+) -> Result<RecoveredSecret, RecoveryError> {
+    let mut shares: Vec<VSSShare> = Vec::new();
+    let mut rejected = Vec::new();
+    let mut contributors = Vec::new();
+    let mut metadata = Vec::new();
+    let mut algorithm = None;
+    for (index, (channel, res)) in responses.enumerate() {
+        let (share_algorithm, share_threshold, mut share, committed_description) = match extract_share_from_response(res, secret_id.as_ref(), version) {
+            Ok(extracted) => extracted,
+            Err(error) => {
+                rejected.push(RejectedResponse { index, error });
+                continue;
+            }
+        };
+
+        // a share of the same version protected under another threshold belongs to another sharing
+        if !threshold_matches(share_threshold, threshold) {
+            let error = RecoveryError::ThresholdMismatch { expected: threshold, got: share_threshold as usize };
+            rejected.push(RejectedResponse { index, error });
+            continue;
+        }
+        if let Some(channel) = channel {
+            share.x = x_for_channel(channel);
+        }
+
+        // a tampered share is dropped on its own, without discarding the others
+        // (shares protected without a commitment have no proof to check)
+        if !is_uncommitted(&share) && verify_share(&share).is_err() {
+            rejected.push(RejectedResponse { index, error: RecoveryError::CorruptShares });
+            continue;
+        }
+
+        // shares created by different algorithms cannot belong to the same sharing
+        if *algorithm.get_or_insert(share_algorithm) != share_algorithm {
+            return Err(RecoveryError::MixedSharings);
+        }
+
+        // the same share received twice adds nothing (and would break interpolation)
+        if !shares.iter().any(|s| s.x == share.x) {
+            // a description committed in the share is authenticated, unlike the helper's copy
+            metadata.push(match committed_description {
+                committed if !committed.is_empty() => (committed, res.keep_list.clone()),
+                _ => (res.version_description.clone(), res.keep_list.clone()),
+            });
+            shares.push(share);
+            contributors.push(index);
+        }
+    }
+
+    // a share without a commitment among committed ones has had its proof stripped; it is not to be trusted
+    if shares.iter().any(|share| !is_uncommitted(share)) {
+        let mut i = 0;
+        while i < shares.len() {
+            if is_uncommitted(&shares[i]) {
+                shares.remove(i);
+                metadata.remove(i);
+                rejected.push(RejectedResponse { index: contributors.remove(i), error: RecoveryError::CorruptShares });
+            } else {
+                i += 1;
+            }
+        }
+        rejected.sort_by_key(|r| r.index);
+    }
+
+    // with nothing usable left, the reason the first response was rejected is the most useful error
+    if shares.is_empty() && !rejected.is_empty() {
+        return Err(rejected.swap_remove(0).error);
+    }
+
+    // shares from different sharings (or tampered shares) cannot be interpolated together
+    let have = shares.len();
+    let data = reconstruct_secret(shares, algorithm.unwrap_or_default(), threshold)
+        .map_err(|error| match error {
+            ReconstructionError::Insufficient => RecoveryError::InsufficientShares { have, need: threshold },
+            ReconstructionError::Inconsistent(DerecVSSError::InconsistentCommitments) => RecoveryError::MixedSharings,
+            ReconstructionError::Inconsistent(_) => RecoveryError::CorruptShares,
+            ReconstructionError::Failed(_) => RecoveryError::ReconstructionFailed,
+        })?;
+
+    let (description, keep_list) = metadata.into_iter().next().unwrap_or_default();
+    Ok(RecoveredSecret { data, rejected, contributors, version, description, keep_list })
+}
+
+/// Reconstructs the newest recoverable version of a secret from responses spanning several versions.
+///
+/// A user may not know which version of a secret is the latest, and may have collected responses for
+/// several. This groups the responses by the version recorded in their shares, and tries the requested
+/// `versions` from highest to lowest with `recover_from_share_responses`, returning the first that
+/// reconstructs.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s collected, for any mix of versions.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `versions` - The versions to try.
+/// * `threshold` - The number of shares required to reconstruct the secret.
+///
+/// # Returns
+///
+/// Returns the recovered version together with the secret.
+///
+/// # Errors
+///
+/// If no requested version can be recovered, returns the error for the highest requested version, or
+/// `RecoveryError::InvalidResponse` if `versions` is empty.
+pub fn recover_any_version(
+    responses: &[GetShareResponseMessage],
+    secret_id: impl AsRef<[u8]>,
+    versions: &[i32],
+    threshold: usize,
+) -> Result<(i32, Vec<u8>), RecoveryError> {
+    let mut versions = versions.to_vec();
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    versions.dedup();
+
+    let mut newest_error = None;
+    for version in versions {
+        let group: Vec<GetShareResponseMessage> = responses
+            .iter()
+            .filter(|res| response_version(res) == Some(version))
+            .cloned()
+            .collect();
+
+        match recover_from_share_responses(&group, secret_id.as_ref(), version, threshold) {
+            Ok(secret) => return Ok((version, secret.data)),
+            Err(err) => { newest_error.get_or_insert(err); }
+        }
+    }
+
+    Err(newest_error.unwrap_or(RecoveryError::InvalidResponse("No versions requested")))
+}
+
+/// Reconstructs a secret that was protected with a secret id key, from its `GetShareResponseMessage`s.
+///
+/// Shares protected with `ProtectOptions::secret_id_key` record `sharing::blind_secret_id(secret_id, key)`
+/// instead of `secret_id`. This hashes the requested `secret_id` the same way before matching it against the
+/// shares, and otherwise behaves like `recover_from_share_responses`. The share requests sent to helpers
+/// should likewise carry the blinded identifier.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s to reconstruct the secret from.
+/// * `secret_id` - The (unblinded) identifier of the secret being recovered.
+/// * `secret_id_key` - The sharer-only key the secret was protected with.
+/// * `version` - The version of the secret to recover.
+/// * `threshold` - The number of shares required to reconstruct the secret.
+///
+/// # Errors
+///
+/// Returns the same errors as `recover_from_share_responses`.
+pub fn recover_from_share_responses_with_key(
+    responses: &[GetShareResponseMessage],
+    secret_id: impl AsRef<[u8]>,
+    secret_id_key: &[u8],
+    version: i32,
+    threshold: usize,
+) -> Result<RecoveredSecret, RecoveryError> {
+    recover_from_share_responses(responses, blind_secret_id(secret_id, secret_id_key), version, threshold)
+}
+
+/// Reconstructs a secret like `recover_from_share_responses`, and checks it against a published commitment.
+///
+/// The shares must have been created with `ProtectOptions::commitment_salt`; the salt they carry is
+/// used to recompute `vss::commit_secret(secret, salt)` over the reconstructed secret, which must equal
+/// `commitment`. This confirms that the right secret came back.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s to reconstruct the secret from.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `version` - The version of the secret to recover.
+/// * `threshold` - The number of shares required to reconstruct the secret.
+/// * `commitment` - The commitment published by the sharer before distributing shares.
+///
+/// # Errors
+///
+/// Returns the errors of `recover_from_share_responses`, `RecoveryError::InvalidResponse` if the shares
+/// used carry no salt or disagree on it, or `RecoveryError::CommitmentMismatch` if the reconstructed secret
+/// does not match the commitment.
+pub fn recover_from_share_responses_with_commitment(
+    responses: &[GetShareResponseMessage],
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+    threshold: usize,
+    commitment: &[u8; 32],
+) -> Result<RecoveredSecret, RecoveryError> {
+    let secret = recover_from_share_responses(responses, secret_id, version, threshold)?;
+
+    // only the responses whose shares were used need to agree on the salt
+    let mut salts = responses
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !secret.rejected.iter().any(|r| r.index == *index))
+        .map(|(_, res)| extract_commitment_salt(res));
+    let salt = salts.next().ok_or(RecoveryError::InvalidResponse("No share responses"))?
+        .map_err(RecoveryError::InvalidResponse)?;
+    for other in salts {
+        if other.map_err(RecoveryError::InvalidResponse)? != salt {
+            return Err(RecoveryError::InvalidResponse("Shares disagree on the secret commitment salt"));
         }
     }
+    let salt: [u8; 32] = salt.try_into()
+        .map_err(|_| RecoveryError::InvalidResponse("Shares carry no secret commitment salt"))?;
+
+    if commit_secret(&secret.data, &salt) != *commitment {
+        return Err(RecoveryError::CommitmentMismatch);
+    }
 
-    // Assuming we have a function to reconstruct the secret from shares
-    let reconstructed_secret = recover(&shares)
-        .map_err(|_| "Failed to reconstruct secret from shares")?;
+    Ok(secret)
+}
+
+/// Splits a recovered secret that was packed with `sharing::pack_secrets` back into its items.
+///
+/// # Arguments
+///
+/// * `packed` - The recovered secret, e.g. `RecoveredSecret::data`.
+///
+/// # Returns
+///
+/// Returns the items in the order they were packed, or `RecoveryError::InvalidPacking`
+/// if a length prefix is truncated or runs past the end of `packed`.
+pub fn unpack_secrets(packed: &[u8]) -> Result<Vec<Vec<u8>>, RecoveryError> {
+    let mut items = Vec::new();
+    let mut rest = packed;
+    while !rest.is_empty() {
+        let (len, tail) = rest.split_first_chunk::<4>().ok_or(RecoveryError::InvalidPacking)?;
+        let len = u32::from_be_bytes(*len) as usize;
+        if tail.len() < len {
+            return Err(RecoveryError::InvalidPacking);
+        }
+        let (item, tail) = tail.split_at(len);
+        items.push(item.to_vec());
+        rest = tail;
+    }
+    Ok(items)
+}
 
-    Ok(reconstructed_secret)
+/// Progress of a recovery attempt, as reported by `recovery_progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryProgress {
+    /// number of responses carrying a valid share that is consistent with the others
+    pub valid_shares: usize,
+    /// number of shares needed to reconstruct the secret
+    pub threshold: usize,
+    /// whether enough valid shares have been collected to attempt reconstruction
+    pub ready: bool,
 }
 
+/// Reports how many of the collected responses yield usable shares, without attempting reconstruction.
+///
+/// A response counts towards progress if it carries a successful result, decodes into a share for the
+/// requested `secret_id`, `version` and `threshold`, and the share's Merkle path verifies against its commitment.
+/// Since shares from different sharings cannot be combined, only the largest group of shares that agree
+/// on the commitment and ciphertext is counted, and each x-coordinate is counted once. As in recovery, shares
+/// without a commitment only count if none of the shares carries one.
+///
+/// This lets a client show progress such as "2 of 3 shares collected", and only call
+/// `recover_from_share_responses` once `ready` is true.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s collected so far.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `version` - The version of the secret being recovered.
+/// * `threshold` - The number of shares required for reconstruction.
+///
+/// # Returns
+///
+/// Returns a `RecoveryProgress` with the number of valid shares and whether the threshold is met.
+pub fn recovery_progress(
+    responses: &[GetShareResponseMessage],
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+    threshold: usize,
+) -> RecoveryProgress {
+    let mut valid_shares: Vec<(ShareAlgorithm, VSSShare)> = responses
+        .iter()
+        .filter_map(|res| extract_share_from_response(res, secret_id.as_ref(), version).ok())
+        .filter(|(_, share_threshold, _, _)| threshold_matches(*share_threshold, threshold))
+        .filter(|(_, _, share, _)| is_uncommitted(share) || verify_share(share).is_ok())
+        .map(|(algorithm, _, share, _)| (algorithm, share))
+        .collect();
+
+    // as in recovery, a share without a commitment among committed ones has had its proof stripped
+    if valid_shares.iter().any(|(_, share)| !is_uncommitted(share)) {
+        valid_shares.retain(|(_, share)| !is_uncommitted(share));
+    }
+
+    // group the shares by the sharing they belong to, counting distinct x-coordinates;
+    // erasure-coded shares each carry a different fragment, so only their commitment is compared
+    let mut groups: HashMap<_, HashSet<&[u8]>> = HashMap::new();
+    for (algorithm, share) in &valid_shares {
+        let ciphertext = match algorithm {
+            ShareAlgorithm::ShamirWithErasureCodedEnvelope => &[][..],
+            _ => share.encrypted_secret.as_slice(),
+        };
+        groups
+            .entry((share.commitment.as_slice(), ciphertext))
+            .or_default()
+            .insert(share.x.as_slice());
+    }
+
+    let valid_shares = groups.values().map(|xs| xs.len()).max().unwrap_or(0);
+    RecoveryProgress { valid_shares, threshold, ready: valid_shares >= threshold }
+}
+
+/// Checks that the shares in a set of responses belong to one sharing, without attempting reconstruction.
+///
+/// The shares are decoded as for `recover_from_share_responses` and passed to `vss::detect_error`, so that
+/// a client can report inconsistent shares before any recovery attempt. Erasure-coded shares each carry
+/// a different fragment of the ciphertext, so only their commitments are compared.
+///
+/// # Arguments
+///
+/// * `responses` - The `GetShareResponseMessage`s collected so far.
+/// * `secret_id` - The identifier of the secret being recovered.
+/// * `version` - The version of the secret being recovered.
+///
+/// # Returns
+///
+/// Returns `Ok(None)` if the shares are consistent, `Ok(Some(error))` with the inconsistency `vss::detect_error`
+/// found otherwise, or the `RecoveryError` of the first response that does not decode into a share.
+pub fn detect_inconsistent_shares(
+    responses: &[GetShareResponseMessage],
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+) -> Result<Option<DerecVSSError>, RecoveryError> {
+    let mut shares = Vec::with_capacity(responses.len());
+    for response in responses {
+        let (algorithm, _, mut share, _) = extract_share_from_response(response, secret_id.as_ref(), version)?;
+        if algorithm == ShareAlgorithm::ShamirWithErasureCodedEnvelope {
+            share.encrypted_secret.clear();
+        }
+        shares.push(share);
+    }
+    Ok(detect_error(&shares))
+}
+
+/// Channels that have yet to contribute a share to a recovery, as reported by `outstanding_channels`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutstandingChannels {
+    /// channels that have not responded yet, in the order they were expected
+    pub pending: Vec<ChannelId>,
+    /// channels that responded without a successful result, in the order they were expected
+    pub failed: Vec<ChannelId>,
+}
+
+/// Reports which of the paired channels a recovery coordinator is still waiting on.
+///
+/// A channel is pending if no response has been received from it, and failed if its response carries an
+/// error status (or no result at all); a coordinator would keep polling the former and retry or give up on
+/// the latter. Responses from channels outside `expected` are ignored. Successful responses are not checked
+/// any further; see `recovery_progress` for whether their shares are usable.
+///
+/// # Arguments
+///
+/// * `expected` - The channels the coordinator requested shares from.
+/// * `received` - The responses received so far, keyed by the channel they came from.
+///
+/// # Returns
+///
+/// Returns the `OutstandingChannels` among `expected`.
+pub fn outstanding_channels(
+    expected: &[ChannelId],
+    received: &HashMap<ChannelId, GetShareResponseMessage>,
+) -> OutstandingChannels {
+    let mut outstanding = OutstandingChannels::default();
+    for channel in expected {
+        match received.get(channel).map(|response| response.result.as_ref()) {
+            None => outstanding.pending.push(*channel),
+            Some(Some(result)) if result.status == StatusEnum::Ok as i32 => {}
+            Some(_) => outstanding.failed.push(*channel),
+        }
+    }
+    outstanding
+}
+
+// decodes the share of a response, along with its algorithm, threshold and committed description
 fn extract_share_from_response(
     response: &GetShareResponseMessage,
     secret_id: impl AsRef<[u8]>,
     version: i32
-) -> Result<VSSShare, &'static str> {
+) -> Result<(ShareAlgorithm, i32, VSSShare, String), RecoveryError> {
     if response.result.is_none() {
-        return Err("Response does not contain a result");
+        return Err(RecoveryError::InvalidResponse("Response does not contain a result"));
     }
 
     let result = response.result.as_ref().unwrap();
     if result.status != StatusEnum::Ok as i32 {
-        return Err("Share response indicates an error");
+        return Err(RecoveryError::HelperFailed { memo: result.memo.clone() });
+    }
+
+    let share_algorithm = ShareAlgorithm::try_from(response.share_algorithm)
+        .map_err(RecoveryError::InvalidResponse)?;
+
+    // empty bytes decode to a default share, whose empty coordinates cannot be interpolated
+    if response.committed_de_rec_share.is_empty() {
+        return Err(RecoveryError::EmptyShare);
     }
 
     let committed_derec_share = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice())
-        .map_err(|_| "Failed to decode CommittedDeRecShare")?;
+        .map_err(|_| RecoveryError::InvalidResponse("Failed to decode CommittedDeRecShare"))?;
 
     let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice())
-        .map_err(|_| "Failed to decode DeRecShare")?;
+        .map_err(|_| RecoveryError::InvalidResponse("Failed to decode DeRecShare"))?;
+
+    if derec_share.x.is_empty() || derec_share.y.is_empty() {
+        return Err(RecoveryError::EmptyShare);
+    }
+
+    // every committed share sits in a tree of the same depth, so its path must be complete; the depth is
+    // not taken from the share, where a helper could declare whatever its truncated path needs
+    let (expected, actual) = (MERKLE_TREE_DEPTH, committed_derec_share.merkle_path.len());
+    if !committed_derec_share.commitment.is_empty() && expected as usize != actual {
+        return Err(RecoveryError::IncompleteMerklePath { expected, actual });
+    }
 
-    if derec_share.secret_id != secret_id.as_ref() {
-        return Err("Secret ID in response does not match the requested secret ID");
+    // compared in constant time, as a blinded secret id must not leak through timing
+    if !bool::from(derec_share.secret_id.as_slice().ct_eq(secret_id.as_ref())) {
+        return Err(RecoveryError::SecretIdMismatch {
+            expected: secret_id.as_ref().to_vec(),
+            got: derec_share.secret_id,
+        });
     }
 
     if derec_share.version != version {
-        return Err("Share version in response does not match the requested version");
+        return Err(RecoveryError::InvalidResponse("Share version in response does not match the requested version"));
     }
 
-    let share = VSSShare {
-        x: derec_share.x,
-        y: derec_share.y,
-        encrypted_secret: derec_share.encrypted_secret,
-        commitment: committed_derec_share.commitment,
-        merkle_path: committed_derec_share.merkle_path.iter().map(|h| (h.is_left, h.hash.to_owned())).collect(),
-    };
+    let share = decode_share_of(share_algorithm, committed_derec_share, &derec_share)
+        .map_err(|_| RecoveryError::CorruptShares)?;
+    check_share_encoding(&share).map_err(|_| RecoveryError::MalformedShare)?;
+
+    Ok((share_algorithm, derec_share.threshold, share, derec_share.version_description))
+}
+
+// whether a share recorded with `share_threshold` belongs to a sharing with `threshold`;
+// shares that do not record their threshold (0) are assumed to
+fn threshold_matches(share_threshold: i32, threshold: usize) -> bool {
+    share_threshold == 0 || share_threshold as usize == threshold
+}
+
+// decodes the version recorded in the share of a response, if any
+fn response_version(response: &GetShareResponseMessage) -> Option<i32> {
+    let committed_derec_share = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice()).ok()?;
+    let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice()).ok()?;
+    Some(derec_share.version)
+}
+
+// decodes the secret commitment salt stored alongside the share in a response
+fn extract_commitment_salt(response: &GetShareResponseMessage) -> Result<Vec<u8>, &'static str> {
+    let committed_derec_share = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice())
+        .map_err(|_| "Failed to decode CommittedDeRecShare")?;
+
+    let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice())
+        .map_err(|_| "Failed to decode DeRecShare")?;
 
-    Ok(share)
+    Ok(derec_share.secret_commitment_salt)
 }
 
 #[cfg(test)]
@@ -165,19 +808,20 @@ mod tests {
         for (i, share) in shares.iter().enumerate() {
             // Generate a share response
             let response = super::generate_share_response(
-            &share.0,
-            &secret_id,
-            &super::generate_share_request(&channels[i], &secret_id.to_vec(), version),
+            share.0,
+            secret_id,
+            &super::generate_share_request(&channels[i], secret_id, version),
             share.1,
+            crate::protos::derec_proto::SenderKind::SharerRecovery,
             );
 
             responses.push(response);
         }
 
         // Attempt to recover the secret from the responses
-        let recovered = super::recover_from_share_responses(&responses, &secret_id.to_vec(), version)
+        let recovered = super::recover_from_share_responses(&responses, secret_id, version, threshold)
             .expect("recovery should succeed");
 
-        assert_eq!(recovered, secret);
+        assert_eq!(recovered.data, secret);
     }
 }
\ No newline at end of file
@@ -0,0 +1,780 @@
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use crate::protos::derec_proto::{CommittedDeRecShare, DeRecShare, GetShareResponseMessage, SenderKind, StatusEnum};
+    use crate::pairing::{create_contact_message, produce_pairing_request_message, requestor_sender_kind};
+    use crate::recovery::{
+        generate_share_request,
+        generate_share_response,
+        generate_share_failure_response,
+        recovery_progress,
+        detect_inconsistent_shares,
+        outstanding_channels,
+        recover_from_share_responses,
+        recover_from_channel_responses,
+        recover_from_channel_map,
+        recover_from_share_responses_with_key,
+        recover_from_share_responses_with_commitment,
+        recover_any_version,
+        unpack_secrets,
+        RecoveryError,
+        RejectedResponse,
+        OutstandingChannels,
+    };
+    use derec_cryptography::vss::commit_secret;
+    use crate::sharing::{pack_secrets, protect_secret, protect_secret_with_options, blind_secret_id, ProtectOptions, ShareAlgorithm};
+
+    fn share_responses(
+        secret_id: &[u8],
+        secret: &[u8],
+        channels: &[u64],
+        threshold: usize,
+        version: i32,
+    ) -> Vec<GetShareResponseMessage> {
+        share_responses_with_options(secret_id, secret, channels, threshold, version, &ProtectOptions::default())
+    }
+
+    fn share_responses_with_options(
+        secret_id: &[u8],
+        secret: &[u8],
+        channels: &[u64],
+        threshold: usize,
+        version: i32,
+        options: &ProtectOptions,
+    ) -> Vec<GetShareResponseMessage> {
+        let sharing = protect_secret_with_options(secret_id, secret, channels, threshold, version, options)
+            .expect("protect_secret should succeed");
+
+        channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, secret_id, version);
+                generate_share_response(channel, secret_id, &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect()
+    }
+
+    // flips a bit of the share's y-coordinate inside the committed share
+    fn corrupt_response(response: &mut GetShareResponseMessage) {
+        let mut committed = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.y[0] ^= 1;
+        committed.de_rec_share = share.encode_to_vec();
+        response.committed_de_rec_share = committed.encode_to_vec();
+    }
+
+    #[test]
+    fn test_recovery_progress_counts_only_valid_shares() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 3, 1);
+        corrupt_response(&mut responses[2]);
+
+        let progress = recovery_progress(&responses, b"id", 1, 3);
+        assert_eq!(progress.valid_shares, 2);
+        assert_eq!(progress.threshold, 3);
+        assert!(!progress.ready);
+
+        // a duplicate of a valid response does not count twice
+        responses.push(responses[0].clone());
+        assert_eq!(recovery_progress(&responses, b"id", 1, 3).valid_shares, 2);
+
+        // shares for another version are ignored entirely
+        assert_eq!(recovery_progress(&responses, b"id", 2, 3).valid_shares, 0);
+    }
+
+    #[test]
+    fn test_recovery_with_blinded_secret_id() {
+        let (secret_id, key, channels) = (b"bank-password", b"sharer-only key", [1, 2, 3]);
+        let options = ProtectOptions { secret_id_key: Some(key), ..Default::default() };
+        let sharing = protect_secret_with_options(secret_id, b"hunter2", channels, 2, 1, &options).unwrap();
+
+        // helpers never see the plaintext identifier
+        let blinded = blind_secret_id(secret_id, key);
+        for msg in sharing.values() {
+            let committed = CommittedDeRecShare::decode(msg.share.as_slice()).unwrap();
+            let share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+            assert_ne!(share.secret_id, secret_id.to_vec());
+            assert_eq!(share.secret_id, blinded);
+        }
+
+        let responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, &blinded, 1);
+                generate_share_response(channel, &blinded, &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        assert_eq!(recover_from_share_responses_with_key(&responses, secret_id, key, 1, 2).unwrap().data, b"hunter2");
+
+        // looking the shares up by the plaintext identifier, or under another key, fails
+        assert!(recover_from_share_responses(&responses, secret_id, 1, 2).is_err());
+        assert!(recover_from_share_responses_with_key(&responses, secret_id, b"other key", 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_share_request_from_non_recovery_sharer_is_refused() {
+        let sharing = protect_secret(b"id", b"secret", [1, 2, 3], 2, 1, None, None).unwrap();
+
+        // the helper records the kind the sharer paired as
+        let (contact, _) = create_contact_message(1, "https://helper.example");
+        let (pair_request, _) = produce_pairing_request_message(1, SenderKind::SharerNonRecovery, &contact).unwrap();
+        let peer_kind = requestor_sender_kind(&pair_request);
+        assert_eq!(peer_kind, SenderKind::SharerNonRecovery);
+
+        let request = generate_share_request(&1, b"id", 1);
+        let response = generate_share_response(&1, b"id", &request, &sharing[&1], peer_kind);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Fail as i32);
+        assert!(response.committed_de_rec_share.is_empty());
+        assert!(recover_from_share_responses(&[response], b"id", 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_share_request_for_another_version_or_secret_is_refused() {
+        let sharing = protect_secret(b"id", b"secret", [1, 2, 3], 2, 2, None, None).unwrap();
+
+        let request = generate_share_request(&1, b"id", 5);
+        let response = generate_share_response(&1, b"id", &request, &sharing[&1], SenderKind::SharerRecovery);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Fail as i32);
+        assert!(response.committed_de_rec_share.is_empty());
+
+        let request = generate_share_request(&1, b"other id", 2);
+        let response = generate_share_response(&1, b"id", &request, &sharing[&1], SenderKind::SharerRecovery);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Fail as i32);
+
+        let request = generate_share_request(&1, b"id", 2);
+        let response = generate_share_response(&1, b"id", &request, &sharing[&1], SenderKind::SharerRecovery);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Ok as i32);
+    }
+
+    #[test]
+    fn test_recovered_secret_matches_published_commitment() {
+        let salt = [0x5a; 32];
+        let commitment = commit_secret(b"hunter2", &salt);
+
+        let options = ProtectOptions { commitment_salt: Some(&salt), ..Default::default() };
+        let responses = share_responses_with_options(b"id", b"hunter2", &[1, 2, 3], 2, 1, &options);
+
+        let recovered = recover_from_share_responses_with_commitment(&responses, b"id", 1, 2, &commitment).unwrap();
+        assert_eq!(commit_secret(&recovered.data, &salt), commitment);
+    }
+
+    #[test]
+    fn test_recovered_secret_against_wrong_commitment_fails() {
+        let salt = [0x5a; 32];
+        let wrong_commitment = commit_secret(b"not hunter2", &salt);
+
+        let options = ProtectOptions { commitment_salt: Some(&salt), ..Default::default() };
+        let responses = share_responses_with_options(b"id", b"hunter2", &[1, 2, 3], 2, 1, &options);
+
+        assert_eq!(
+            recover_from_share_responses_with_commitment(&responses, b"id", 1, 2, &wrong_commitment),
+            Err(RecoveryError::CommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn test_recovery_below_threshold_is_rejected() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 3, 1);
+        responses.truncate(2);
+
+        assert_eq!(
+            recover_from_share_responses(&responses, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+
+        // a duplicated response is not a distinct share
+        responses.push(responses[0].clone());
+        assert_eq!(
+            recover_from_share_responses(&responses, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+    }
+
+    #[test]
+    fn test_recovery_from_mixed_sharings_is_rejected() {
+        // two independent sharings of the same secret id and version
+        let first = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+        let second = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+
+        let mixed = vec![first[0].clone(), second[1].clone()];
+        assert_eq!(recover_from_share_responses(&mixed, b"id", 1, 2), Err(RecoveryError::MixedSharings));
+
+        // the same number of shares from one sharing recovers fine
+        assert_eq!(recover_from_share_responses(&first[..2], b"id", 1, 2).unwrap().data, b"secret");
+    }
+
+    #[test]
+    fn test_recovery_dispatches_on_share_algorithm() {
+        let channels = [1, 2, 3];
+        for algorithm in [ShareAlgorithm::ShamirBw6, ShareAlgorithm::ShamirWithAesEnvelope] {
+            let options = ProtectOptions { share_algorithm: algorithm, ..Default::default() };
+            let sharing = protect_secret_with_options(b"id", b"hunter2", channels, 2, 1, &options).unwrap();
+
+            let responses: Vec<_> = channels
+                .iter()
+                .map(|channel| {
+                    assert_eq!(sharing[channel].share_algorithm, algorithm as i32);
+                    let request = generate_share_request(channel, b"id", 1);
+                    generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+                })
+                .collect();
+
+            // only the envelope carries an encrypted copy of the secret
+            let committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+            let share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+            assert_eq!(share.encrypted_secret.is_empty(), algorithm == ShareAlgorithm::ShamirBw6);
+
+            assert_eq!(recover_from_share_responses(&responses[..2], b"id", 1, 2).unwrap().data, b"hunter2");
+
+            // recovering along the other path does not yield the secret; a mislabeled envelope key can
+            // pass the direct path's padding check by chance, so only the result is asserted on
+            let mut mislabeled = responses.clone();
+            for response in &mut mislabeled {
+                response.share_algorithm = 1 - algorithm as i32;
+            }
+            let recovered = recover_from_share_responses(&mislabeled[..2], b"id", 1, 2);
+            assert_ne!(recovered.map(|recovered| recovered.data), Ok(b"hunter2".to_vec()));
+
+            let mut unknown = responses.clone();
+            unknown[0].share_algorithm = 7;
+            assert_eq!(
+                recover_from_share_responses(&unknown, b"id", 1, 2).unwrap().rejected,
+                vec![RejectedResponse { index: 0, error: RecoveryError::InvalidResponse("Unknown share algorithm") }]
+            );
+            assert_eq!(
+                recover_from_share_responses(&unknown[..1], b"id", 1, 2),
+                Err(RecoveryError::InvalidResponse("Unknown share algorithm"))
+            );
+        }
+
+        // a secret too large for a single field element needs the envelope
+        let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirBw6, ..Default::default() };
+        assert!(protect_secret_with_options(b"id", [0u8; 64], channels, 2, 1, &options).is_err());
+    }
+
+    #[test]
+    fn test_recovery_rejects_empty_committed_share() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+        responses[1].committed_de_rec_share = Vec::new();
+        assert_eq!(recover_from_share_responses(&responses[1..2], b"id", 1, 2), Err(RecoveryError::EmptyShare));
+
+        // a committed share wrapping a default DeRecShare is just as empty
+        let default_share = DeRecShare { secret_id: b"id".to_vec(), version: 1, ..Default::default() };
+        let committed = CommittedDeRecShare { de_rec_share: default_share.encode_to_vec(), ..Default::default() };
+        responses[1].committed_de_rec_share = committed.encode_to_vec();
+        assert_eq!(recover_from_share_responses(&responses[1..2], b"id", 1, 2), Err(RecoveryError::EmptyShare));
+
+        // the remaining responses still suffice, and the empty one is reported
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 1, error: RecoveryError::EmptyShare }]);
+    }
+
+    #[test]
+    fn test_recover_any_version_picks_recoverable_version() {
+        let mut responses = share_responses(b"id", b"old secret", &[1, 2, 3], 3, 1);
+        responses.extend(share_responses(b"id", b"new secret", &[1, 2, 3], 3, 2));
+
+        // only two shares of version 1 made it back
+        responses.remove(0);
+        assert_eq!(
+            recover_any_version(&responses, b"id", &[1, 2], 3).unwrap(),
+            (2, b"new secret".to_vec())
+        );
+
+        // with version 2 short of shares instead, version 1 is recovered
+        let mut responses = share_responses(b"id", b"old secret", &[1, 2, 3], 3, 1);
+        responses.extend(share_responses(b"id", b"new secret", &[1, 2, 3], 3, 2));
+        responses.pop();
+        assert_eq!(
+            recover_any_version(&responses, b"id", &[2, 1], 3).unwrap(),
+            (1, b"old secret".to_vec())
+        );
+
+        // if nothing is recoverable, the newest version's error is reported
+        assert_eq!(
+            recover_any_version(&responses, b"id", &[2], 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+    }
+
+    #[test]
+    fn test_recover_from_channel_responses_derives_x_from_channel() {
+        let channels = [10u64, 20, 30];
+        let responses = share_responses(b"id", b"secret", &channels, 2, 1);
+
+        // each share sits at the x-coordinate of its channel
+        for (channel, response) in channels.iter().zip(responses.iter()) {
+            let committed = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice()).unwrap();
+            let share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+            assert_eq!(share.x, derec_cryptography::vss::x_for_channel(*channel));
+        }
+
+        let by_channel: Vec<(u64, GetShareResponseMessage)> =
+            channels.iter().copied().zip(responses.iter().cloned()).collect();
+        assert_eq!(recover_from_channel_responses(&by_channel, b"id", 1, 2).unwrap().data, b"secret");
+
+        // a share presented on another helper's channel no longer verifies
+        let swapped = [(10, responses[1].clone()), (20, responses[0].clone())];
+        assert_eq!(
+            recover_from_channel_responses(&swapped, b"id", 1, 2),
+            Err(RecoveryError::CorruptShares)
+        );
+    }
+
+    #[test]
+    fn test_recovery_groups_shares_by_threshold() {
+        // the secret was re-protected as 3-of-5, but the old 2-of-3 shares are still around
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+        responses.extend(share_responses(b"id", b"secret", &[4, 5, 6, 7, 8], 3, 1));
+
+        assert_eq!(recover_from_share_responses(&responses, b"id", 1, 2).unwrap().data, b"secret");
+        assert_eq!(recover_from_share_responses(&responses, b"id", 1, 3).unwrap().data, b"secret");
+        assert_eq!(recovery_progress(&responses, b"id", 1, 3).valid_shares, 5);
+
+        // plenty of shares overall, but only two of the 3-of-5 sharing
+        let mixed: Vec<_> = responses[..3].iter().chain(&responses[3..5]).cloned().collect();
+        assert_eq!(
+            recover_from_share_responses(&mixed, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+        assert_eq!(recover_from_share_responses(&mixed, b"id", 1, 2).unwrap().data, b"secret");
+
+        // the shares of the other sharing are reported, with the threshold they were protected under
+        let recovered = recover_from_share_responses(&mixed, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.contributors, vec![0, 1, 2]);
+        assert_eq!(
+            recovered.rejected,
+            [3, 4].map(|index| RejectedResponse { index, error: RecoveryError::ThresholdMismatch { expected: 2, got: 3 } })
+        );
+
+        // and when no share matches, the mismatch is the error
+        assert_eq!(
+            recover_from_share_responses(&responses[3..], b"id", 1, 2),
+            Err(RecoveryError::ThresholdMismatch { expected: 2, got: 3 })
+        );
+    }
+
+    #[test]
+    fn test_recovery_proceeds_past_bad_responses() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3, 4, 5], 3, 1);
+        responses[1].committed_de_rec_share = vec![0xff; 7];
+        corrupt_response(&mut responses[3]);
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 3).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(
+            recovered.rejected,
+            vec![
+                RejectedResponse { index: 1, error: RecoveryError::InvalidResponse("Failed to decode CommittedDeRecShare") },
+                RejectedResponse { index: 3, error: RecoveryError::CorruptShares },
+            ]
+        );
+
+        // with a third bad response, the good ones fall short
+        corrupt_response(&mut responses[4]);
+        assert_eq!(
+            recover_from_share_responses(&responses, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+    }
+
+    #[test]
+    fn test_erasure_coded_envelope_survives_a_lost_fragment() {
+        let channels = [1, 2, 3, 4];
+        let secret = [0x5a; 200];
+        let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirWithErasureCodedEnvelope, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", secret, channels, 3, 1, &options).unwrap();
+
+        let responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, b"id", 1);
+                generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        // every helper holds a different fragment, each a fraction of the ciphertext
+        let fragments: Vec<Vec<u8>> = responses
+            .iter()
+            .map(|response| {
+                let committed = CommittedDeRecShare::decode(response.committed_de_rec_share.as_slice()).unwrap();
+                DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap().encrypted_secret
+            })
+            .collect();
+        assert!(fragments.iter().all(|fragment| fragment.len() < secret.len()));
+        assert_eq!(fragments.iter().collect::<std::collections::HashSet<_>>().len(), channels.len());
+
+        // the fragment of the first helper is lost, the rest rebuild the ciphertext and then the secret
+        let ciphertext = derec_cryptography::vss::erasure_decode(&fragments[1..]).unwrap();
+        assert!(ciphertext.len() > secret.len());
+        assert!(recovery_progress(&responses[1..], b"id", 1, 3).ready);
+        assert_eq!(recover_from_share_responses(&responses[1..], b"id", 1, 3).unwrap().data, secret);
+
+        assert_eq!(
+            recover_from_share_responses(&responses[2..], b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+    }
+
+    #[test]
+    fn test_erasure_coded_recovery_skips_a_tampered_fragment() {
+        let channels = [1, 2, 3, 4];
+        let secret = [0x5a; 200];
+        let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirWithErasureCodedEnvelope, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", secret, channels, 3, 1, &options).unwrap();
+        let mut responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, b"id", 1);
+                generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        // the first helper alters its fragment, keeping its header; the leaf covers the fragment's digest
+        let mut committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        *share.encrypted_secret.last_mut().unwrap() ^= 1;
+        committed.de_rec_share = share.encode_to_vec();
+        responses[0].committed_de_rec_share = committed.encode_to_vec();
+
+        // it also cannot rewrite the digests to match, since they are bound to the commitment
+        let mut committed = CommittedDeRecShare::decode(responses[1].committed_de_rec_share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.encrypted_secret[10] ^= 1;
+        let digest = <sha2::Sha256 as sha2::Digest>::digest(&share.encrypted_secret);
+        let offset = share.encrypted_secret[0] as usize * 32;
+        share.fragment_digests[offset..offset + 32].copy_from_slice(&digest);
+        committed.de_rec_share = share.encode_to_vec();
+        let forged = GetShareResponseMessage { committed_de_rec_share: committed.encode_to_vec(), ..responses[1].clone() };
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 3).unwrap();
+        assert_eq!(recovered.data, secret);
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 0, error: RecoveryError::CorruptShares }]);
+        assert!(!recovery_progress(&responses[..3], b"id", 1, 3).ready);
+
+        let with_forged = [forged, responses[2].clone(), responses[3].clone()];
+        assert_eq!(
+            recover_from_share_responses(&with_forged, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+    }
+
+    #[test]
+    fn test_recover_from_channel_map_counts_each_channel_once() {
+        let channels = [10u64, 20, 30];
+        let responses = share_responses(b"id", b"secret", &channels, 3, 1);
+
+        // the response of channel 10 arrives twice; keying by channel keeps one of them
+        let arrivals = [
+            (10, responses[0].clone()),
+            (20, responses[1].clone()),
+            (10, responses[0].clone()),
+        ];
+        let by_channel: std::collections::HashMap<_, _> = arrivals.iter().cloned().collect();
+        assert_eq!(
+            recover_from_channel_map(&by_channel, b"id", 1, 3),
+            Err(RecoveryError::InsufficientShares { have: 2, need: 3 })
+        );
+
+        // with every helper answering, all are reported as contributors; a bad one is reported by channel
+        let mut by_channel: std::collections::HashMap<_, _> = channels.iter().copied().zip(responses.iter().cloned()).collect();
+        let recovered = recover_from_channel_map(&by_channel, b"id", 1, 3).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(recovered.contributors, vec![10, 20, 30]);
+
+        by_channel.insert(40, responses[0].clone());
+        let recovered = recover_from_channel_map(&by_channel, b"id", 1, 3).unwrap();
+        assert_eq!(recovered.contributors, vec![10, 20, 30]);
+        assert_eq!(recovered.rejected, vec![(40, RecoveryError::CorruptShares)]);
+    }
+
+    #[test]
+    fn test_recovery_rejects_non_canonical_coordinates() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+
+        // a trailing byte leaves the decoded value unchanged, but makes another encoding of it
+        let mut committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.x.push(0);
+        committed.de_rec_share = share.encode_to_vec();
+        responses[0].committed_de_rec_share = committed.encode_to_vec();
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 0, error: RecoveryError::MalformedShare }]);
+    }
+
+    #[test]
+    fn test_recovery_rejects_truncated_merkle_path() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+
+        let mut committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+        committed.merkle_path.pop();
+        responses[0].committed_de_rec_share = committed.encode_to_vec();
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        let depth = derec_cryptography::vss::MERKLE_TREE_DEPTH;
+        assert_eq!(
+            recovered.rejected,
+            vec![RejectedResponse {
+                index: 0,
+                error: RecoveryError::IncompleteMerklePath { expected: depth, actual: depth as usize - 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_recovery_returns_version_metadata() {
+        let channels = [1u64, 2, 3];
+        let sharing = protect_secret(b"id", b"secret", channels, 2, 4, Some(&[3, 4]), Some("moved to new bank")).unwrap();
+        let mut responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, b"id", 4);
+                generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        let recovered = recover_from_share_responses(&responses, b"id", 4, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(recovered.version, 4);
+        assert_eq!(recovered.description, "moved to new bank");
+        assert_eq!(recovered.keep_list, vec![3, 4]);
+
+        // a description committed in the shares wins over what a helper reports
+        let options = ProtectOptions { description: Some("moved to new bank"), commit_description: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", channels, 2, 4, &options).unwrap();
+        for (channel, response) in channels.iter().zip(responses.iter_mut()) {
+            let request = generate_share_request(channel, b"id", 4);
+            *response = generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery);
+            response.version_description = String::from("nothing changed");
+        }
+        assert_eq!(recover_from_share_responses(&responses, b"id", 4, 2).unwrap().description, "moved to new bank");
+    }
+
+    #[test]
+    fn test_recovery_without_commitments() {
+        let channels = [1u64, 2, 3];
+        let options = ProtectOptions { omit_commitment: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", channels, 2, 1, &options).unwrap();
+        let responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, b"id", 1);
+                generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        let committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+        assert!(committed.commitment.is_empty() && committed.merkle_path.is_empty());
+        assert!(recovery_progress(&responses[1..], b"id", 1, 2).ready);
+        assert_eq!(recover_from_share_responses(&responses[1..], b"id", 1, 2).unwrap().data, b"secret");
+
+        // next to committed shares, a share without a commitment is rejected rather than trusted
+        let sharing = protect_secret(b"id", b"secret", channels, 2, 1, None, None).unwrap();
+        let mut mixed = responses.clone();
+        for (channel, response) in channels.iter().zip(mixed.iter_mut()).skip(1) {
+            let request = generate_share_request(channel, b"id", 1);
+            *response = generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery);
+        }
+        let recovered = recover_from_share_responses(&mixed, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(recovered.contributors, vec![1, 2]);
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 0, error: RecoveryError::CorruptShares }]);
+
+        // progress counts the same shares recovery would use
+        let mut mostly_uncommitted = responses.clone();
+        mostly_uncommitted[2] = mixed[2].clone();
+        let progress = recovery_progress(&mostly_uncommitted, b"id", 1, 2);
+        assert_eq!((progress.valid_shares, progress.ready), (1, false));
+        assert_eq!(
+            recover_from_share_responses(&mostly_uncommitted, b"id", 1, 2),
+            Err(RecoveryError::InsufficientShares { have: 1, need: 2 })
+        );
+    }
+
+    #[test]
+    fn test_packed_secrets_round_trip() {
+        let items: [&[u8]; 3] = [b"bank pin", b"", b"a much longer recovery passphrase"];
+        let channels = [1u64, 2, 3];
+        let sharing = protect_secret(b"id", pack_secrets(&items), channels, 2, 1, None, None).unwrap();
+        let responses: Vec<_> = channels
+            .iter()
+            .map(|channel| {
+                let request = generate_share_request(channel, b"id", 1);
+                generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        let recovered = recover_from_share_responses(&responses[..2], b"id", 1, 2).unwrap();
+        assert_eq!(unpack_secrets(&recovered.data).unwrap(), items.map(|item| item.to_vec()).to_vec());
+
+        // a truncated prefix or an item running past the end is rejected
+        assert_eq!(unpack_secrets(&[0, 0, 1]), Err(RecoveryError::InvalidPacking));
+        assert_eq!(unpack_secrets(&[0, 0, 0, 2, 7]), Err(RecoveryError::InvalidPacking));
+    }
+
+    #[test]
+    fn test_share_of_another_version_is_detected() {
+        let channels = [1u64, 2, 3];
+        let v1 = protect_secret(b"id", b"old secret", channels, 2, 1, None, None).unwrap();
+        let mut responses = share_responses(b"id", b"new secret", &channels, 2, 2);
+
+        // a helper relabels its version 1 share as version 2; the Merkle path itself is untouched
+        let mut committed = CommittedDeRecShare::decode(v1[&1].share.as_slice()).unwrap();
+        let mut derec_share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        derec_share.version = 2;
+        committed.de_rec_share = derec_share.encode_to_vec();
+        let mut swapped = v1[&1].clone();
+        swapped.version = 2;
+        swapped.share = committed.encode_to_vec();
+        let request = generate_share_request(&1, b"id", 2);
+        responses[0] = generate_share_response(&1, b"id", &request, &swapped, SenderKind::SharerRecovery);
+
+        assert!(!recovery_progress(&responses[..2], b"id", 2, 2).ready);
+        assert_eq!(
+            recover_from_share_responses(&responses[..2], b"id", 2, 2),
+            Err(RecoveryError::InsufficientShares { have: 1, need: 2 })
+        );
+        let recovered = recover_from_share_responses(&responses, b"id", 2, 2).unwrap();
+        assert_eq!(recovered.data, b"new secret");
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 0, error: RecoveryError::CorruptShares }]);
+    }
+
+    #[test]
+    fn test_detect_inconsistent_shares_before_recovery() {
+        let consistent = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+        assert!(matches!(detect_inconsistent_shares(&consistent, b"id", 1), Ok(None)));
+
+        // a share of another sharing of the same secret and version
+        let mut mixed = consistent.clone();
+        mixed[2] = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1).remove(2);
+        assert!(matches!(
+            detect_inconsistent_shares(&mixed, b"id", 1),
+            Ok(Some(derec_cryptography::vss::DerecVSSError::InconsistentCommitments))
+        ));
+
+        // responses that do not decode are reported as such
+        assert_eq!(detect_inconsistent_shares(&consistent, b"id", 2).err(), Some(RecoveryError::InvalidResponse(
+            "Share version in response does not match the requested version"
+        )));
+
+        // erasure-coded shares carry different fragments, but are consistent
+        let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirWithErasureCodedEnvelope, ..Default::default() };
+        let erasure_coded = share_responses_with_options(b"id", &[7u8; 100], &[1, 2, 3], 2, 1, &options);
+        assert!(matches!(detect_inconsistent_shares(&erasure_coded, b"id", 1), Ok(None)));
+
+        // the WASM binding reports the inconsistency by name
+        use derec_cryptography::vss::DerecVSSError;
+        assert_eq!(crate::recovery::inconsistency_name(&DerecVSSError::InconsistentCommitments), "InconsistentCommitments");
+        assert_eq!(crate::recovery::inconsistency_name(&DerecVSSError::TooManyChannels { max: 128 }), "TooManyChannels");
+    }
+
+    #[test]
+    fn test_share_for_another_secret_id_reports_both_ids() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+
+        let mut committed = CommittedDeRecShare::decode(responses[0].committed_de_rec_share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.secret_id = b"other".to_vec();
+        committed.de_rec_share = share.encode_to_vec();
+        responses[0].committed_de_rec_share = committed.encode_to_vec();
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"secret");
+        assert_eq!(
+            recovered.rejected,
+            vec![RejectedResponse {
+                index: 0,
+                error: RecoveryError::SecretIdMismatch { expected: b"id".to_vec(), got: b"other".to_vec() },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_refused_share_request_reports_the_memo() {
+        let mut responses = share_responses(b"id", b"secret", &[1, 2, 3], 2, 1);
+        responses[0] = generate_share_failure_response("version pruned");
+
+        let recovered = recover_from_share_responses(&responses, b"id", 1, 2).unwrap();
+        let error = RecoveryError::HelperFailed { memo: "version pruned".to_string() };
+        assert_eq!(recovered.rejected, vec![RejectedResponse { index: 0, error: error.clone() }]);
+        assert_eq!(error.to_string(), "helper refused the request: version pruned");
+
+        let error = recover_from_share_responses(&responses[..1], b"id", 1, 2).unwrap_err();
+        assert!(error.to_string().contains("version pruned"));
+    }
+
+    #[test]
+    fn test_outstanding_channels_separates_pending_from_failed() {
+        let secret_id = b"outstanding";
+        let expected = [1, 2, 3, 4, 5];
+        let responses = share_responses(secret_id, b"coordinated secret", &[1, 2], 2, 1);
+
+        let mut received = std::collections::HashMap::new();
+        received.insert(1, responses[0].clone());
+        received.insert(2, responses[1].clone());
+        received.insert(4, generate_share_failure_response("share not found"));
+        // a stray response from a channel we did not ask is ignored
+        received.insert(9, generate_share_failure_response("unknown secret"));
+
+        assert_eq!(
+            outstanding_channels(&expected, &received),
+            OutstandingChannels { pending: vec![3, 5], failed: vec![4] }
+        );
+    }
+
+    // store-share requests produced by `protect_secret` before the share algorithm could be chosen:
+    // secret id `baseline-id`, secret `hunter2`, version 1, channels 1 and 2 of a 2-of-3 sharing
+    const BASELINE_SHARE_REQUESTS: [&[&str]; 2] = [
+        &[
+            "0ab9030a98010a2353ee8c04591a39db6eb89a12ffd6d24afdcce4f6c2e554a2cd9aef845d04df35c12cbf1230d373a8",
+            "d07b4d56539e17ad86823af22620952fdac5a85ae20ccd3ca1350f76d5a1bf28dacb153c346455ec5caae352001a3026",
+            "a0607ef2b50447a9f5d02434cea94188b8db8057c2f985e9d14b2e05a8a3634df80742dc5cdcacc32958561aef970122",
+            "0b626173656c696e652d69642801122070bfbbc7776dd2ea17a2394227c2d80344f8aa3f31ef5857d6576904f1cdf837",
+            "1a2212208ec4817917fe4d684b940de06aa4137f3358550bcb7eb673d6410ed2669fed841a221220973c961c54c50a30",
+            "181de851e4155f545368741b489e82fe876103a4ea90e15e1a221220cba36960a94b81a067793c809efeadadfd92cc8d",
+            "a2b692ba2540a88dc4d6c4db1a22122046f869eb61152be158fdcfa5e0492ec8adb6863eaf146d5d80751ea2f875bed3",
+            "1a22122030a7e71b0c33206c923569a8f93733083c7346244ad0acf25818a600da3807f21a221220a9669804a2d7867f",
+            "ba6d4d5ac4b894d3c0dfd582921bf6ef68a16a8ed1c3aa251a221220c3571439e165aa924ebe178b5dca9f60a65c7cd4",
+            "8359ebabe1e1656da74817271801",
+        ],
+        &[
+            "0abb030a98010a2353ee8c04591a39db6eb89a12ffd6d24afdcce4f6c2e554a2cd9aef845d04df35c12cbf12302f4ca1",
+            "bb37a5ec132769b7ddb73daff6c1e4b2989e9fe7155cf629c46aa83706a40606bbb3401fce8a4ddb9f401c27011a307c",
+            "82f539f9dd9cd30ebae6cdfe2847a53757d557c92b3f2fbbd0e0893a7ca957a215a1e33271e4397fac22674a44100022",
+            "0b626173656c696e652d69642801122070bfbbc7776dd2ea17a2394227c2d80344f8aa3f31ef5857d6576904f1cdf837",
+            "1a240801122077d03895566c626bfaea605010ca5578ef4bedef2ebd35daa4af0504f4ea61081a221220973c961c54c5",
+            "0a30181de851e4155f545368741b489e82fe876103a4ea90e15e1a221220cba36960a94b81a067793c809efeadadfd92",
+            "cc8da2b692ba2540a88dc4d6c4db1a22122046f869eb61152be158fdcfa5e0492ec8adb6863eaf146d5d80751ea2f875",
+            "bed31a22122030a7e71b0c33206c923569a8f93733083c7346244ad0acf25818a600da3807f21a221220a9669804a2d7",
+            "867fba6d4d5ac4b894d3c0dfd582921bf6ef68a16a8ed1c3aa251a221220c3571439e165aa924ebe178b5dca9f60a65c",
+            "7cd48359ebabe1e1656da74817271801",
+        ],
+    ];
+
+    #[test]
+    fn test_baseline_share_requests_still_recover() {
+        let responses: Vec<_> = BASELINE_SHARE_REQUESTS
+            .iter()
+            .zip([1u64, 2])
+            .map(|(hex, channel)| {
+                let hex = hex.concat();
+                let bytes: Vec<u8> = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                    .collect();
+                let stored = crate::protos::derec_proto::StoreShareRequestMessage::decode(bytes.as_slice()).unwrap();
+                assert_eq!(stored.share_algorithm, ShareAlgorithm::ShamirWithAesEnvelope as i32);
+
+                let request = generate_share_request(&channel, b"baseline-id", 1);
+                generate_share_response(&channel, b"baseline-id", &request, &stored, SenderKind::SharerRecovery)
+            })
+            .collect();
+
+        let recovered = recover_from_share_responses(&responses, b"baseline-id", 1, 2).unwrap();
+        assert_eq!(recovered.data, b"hunter2");
+    }
+}
@@ -1,6 +1,37 @@
+#[allow(clippy::module_inception)]
 pub mod sharing;
 use prost::Message;
 pub use sharing::protect_secret;
+pub use sharing::protect_secret_ordered;
+pub use sharing::protect_secret_with_options;
+pub use sharing::protect_secret_with_rng;
+pub use sharing::protect_secret_with_key;
+pub use sharing::protect_secret_deterministic;
+pub use sharing::ProtectOptions;
+pub use sharing::ShareAlgorithm;
+pub use sharing::validate_secret_len;
+pub use sharing::blind_secret_id;
+pub use sharing::pack_secrets;
+pub use sharing::STORE_CHALLENGE_SIZE;
+pub use sharing::generate_store_challenge;
+pub use sharing::authorize_store_request;
+pub use sharing::verify_store_authorization;
+pub use sharing::extract_channel_share;
+pub use sharing::share_x_coordinates;
+pub use sharing::is_recoverable;
+pub use sharing::verify_share_round_trip;
+pub use sharing::recovery_probability;
+pub use sharing::recommend_parameters;
+pub use sharing::estimate_share_size;
+pub use sharing::check_version_consistency;
+pub use sharing::generate_store_share_response;
+pub use sharing::process_store_share_response;
+pub use sharing::StoreShareError;
+pub use sharing::DistributionStatus;
+pub use sharing::export_sharing;
+pub use sharing::import_sharing;
+
+use crate::protos::derec_proto::StoreShareResponseMessage;
 
 use wasm_bindgen::prelude::*;
 use std::collections::HashMap;
@@ -10,6 +41,10 @@ struct TsProtectSecretResult {
     value: HashMap<u64, Vec<u8>>,
 }
 
+/// Protects a secret like `protect_secret`, returning `{ value: Map<channel, StoreShareRequestMessage bytes> }`.
+///
+/// Returns `Err` with a description of the problem if the sharing cannot be made, e.g. with no
+/// channels, more channels than `vss::MAX_SHARES`, or a threshold above the number of channels.
 #[wasm_bindgen]
 pub fn ts_protect_secret(
     secret_id: &[u8],
@@ -17,20 +52,48 @@ pub fn ts_protect_secret(
     channels: &[u64],
     threshold: u32,
     version: u32,
-) -> JsValue {
+) -> Result<JsValue, String> {
+    ts_protect_secret_with_options(secret_id, secret_data, channels, threshold, version, false)
+}
 
-    let sharing = sharing::protect_secret(
+/// Protects a secret like `ts_protect_secret`. With `allow_trivial_threshold`, a threshold of 1 is
+/// accepted, letting any single helper recover the secret (see `ProtectOptions::allow_trivial_threshold`).
+#[wasm_bindgen]
+pub fn ts_protect_secret_with_options(
+    secret_id: &[u8],
+    secret_data: &[u8],
+    channels: &[u64],
+    threshold: u32,
+    version: u32,
+    allow_trivial_threshold: bool,
+) -> Result<JsValue, String> {
+    let options = ProtectOptions { allow_trivial_threshold, ..Default::default() };
+    let sharing = sharing::protect_secret_with_options(
         secret_id,
         secret_data,
         channels,
         threshold as usize,
         version as i32,
-        None,
-        None,
-    ).unwrap();
+        &options,
+    ).map_err(|e| e.to_string())?;
 
     let wrapper = TsProtectSecretResult { value: sharing.into_iter().map(|(k, v)| (k, v.encode_to_vec())).collect() };
-    serde_wasm_bindgen::to_value(&wrapper).unwrap()
+    serde_wasm_bindgen::to_value(&wrapper).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn ts_generate_store_share_response(
+    channel_id: u64,
+    version: u32,
+    success: bool,
+) -> Vec<u8> {
+    sharing::generate_store_share_response(&channel_id, version as i32, success).encode_to_vec()
+}
+
+#[wasm_bindgen]
+pub fn ts_process_store_share_response(response: &[u8]) -> Result<(), String> {
+    let response = StoreShareResponseMessage::decode(response).map_err(|e| e.to_string())?;
+    sharing::process_store_share_response(&response).map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
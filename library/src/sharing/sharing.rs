@@ -1,9 +1,25 @@
 use prost::Message;
-use rand::RngCore;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use derec_cryptography::vss;
-use crate::protos::derec_proto::{StoreShareRequestMessage, DeRecShare, CommittedDeRecShare, committed_de_rec_share::SiblingHash};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use derec_cryptography::channel;
+use derec_cryptography::rng::SecureRng;
+use derec_cryptography::pairing::PairingSharedKey;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use crate::protos::derec_proto::{
+    StoreShareRequestMessage,
+    StoreShareResponseMessage,
+    DeRecShare,
+    CommittedDeRecShare,
+    committed_de_rec_share::SiblingHash,
+    Result as DerecResult,
+    StatusEnum
+};
 use crate::types::*;
+use crate::framing::{frame, Deframer};
+use crate::kdf::{derive_from_passphrase, Argon2Params};
 
 /// Protects a secret by splitting it into verifiable secret shares and preparing messages for distribution.
 ///
@@ -24,11 +40,15 @@ use crate::types::*;
 /// # Returns
 ///
 /// Returns a `Result` containing a `SharingMulticastMessage`, which maps each channel to its corresponding
-/// `StoreShareRequestMessage`. Returns an error string if share generation fails.
+/// `StoreShareRequestMessage`. Returns a `DerecVSSError` if share generation fails.
 ///
 /// # Errors
 ///
-/// Returns an error if the verifiable secret sharing (VSS) process fails to generate shares.
+/// Returns `DerecVSSError::NoChannels` if `channels` is empty, `DerecVSSError::InvalidAccessStructure`
+/// if `threshold` is zero or exceeds the number of channels, `DerecVSSError::TrivialThreshold` if it is 1
+/// (see `ProtectOptions::allow_trivial_threshold`), `DerecVSSError::TooManyChannels` if more
+/// channels are supplied than the Merkle commitment can hold, or any error from the verifiable secret
+/// sharing (VSS) process.
 ///
 /// # Example
 ///
@@ -49,15 +69,375 @@ pub fn protect_secret(
     version: i32,
     keep_list: Option<&[i32]>,
     description: Option<&str>,
-) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, &'static str> {
-    // our secret sharing scheme requires some entropy
-    let mut rng = rand::rngs::OsRng;
-    let mut entropy: [u8; 32] = [0; 32];
-    rng.fill_bytes(&mut entropy);
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    let options = ProtectOptions { keep_list, description, ..Default::default() };
+    protect_secret_with_options(secret_id, secret_data, channels, threshold, version, &options)
+}
+
+/// Protects a secret like `protect_secret`, returning the messages in ascending order of channel id.
+///
+/// The map returned by `protect_secret` iterates in an unspecified order; this variant gives callers
+/// a stable order, e.g. for distribution logs or snapshot tests.
+///
+/// # Errors
+///
+/// Returns the same errors as `protect_secret`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::protect_secret_ordered;
+/// let sharing = protect_secret_ordered(b"id", b"secret", &[3, 1, 2], 2, 1, None, None).unwrap();
+/// let channels: Vec<u64> = sharing.iter().map(|(channel, _)| *channel).collect();
+/// assert_eq!(channels, vec![1, 2, 3]);
+/// ```
+pub fn protect_secret_ordered(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    keep_list: Option<&[i32]>,
+    description: Option<&str>,
+) -> Result<Vec<(ChannelId, StoreShareRequestMessage)>, vss::DerecVSSError> {
+    let sharing = protect_secret(secret_id, secret_data, channels, threshold, version, keep_list, description)?;
+    let mut ordered: Vec<_> = sharing.into_iter().collect();
+    ordered.sort_unstable_by_key(|(channel, _)| *channel);
+    Ok(ordered)
+}
+
+/// Optional parameters for `protect_secret_with_options`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtectOptions<'a> {
+    /// versions the helper must retain (including this new one)
+    pub keep_list: Option<&'a [i32]>,
+    /// a description of the version, readable by the helper
+    pub description: Option<&'a str>,
+    /// a sharer-only key; if set, helpers store `blind_secret_id(secret_id, key)` instead of the raw `secret_id`
+    pub secret_id_key: Option<&'a [u8]>,
+    /// a random salt; if set, it is stored in every share so that the secret can be checked
+    /// against `vss::commit_secret(secret, salt)` after recovery
+    pub commitment_salt: Option<&'a [u8; 32]>,
+    /// how the secret is turned into shares; see `ShareAlgorithm`
+    pub share_algorithm: ShareAlgorithm,
+    /// if set, the description is also recorded in every share and bound to the commitment,
+    /// so that a relay altering it is detected by `check_version_consistency` and during recovery
+    pub commit_description: bool,
+    /// a caller-held master seed; if set, all randomness is derived from it together with the secret id
+    /// and version, so repeating the call yields byte-identical shares (see `protect_secret_deterministic`)
+    pub master_seed: Option<&'a [u8; 32]>,
+    /// if set, shares carry no Merkle commitment (see `vss::ShareOptions::commit`), saving
+    /// `(vss::MERKLE_TREE_DEPTH + 1) × 32` bytes each; only for channels that already authenticate
+    /// shares, since recovery can then no longer single out a corrupted share. `commit_description`
+    /// has no effect, as there is no commitment to bind the description to
+    pub omit_commitment: bool,
+    /// if set, a threshold of 1 is accepted; by default it is refused with
+    /// `DerecVSSError::TrivialThreshold`, since any single helper could then reconstruct the secret
+    pub allow_trivial_threshold: bool,
+    /// if set, the shares sit at pseudorandom leaves of the Merkle tree (see `vss::commit_scattered`)
+    /// rather than its first `n`, so a helper's authentication path reveals nothing about how many
+    /// helpers hold a share; has no effect with `omit_commitment`
+    pub scatter_leaves: bool,
+}
+
+/// The algorithm used to create the `share` bytes of a `StoreShareRequestMessage`,
+/// as recorded in its `share_algorithm` field.
+///
+/// All algorithms serialize a `CommittedDeRecShare`; they differ in what is Shamir-shared,
+/// and in how the ciphertext of the secret is distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(i32)]
+pub enum ShareAlgorithm {
+    /// the secret is encrypted under a fresh AES key, and the key is Shamir-shared;
+    /// this is how every share was created before the algorithm could be chosen
+    #[default]
+    ShamirWithAesEnvelope = 0,
+    /// the secret itself is Shamir-shared over the BW6-761 scalar field, with no envelope;
+    /// only secrets of up to `vss::MAX_DIRECT_SECRET_SIZE` bytes can be shared this way
+    ShamirBw6 = 1,
+    /// as `ShamirWithAesEnvelope`, but instead of every share carrying the whole ciphertext, each
+    /// carries one fragment of it (see `vss::erasure_encode`), any `threshold` of which rebuild it;
+    /// this shrinks what each helper stores and tolerates lost fragments; the digests of all fragments
+    /// are bound to the commitment, so an altered fragment is rejected with its share
+    ShamirWithErasureCodedEnvelope = 2,
+}
+
+impl TryFrom<i32> for ShareAlgorithm {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ShareAlgorithm::ShamirWithAesEnvelope),
+            1 => Ok(ShareAlgorithm::ShamirBw6),
+            2 => Ok(ShareAlgorithm::ShamirWithErasureCodedEnvelope),
+            _ => Err("Unknown share algorithm"),
+        }
+    }
+}
+
+/// Checks that a secret of `len` bytes can be protected with `algorithm`.
+///
+/// `ShareAlgorithm::ShamirBw6` places the secret in a single field element, so it takes at most
+/// `vss::MAX_DIRECT_SECRET_SIZE` bytes (`vss::MAX_DIRECT_SECRET_BITS` bits). The AES envelope takes
+/// secrets of any length; its erasure-coded variant records the ciphertext length in 32 bits.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::SecretTooLarge`, carrying the largest size allowed, if `len` exceeds it.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{validate_secret_len, ShareAlgorithm};
+/// assert!(validate_secret_len(31, ShareAlgorithm::ShamirBw6).is_ok());
+/// assert!(validate_secret_len(32, ShareAlgorithm::ShamirBw6).is_err());
+/// assert!(validate_secret_len(1 << 20, ShareAlgorithm::ShamirWithAesEnvelope).is_ok());
+/// ```
+pub fn validate_secret_len(len: usize, algorithm: ShareAlgorithm) -> Result<(), vss::DerecVSSError> {
+    let max = match algorithm {
+        ShareAlgorithm::ShamirBw6 => vss::MAX_DIRECT_SECRET_SIZE,
+        ShareAlgorithm::ShamirWithAesEnvelope => usize::MAX,
+        // the ciphertext adds a 12-byte nonce and a 16-byte tag to the secret
+        ShareAlgorithm::ShamirWithErasureCodedEnvelope => (u32::MAX as usize).saturating_sub(12 + 16),
+    };
+    if len > max {
+        return Err(vss::DerecVSSError::SecretTooLarge { max });
+    }
+    Ok(())
+}
+
+/// Protects a secret like `protect_secret`, taking its optional parameters as a `ProtectOptions`.
+///
+/// If `options.secret_id_key` is set, each share records a keyed hash of `secret_id` (see
+/// `blind_secret_id`) rather than the identifier itself, so helpers can match requests to shares
+/// without learning what the secret is called. Recovery must then look the shares up with
+/// `recovery::recover_from_share_responses_with_key` under the same key.
+///
+/// # Arguments
+///
+/// * `secret_id` - An identifier for the secret, used to associate shares with the original secret.
+/// * `secret_data` - The secret data to be protected and shared.
+/// * `channels` - A slice of identifiers representing the recipients of each share.
+/// * `threshold` - The minimum number of shares required to reconstruct the secret.
+/// * `version` - The version number of the secret or sharing scheme.
+/// * `options` - The keep list, description, secret id key and share algorithm to use.
+///
+/// # Errors
+///
+/// Returns the same errors as `protect_secret`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret_with_options, blind_secret_id, ProtectOptions};
+/// let key = b"sharer-only key";
+/// let options = ProtectOptions { secret_id_key: Some(key), ..Default::default() };
+/// let sharing = protect_secret_with_options(b"bank-password", b"hunter2", &[1, 2, 3], 2, 1, &options).unwrap();
+/// assert_eq!(sharing.len(), 3);
+/// ```
+pub fn protect_secret_with_options(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    options: &ProtectOptions,
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    protect_secret_inner(secret_id, secret_data, channels, threshold, version, options, None, &mut rand::rngs::OsRng)
+}
+
+/// Protects a secret like `protect_secret_with_options`, drawing its randomness from `rng` instead of the OS.
+///
+/// This lets tests inject a seeded generator and obtain reproducible shares. Production code should use
+/// `protect_secret_with_options`, or `ProtectOptions::master_seed` for reproducible sharings, which takes
+/// precedence over `rng`.
+///
+/// # Arguments
+///
+/// * `secret_id` - An identifier for the secret, used to associate shares with the original secret.
+/// * `secret_data` - The secret data to be protected and shared.
+/// * `channels` - A slice of identifiers representing the recipients of each share.
+/// * `threshold` - The minimum number of shares required to reconstruct the secret.
+/// * `version` - The version number of the secret or sharing scheme.
+/// * `options` - The `ProtectOptions` to apply.
+/// * `rng` - A cryptographically secure generator.
+///
+/// # Errors
+///
+/// Returns the same errors as `protect_secret`.
+pub fn protect_secret_with_rng(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    options: &ProtectOptions,
+    rng: &mut impl SecureRng,
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    protect_secret_inner(secret_id, secret_data, channels, threshold, version, options, None, rng)
+}
+
+/// Protects a secret like `protect_secret`, encrypting it under a caller-supplied AES key.
+///
+/// The secret is encrypted under `aes_key` (with `ShareAlgorithm::ShamirWithAesEnvelope`), and it is
+/// `aes_key` itself that is split among the helpers. This suits callers whose key management (e.g. an HSM)
+/// holds the key: the helpers' shares back the key up, and `vss::recover_key_only` over them returns it.
+///
+/// # Arguments
+///
+/// * `secret_id` - An identifier for the secret, used to associate shares with the original secret.
+/// * `secret_data` - The secret data to be protected and shared.
+/// * `channels` - A slice of identifiers representing the recipients of each share.
+/// * `threshold` - The minimum number of shares required to reconstruct the secret.
+/// * `version` - The version number of the secret or sharing scheme.
+/// * `aes_key` - The 256-bit AES key to encrypt the secret under and to share.
+///
+/// # Errors
+///
+/// Returns the same errors as `protect_secret`.
+pub fn protect_secret_with_key(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    aes_key: [u8; 32],
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirWithAesEnvelope, ..Default::default() };
+    protect_secret_inner(secret_id, secret_data, channels, threshold, version, &options, Some(&aes_key), &mut rand::rngs::OsRng)
+}
+
+/// Protects a secret like `protect_secret`, deriving all randomness from a caller-held master seed.
+///
+/// The sharing entropy is a keyed hash of `(secret_id, version)` under `master_seed`, so calling this again
+/// for the same secret, version, channels and threshold (e.g. when retrying after a crash) yields byte-identical
+/// messages, and helpers never receive two conflicting sharings of one version. Different versions or secret ids
+/// still get independent randomness.
+///
+/// The master seed must be drawn from a CSPRNG and kept as carefully as the secrets it protects: anyone holding
+/// it and a secret can regenerate every share of that secret.
+///
+/// # Arguments
+///
+/// * `secret_id` - An identifier for the secret, used to associate shares with the original secret.
+/// * `secret_data` - The secret data to be protected and shared.
+/// * `channels` - A slice of identifiers representing the recipients of each share.
+/// * `threshold` - The minimum number of shares required to reconstruct the secret.
+/// * `version` - The version number of the secret or sharing scheme.
+/// * `master_seed` - The caller's 256-bit master seed.
+///
+/// # Errors
+///
+/// Returns the same errors as `protect_secret`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::protect_secret_deterministic;
+/// let seed = [0x42; 32];
+/// let first = protect_secret_deterministic(b"id", b"secret", &[1, 2, 3], 2, 1, &seed).unwrap();
+/// let retry = protect_secret_deterministic(b"id", b"secret", &[1, 2, 3], 2, 1, &seed).unwrap();
+/// assert_eq!(first, retry);
+/// ```
+pub fn protect_secret_deterministic(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    master_seed: &[u8; 32],
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    let options = ProtectOptions { master_seed: Some(master_seed), ..Default::default() };
+    protect_secret_inner(secret_id, secret_data, channels, threshold, version, &options, None, &mut rand::rngs::OsRng)
+}
+
+// shared body of the protect_secret variants; `envelope_key`, if given, replaces
+// the freshly derived AES key of the ShamirWithAesEnvelope algorithm
+#[allow(clippy::too_many_arguments)]
+fn protect_secret_inner(
+    secret_id: impl AsRef<[u8]>,
+    secret_data: impl AsRef<[u8]>,
+    channels: impl AsRef<[ChannelId]>,
+    threshold: usize,
+    version: i32,
+    options: &ProtectOptions,
+    envelope_key: Option<&[u8; 32]>,
+    rng: &mut impl SecureRng,
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, vss::DerecVSSError> {
+    validate_secret_len(secret_data.as_ref().len(), options.share_algorithm)?;
 
     let (t, n) = (threshold as u64, channels.as_ref().len() as u64);
-    let vss_shares = vss::share((t,n), secret_data.as_ref(), &entropy)
-        .map_err(|_| "VSS failed to generate shares")?;
+
+    if n == 0 {
+        return Err(vss::DerecVSSError::NoChannels);
+    }
+
+    // the threshold must be reachable with the channels we have
+    if t == 0 || t > n {
+        return Err(vss::DerecVSSError::InvalidAccessStructure);
+    }
+
+    // every channel needs its own leaf in the Merkle commitment
+    if vss::min_merkle_depth(n) > vss::MERKLE_TREE_DEPTH {
+        return Err(vss::DerecVSSError::TooManyChannels { max: vss::MAX_SHARES });
+    }
+
+    // each channel's share sits at an x-coordinate anyone can recompute from the channel id
+    let xs: Vec<Vec<u8>> = channels.as_ref().iter().map(|channel| vss::x_for_channel(*channel)).collect();
+
+    // our secret sharing scheme requires some entropy, which we draw from the caller's CSPRNG
+    // (the OS one, by default) unless the caller asked for a reproducible sharing
+    let mut draw_entropy = |label: &[u8]| -> [u8; 32] {
+        match options.master_seed {
+            Some(seed) => derive_sharing_entropy(seed, secret_id.as_ref(), version, label),
+            None => {
+                let mut entropy = [0u8; 32];
+                rng.fill_bytes(&mut entropy);
+                entropy
+            }
+        }
+    };
+
+    let entropy = draw_entropy(b"share");
+    let share_options = vss::ShareOptions {
+        key: envelope_key,
+        direct: options.share_algorithm == ShareAlgorithm::ShamirBw6,
+        xs: Some(&xs),
+        allow_trivial_threshold: options.allow_trivial_threshold,
+        commit: !options.omit_commitment,
+    };
+    let mut vss_shares = vss::share_with_options((t,n), secret_data.as_ref(), &entropy, &share_options)?;
+
+    // spread the (identical) ciphertext of the shares across them, committing to every fragment
+    let mut fragment_digests = Vec::new();
+    if options.share_algorithm == ShareAlgorithm::ShamirWithErasureCodedEnvelope {
+        let fragments = vss::erasure_encode(&vss_shares[0].encrypted_secret, threshold, vss_shares.len())?;
+        fragment_digests = fragments.iter().flat_map(Sha256::digest).collect();
+        for (share, fragment) in vss_shares.iter_mut().zip(fragments) {
+            share.encrypted_secret = fragment;
+        }
+    }
+
+    // helpers only ever see the blinded identifier when a secret id key is given
+    let stored_secret_id = match options.secret_id_key {
+        Some(key) => blind_secret_id(secret_id.as_ref(), key),
+        None => secret_id.as_ref().to_vec(),
+    };
+
+    // bind every share to its secret id, version and description, so none of them can be altered without
+    // breaking the Merkle proofs; a helper can then not pass off a share of one version as another
+    let committed_description = match options.description {
+        Some(description) if options.commit_description && !options.omit_commitment => description,
+        _ => "",
+    };
+    if !options.omit_commitment {
+        let entropy = draw_entropy(b"commit-binding");
+        let binding = share_binding(&stored_secret_id, version, committed_description, &fragment_digests);
+        vss::commit_associated_data(&mut vss_shares, &binding, &entropy)?;
+        if options.scatter_leaves {
+            vss::commit_scattered(&mut vss_shares, &draw_entropy(b"commit-scatter"))?;
+        }
+    }
 
     // let's iterate over all shares and prepare DeRec protocol messages
     let mut output = HashMap::new();
@@ -66,8 +446,13 @@ pub fn protect_secret(
             encrypted_secret: share.encrypted_secret.to_owned(),
             x: share.x.to_owned(),
             y: share.y.to_owned(),
-            secret_id: secret_id.as_ref().to_vec(),
-            version: version,
+            secret_id: stored_secret_id.clone(),
+            version,
+            secret_commitment_salt: options.commitment_salt.map(|salt| salt.to_vec()).unwrap_or_default(),
+            version_description: committed_description.to_string(),
+            threshold: threshold as i32,
+            key_hash: share.key_hash.to_owned(),
+            fragment_digests: fragment_digests.clone(),
         };
 
         let committed_derec_share = CommittedDeRecShare {
@@ -81,14 +466,887 @@ pub fn protect_secret(
 
         let outbound_msg = StoreShareRequestMessage {
             share: committed_derec_share.encode_to_vec(),
-            share_algorithm: 0,
-            version: version,
-            keep_list: keep_list.map(|lst| lst.to_vec()).unwrap_or_default(),
-            version_description: description.map(|d| d.to_string()).unwrap_or_default(),
+            share_algorithm: options.share_algorithm as i32,
+            version,
+            keep_list: options.keep_list.map(|lst| lst.to_vec()).unwrap_or_default(),
+            version_description: options.description.map(|d| d.to_string()).unwrap_or_default(),
+            store_authorization: Vec::new(),
         };
 
         output.insert(*channel, outbound_msg);
     }
 
     Ok(output)
-}
\ No newline at end of file
+}
+
+const SHARE_BINDING_TAG: &[u8] = b"derec-share-binding-v1";
+const FRAGMENT_SHARE_BINDING_TAG: &[u8] = b"derec-share-binding-v2";
+
+// the associated data committed with every share: a domain tag, the (length-prefixed) stored secret id,
+// the version, and the committed description; erasure-coded shares also commit to the (length-prefixed)
+// digests of all fragments, under their own tag so that the layout of the other shares is unchanged
+fn share_binding(secret_id: &[u8], version: i32, description: &str, fragment_digests: &[u8]) -> Vec<u8> {
+    let tag = if fragment_digests.is_empty() { SHARE_BINDING_TAG } else { FRAGMENT_SHARE_BINDING_TAG };
+    let mut binding = tag.to_vec();
+    binding.extend_from_slice(&(secret_id.len() as u32).to_be_bytes());
+    binding.extend_from_slice(secret_id);
+    binding.extend_from_slice(&version.to_be_bytes());
+    if !fragment_digests.is_empty() {
+        binding.extend_from_slice(&(fragment_digests.len() as u32).to_be_bytes());
+        binding.extend_from_slice(fragment_digests);
+    }
+    binding.extend_from_slice(description.as_bytes());
+    binding
+}
+
+// whether an erasure-coded share's fragment is the one its digests were committed to; the fragment's
+// index is the first byte of its header (see `vss::erasure_encode`)
+fn fragment_matches_digest(derec_share: &DeRecShare) -> bool {
+    let Some(&index) = derec_share.encrypted_secret.first() else {
+        return false;
+    };
+    let offset = index as usize * 32;
+    derec_share
+        .fragment_digests
+        .get(offset..offset + 32)
+        .is_some_and(|digest| digest == Sha256::digest(&derec_share.encrypted_secret).as_slice())
+}
+
+// Reassembles the VSS share carried by a committed DeRec share. Its associated data is the binding of
+// `share_binding`; shares protected before that binding existed committed to the description alone, and
+// are accepted as such, though their version and secret id are then not authenticated.
+pub(crate) fn decode_vss_share(committed_derec_share: CommittedDeRecShare, derec_share: &DeRecShare) -> vss::VSSShare {
+    let mut share = vss::VSSShare {
+        x: derec_share.x.clone(),
+        y: derec_share.y.clone(),
+        encrypted_secret: derec_share.encrypted_secret.clone(),
+        key_hash: derec_share.key_hash.clone(),
+        associated_data: share_binding(
+            &derec_share.secret_id,
+            derec_share.version,
+            &derec_share.version_description,
+            &derec_share.fragment_digests
+        ),
+        commitment: committed_derec_share.commitment,
+        merkle_path: committed_derec_share.merkle_path.iter().map(|h| (h.is_left, h.hash.to_owned())).collect(),
+    };
+
+    if !vss::is_uncommitted(&share) && vss::verify_share(&share).is_err() {
+        let legacy = vss::VSSShare { associated_data: derec_share.version_description.clone().into_bytes(), ..share.clone() };
+        if vss::verify_share(&legacy).is_ok() {
+            share = legacy;
+        }
+    }
+    share
+}
+
+// Reassembles the VSS share of a committed DeRec share protected with `algorithm`, like `decode_vss_share`.
+// An erasure-coded share's fragment is only covered by the commitment through the fragment digests, so
+// one that does not match its digest is rejected as `DerecVSSError::CorruptShares`.
+pub(crate) fn decode_share_of(
+    algorithm: ShareAlgorithm,
+    committed_derec_share: CommittedDeRecShare,
+    derec_share: &DeRecShare,
+) -> Result<vss::VSSShare, vss::DerecVSSError> {
+    if algorithm == ShareAlgorithm::ShamirWithErasureCodedEnvelope && !fragment_matches_digest(derec_share) {
+        return Err(vss::DerecVSSError::CorruptShares);
+    }
+    Ok(decode_vss_share(committed_derec_share, derec_share))
+}
+
+// Why `reconstruct_secret` failed: too few shares, shares that cannot be combined, or a failed reconstruction.
+#[derive(Debug)]
+pub(crate) enum ReconstructionError {
+    Insufficient,
+    Inconsistent(vss::DerecVSSError),
+    Failed(vss::DerecVSSError),
+}
+
+// Reconstructs the secret from the decoded shares of one sharing, protected with `algorithm`: rebuilds an
+// erasure-coded ciphertext from the shares' fragments, checks the shares agree, and interpolates.
+pub(crate) fn reconstruct_secret(
+    mut shares: Vec<vss::VSSShare>,
+    algorithm: ShareAlgorithm,
+    threshold: usize,
+) -> Result<Vec<u8>, ReconstructionError> {
+    // each share carries a fragment of the ciphertext; rebuild it before checking the shares agree on it
+    if algorithm == ShareAlgorithm::ShamirWithErasureCodedEnvelope {
+        if shares.len() < threshold {
+            return Err(ReconstructionError::Insufficient);
+        }
+        let fragments: Vec<&[u8]> = shares.iter().map(|s| s.encrypted_secret.as_slice()).collect();
+        let encrypted_secret = vss::erasure_decode(&fragments).map_err(ReconstructionError::Inconsistent)?;
+        for share in &mut shares {
+            share.encrypted_secret = encrypted_secret.clone();
+        }
+    }
+
+    if let Some(error) = vss::detect_error(&shares) {
+        return Err(ReconstructionError::Inconsistent(error));
+    }
+    if shares.is_empty() || shares.len() < threshold {
+        return Err(ReconstructionError::Insufficient);
+    }
+
+    match algorithm {
+        ShareAlgorithm::ShamirBw6 => vss::recover_direct(&shares),
+        _ => vss::recover(&shares),
+    }.map_err(ReconstructionError::Failed)
+}
+
+/// Computes the keyed hash of a secret identifier that helpers store in place of the identifier.
+///
+/// The hash is HMAC-SHA256 of `secret_id` under a key known only to the sharer, so a helper
+/// cannot recover or guess the identifier (e.g. "bank-password") from what it stores, while
+/// the sharer can still recompute it to look shares up during recovery.
+///
+/// # Arguments
+///
+/// * `secret_id` - The identifier of the secret.
+/// * `key` - The sharer-only key used to blind identifiers.
+///
+/// # Returns
+///
+/// Returns the 32-byte blinded identifier.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::blind_secret_id;
+/// let blinded = blind_secret_id(b"bank-password", b"sharer-only key");
+/// assert_eq!(blinded.len(), 32);
+/// assert_ne!(blinded, b"bank-password".to_vec());
+/// ```
+pub fn blind_secret_id(secret_id: impl AsRef<[u8]>, key: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(secret_id.as_ref());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Packs several secrets into one, so that a single sharing can carry them all.
+///
+/// Each item is written as a 4-byte big-endian length followed by its bytes; after recovery,
+/// `recovery::unpack_secrets` splits the recovered secret back into the items, in order.
+///
+/// # Arguments
+///
+/// * `items` - The secrets to pack; each must be shorter than 4 GiB.
+///
+/// # Returns
+///
+/// Returns the packed bytes, to be passed as the secret to `protect_secret`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::pack_secrets;
+/// use derec_library::recovery::unpack_secrets;
+/// let packed = pack_secrets(&[b"pin", b"", b"password"]);
+/// assert_eq!(packed.len(), 3 * 4 + 11);
+/// assert_eq!(unpack_secrets(&packed).unwrap(), vec![b"pin".to_vec(), vec![], b"password".to_vec()]);
+/// ```
+pub fn pack_secrets(items: &[&[u8]]) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(items.iter().map(|item| 4 + item.len()).sum());
+    for item in items {
+        let len = u32::try_from(item.len()).expect("packed secrets must be shorter than 4 GiB");
+        packed.extend_from_slice(&len.to_be_bytes());
+        packed.extend_from_slice(item);
+    }
+    packed
+}
+
+// HMAC-SHA256 under the master seed of a label and the (length-prefixed) secret id and version,
+// so that every sharing and every use within it gets independent entropy
+fn derive_sharing_entropy(master_seed: &[u8; 32], secret_id: &[u8], version: i32, label: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(master_seed)
+        .expect("HMAC accepts keys of any length");
+    mac.update(b"derec-deterministic-protect");
+    mac.update(label);
+    mac.update(&(secret_id.len() as u32).to_be_bytes());
+    mac.update(secret_id);
+    mac.update(&version.to_be_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+/// Size in bytes of the challenges returned by `generate_store_challenge`.
+pub const STORE_CHALLENGE_SIZE: usize = 32;
+
+/// Generates a fresh challenge that a helper sends to the sharer before accepting a share.
+///
+/// The sharer answers by authorizing its `StoreShareRequestMessage` for this challenge with
+/// `authorize_store_request`, and the helper checks the answer with `verify_store_authorization`.
+/// Because every store is bound to a challenge the helper has just issued, a request captured
+/// earlier and replayed later does not verify.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::generate_store_challenge;
+/// assert_ne!(generate_store_challenge(), generate_store_challenge());
+/// ```
+pub fn generate_store_challenge() -> [u8; STORE_CHALLENGE_SIZE] {
+    let mut challenge = [0u8; STORE_CHALLENGE_SIZE];
+    rand::rngs::OsRng.fill_bytes(&mut challenge);
+    challenge
+}
+
+/// Answers a helper's store challenge by setting the `store_authorization` field of `request`.
+///
+/// The authorization is HMAC-SHA256, under the channel's shared key, over the challenge and every other
+/// field of the request (the share, its algorithm, the version, the keep list and the description), so it
+/// cannot be moved to a different share or challenge, nor the request altered after it was authorized.
+///
+/// # Arguments
+///
+/// * `request` - The `StoreShareRequestMessage` about to be sent to the helper.
+/// * `challenge` - The challenge received from the helper.
+/// * `channel_key` - The shared key established with the helper during pairing.
+pub fn authorize_store_request(
+    request: &mut StoreShareRequestMessage,
+    challenge: &[u8],
+    channel_key: &PairingSharedKey,
+) {
+    request.store_authorization = store_authorization_mac(request, challenge, channel_key)
+        .finalize()
+        .into_bytes()
+        .to_vec();
+}
+
+/// Checks, on the helper, that `request` was authorized for the challenge the helper issued.
+///
+/// # Arguments
+///
+/// * `request` - The received `StoreShareRequestMessage`.
+/// * `challenge` - The challenge the helper issued for this store; it should be discarded afterwards.
+/// * `channel_key` - The shared key established with the sharer during pairing.
+///
+/// # Errors
+///
+/// Returns an error if the request carries no authorization, or if it was authorized for a different
+/// challenge, key, or content of any of its fields.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::*;
+/// let key = [9u8; 32];
+/// let mut sharing = protect_secret(b"id", b"secret", &[1, 2], 2, 1, None, None).unwrap();
+/// let request = sharing.get_mut(&1).unwrap();
+///
+/// let challenge = generate_store_challenge();
+/// authorize_store_request(request, &challenge, &key);
+/// assert!(verify_store_authorization(request, &challenge, &key).is_ok());
+/// ```
+pub fn verify_store_authorization(
+    request: &StoreShareRequestMessage,
+    challenge: &[u8],
+    channel_key: &PairingSharedKey,
+) -> Result<(), &'static str> {
+    if request.store_authorization.is_empty() {
+        return Err("Store request carries no authorization");
+    }
+
+    store_authorization_mac(request, challenge, channel_key)
+        .verify_slice(&request.store_authorization)
+        .map_err(|_| "Store request is not authorized for this challenge")
+}
+
+// the MAC binding every field of a store request but the authorization itself to a helper's challenge;
+// variable-length fields are length-prefixed so that no two requests are fed in alike
+fn store_authorization_mac(
+    request: &StoreShareRequestMessage,
+    challenge: &[u8],
+    channel_key: &PairingSharedKey,
+) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(channel_key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(b"derec-store-authorization");
+    mac.update(&(challenge.len() as u64).to_be_bytes());
+    mac.update(challenge);
+    mac.update(&request.version.to_be_bytes());
+    mac.update(&request.share_algorithm.to_be_bytes());
+    mac.update(&(request.keep_list.len() as u64).to_be_bytes());
+    for version in &request.keep_list {
+        mac.update(&version.to_be_bytes());
+    }
+    mac.update(&(request.version_description.len() as u64).to_be_bytes());
+    mac.update(request.version_description.as_bytes());
+    mac.update(&request.share);
+    mac
+}
+
+/// Extracts the verifiable share held by a single channel from a sharing.
+///
+/// This decodes the `StoreShareRequestMessage` addressed to `channel` into a `VSSShare`
+/// (x, y, encrypted secret, commitment and Merkle path), so that an auditor can check
+/// that one helper holds a valid share with `vss::verify_share`, without the whole sharing.
+///
+/// # Arguments
+///
+/// * `sharing` - The per-channel messages produced by `protect_secret`.
+/// * `channel` - The channel whose share should be extracted.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::UnknownChannel` if the sharing has no message for `channel`, or
+/// `DerecVSSError::MalformedShare` if the message does not decode into a committed share.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret, extract_channel_share};
+/// use derec_cryptography::vss;
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 1, None, None).unwrap();
+/// let share = extract_channel_share(&sharing, 2).unwrap();
+/// assert!(vss::verify_share(&share).is_ok());
+/// ```
+pub fn extract_channel_share(
+    sharing: &HashMap<ChannelId, StoreShareRequestMessage>,
+    channel: ChannelId,
+) -> Result<vss::VSSShare, vss::DerecVSSError> {
+    let msg = sharing.get(&channel).ok_or(vss::DerecVSSError::UnknownChannel)?;
+
+    let committed_derec_share = CommittedDeRecShare::decode(msg.share.as_slice())
+        .map_err(|_| vss::DerecVSSError::MalformedShare)?;
+    let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice())
+        .map_err(|_| vss::DerecVSSError::MalformedShare)?;
+
+    Ok(decode_vss_share(committed_derec_share, &derec_share))
+}
+
+/// Checks, right after `protect_secret`, that a share together with a threshold of others reconstructs
+/// the secret, including decrypting its envelope.
+///
+/// A helper cannot confirm on its own that its share is usable, as the envelope key only emerges from a
+/// threshold of shares. The sharer, who still holds all of them, can: this reconstructs the secret from
+/// `share` and the first `threshold - 1` distinct `others`, exactly as recovery would. If the others alone
+/// reach the threshold, their reconstruction must also agree, which catches a share that interpolates to
+/// a different (but still decryptable) value, as is possible with `ShareAlgorithm::ShamirBw6`.
+///
+/// # Arguments
+///
+/// * `share` - The message whose share is checked.
+/// * `others` - Other messages of the same sharing.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::MalformedShare` if a message does not decode, `DerecVSSError::InsufficientShares`
+/// if fewer than a threshold of distinct shares are given, `DerecVSSError::InconsistentShareAlgorithms` if
+/// the messages name different share algorithms, `DerecVSSError::CorruptShares` if a share fails its Merkle
+/// proof or the reconstructions disagree, or any error from reconstruction and decryption.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret, verify_share_round_trip};
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 1, None, None).unwrap();
+/// let others = [sharing[&2].clone(), sharing[&3].clone()];
+/// assert!(verify_share_round_trip(&sharing[&1], &others).is_ok());
+/// ```
+pub fn verify_share_round_trip(
+    share: &StoreShareRequestMessage,
+    others: &[StoreShareRequestMessage],
+) -> Result<(), vss::DerecVSSError> {
+    let algorithm = ShareAlgorithm::try_from(share.share_algorithm).map_err(|_| vss::DerecVSSError::MalformedShare)?;
+
+    let mut shares: Vec<vss::VSSShare> = Vec::new();
+    let mut threshold = 0;
+    for msg in std::iter::once(share).chain(others) {
+        let committed_derec_share = CommittedDeRecShare::decode(msg.share.as_slice())
+            .map_err(|_| vss::DerecVSSError::MalformedShare)?;
+        let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice())
+            .map_err(|_| vss::DerecVSSError::MalformedShare)?;
+        if msg.share_algorithm != share.share_algorithm {
+            return Err(vss::DerecVSSError::InconsistentShareAlgorithms);
+        }
+        if threshold == 0 {
+            threshold = derec_share.threshold.max(0) as usize;
+        }
+
+        let decoded = decode_share_of(algorithm, committed_derec_share, &derec_share)?;
+        if !vss::is_uncommitted(&decoded) && vss::verify_share(&decoded).is_err() {
+            return Err(vss::DerecVSSError::CorruptShares);
+        }
+        if !shares.iter().any(|s| s.x == decoded.x) {
+            shares.push(decoded);
+        }
+    }
+
+    // shares recorded before the threshold was stored give no hint; then all of them are used
+    let threshold = if threshold == 0 { shares.len() } else { threshold };
+    if shares.len() < threshold {
+        return Err(vss::DerecVSSError::InsufficientShares);
+    }
+
+    let secret = reconstruct(&shares[..threshold], algorithm)?;
+    if shares.len() > threshold && reconstruct(&shares[1..threshold + 1], algorithm)? != secret {
+        return Err(vss::DerecVSSError::CorruptShares);
+    }
+    Ok(())
+}
+
+// reconstructs the secret from exactly a threshold of decoded shares, as recovery does
+fn reconstruct(shares: &[vss::VSSShare], algorithm: ShareAlgorithm) -> Result<Vec<u8>, vss::DerecVSSError> {
+    reconstruct_secret(shares.to_vec(), algorithm, shares.len()).map_err(|error| match error {
+        ReconstructionError::Insufficient => vss::DerecVSSError::InsufficientShares,
+        ReconstructionError::Inconsistent(error) | ReconstructionError::Failed(error) => error,
+    })
+}
+
+/// Maps each channel of a sharing to the x-coordinate of the share it was issued.
+///
+/// Every helper should hold a distinct point; two helpers issued the same x would hold the same share,
+/// making one of them redundant. This lets an auditor check that all x-coordinates are unique.
+///
+/// # Arguments
+///
+/// * `sharing` - The per-channel messages produced by `protect_secret`.
+///
+/// # Errors
+///
+/// Returns `DerecVSSError::MalformedShare` if any message does not decode into a committed share.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashSet;
+/// use derec_library::sharing::{protect_secret, share_x_coordinates};
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 1, None, None).unwrap();
+/// let xs = share_x_coordinates(&sharing).unwrap();
+/// assert_eq!(xs.values().collect::<HashSet<_>>().len(), 3);
+/// ```
+pub fn share_x_coordinates(
+    sharing: &HashMap<ChannelId, StoreShareRequestMessage>,
+) -> Result<HashMap<ChannelId, Vec<u8>>, vss::DerecVSSError> {
+    sharing
+        .keys()
+        .map(|channel| Ok((*channel, extract_channel_share(sharing, *channel)?.x)))
+        .collect()
+}
+
+/// Estimates the encoded size, in bytes, of each `StoreShareRequestMessage` in a sharing.
+///
+/// This lets a caller check, before distributing shares, that they respect a helper's maximum share size.
+/// The estimate adds up the protobuf encoding of the share coordinates (compressed field elements), the
+/// AES-GCM envelope around a `secret_len`-byte secret and the hash of its key, the share's version and
+/// threshold, the Merkle commitment and a `depth`-node Merkle path (widened if `n` shares need a deeper tree),
+/// counting every Merkle node as a left sibling. Versions and thresholds are assumed to be below 128; the secret
+/// id, keep list, description and commitment salt are caller-chosen and not included.
+///
+/// # Arguments
+///
+/// * `secret_len` - The length of the secret to be protected.
+/// * `n` - The number of shares (channels).
+/// * `depth` - The depth of the Merkle tree, normally `vss::MERKLE_TREE_DEPTH`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::estimate_share_size;
+/// use derec_cryptography::vss::MERKLE_TREE_DEPTH;
+/// let size = estimate_share_size(1024, 5, MERKLE_TREE_DEPTH);
+/// assert!(size > 1024 && size < 2048);
+/// ```
+pub fn estimate_share_size(secret_len: usize, n: usize, depth: u32) -> usize {
+    // a length-delimited field with a one-byte tag
+    fn field(len: usize) -> usize {
+        1 + prost::encoding::encoded_len_varint(len as u64) + len
+    }
+    // a small int32 field (e.g. a version or threshold below 128): one-byte tag and one varint byte
+    const INT_FIELD: usize = 1 + 1;
+    // a compressed BW6-761 scalar
+    const COORDINATE_SIZE: usize = 48;
+    const HASH_SIZE: usize = 32;
+    // nonce prefix and authentication tag of the AES-GCM envelope
+    const ENVELOPE_OVERHEAD: usize = 12 + 16;
+
+    let depth = depth.max(vss::min_merkle_depth(n as u64)) as usize;
+
+    // the share's version and threshold are both small int32 fields
+    let derec_share = field(secret_len + ENVELOPE_OVERHEAD) + field(HASH_SIZE) + 2 * field(COORDINATE_SIZE) + 2 * INT_FIELD;
+    let sibling = field(2 + field(HASH_SIZE));
+    let committed = field(derec_share) + field(HASH_SIZE) + depth * sibling;
+    field(committed) + 2 * INT_FIELD
+}
+
+/// Checks whether a sharing holds enough distinct shares to ever reach `threshold`.
+///
+/// Only messages that decode into a committed share count, and helpers issued the same x-coordinate
+/// count once. A UI can use this to warn the user before distributing shares that could never be
+/// recovered from.
+///
+/// # Arguments
+///
+/// * `sharing` - The per-channel messages produced by `protect_secret`.
+/// * `threshold` - The number of shares recovery will require.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret, is_recoverable};
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 1, None, None).unwrap();
+/// assert!(is_recoverable(&sharing, 2));
+/// assert!(!is_recoverable(&sharing, 4));
+/// ```
+pub fn is_recoverable(sharing: &HashMap<ChannelId, StoreShareRequestMessage>, threshold: usize) -> bool {
+    let distinct_xs: std::collections::HashSet<Vec<u8>> = sharing
+        .keys()
+        .filter_map(|channel| extract_channel_share(sharing, *channel).ok())
+        .map(|share| share.x)
+        .collect();
+
+    threshold > 0 && distinct_xs.len() >= threshold
+}
+
+/// Computes the probability that at least `threshold` of `n` helpers are reachable at recovery time.
+///
+/// Each helper is assumed to be available independently with probability `availability`, so this is the
+/// survival function of the binomial distribution, `P[X >= threshold]` for `X ~ Binomial(n, availability)`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::recovery_probability;
+/// assert!((recovery_probability(0.5, 2, 3) - 0.5).abs() < 1e-12);
+/// ```
+pub fn recovery_probability(availability: f64, threshold: usize, n: usize) -> f64 {
+    let q = 1.0 - availability;
+    let mut binomial = 1.0; // C(n, k), built up incrementally
+    let mut probability = 0.0;
+    for k in 0..=n {
+        if k >= threshold {
+            probability += binomial * availability.powi(k as i32) * q.powi((n - k) as i32);
+        }
+        binomial = binomial * (n - k) as f64 / (k + 1) as f64;
+    }
+    probability.min(1.0)
+}
+
+/// Recommends how many helpers to share a secret with, and the threshold to use, for a backup that must be
+/// recoverable with at least `target_recovery_prob`, given that each helper is reachable with probability
+/// `availability`.
+///
+/// Returns the smallest number of helpers `n` for which some threshold of at least 2 meets the target (see
+/// `recovery_probability`), together with the largest such threshold, as `(threshold, n)`. A higher threshold
+/// means more helpers must collude to reconstruct the secret, so it is preferred whenever the target allows.
+///
+/// # Returns
+///
+/// `None` if either probability lies outside `[0, 1]`, or the target cannot be met with at most
+/// `vss::MAX_SHARES` helpers.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::recommend_parameters;
+/// // with helpers that are up 90% of the time, 2 of 4 recovers 99% of the time
+/// assert_eq!(recommend_parameters(0.9, 0.99), Some((2, 4)));
+/// ```
+pub fn recommend_parameters(availability: f64, target_recovery_prob: f64) -> Option<(usize, usize)> {
+    if !(0.0..=1.0).contains(&availability) || !(0.0..=1.0).contains(&target_recovery_prob) {
+        return None;
+    }
+
+    (2..=vss::MAX_SHARES as usize).find_map(|n| {
+        (2..=n)
+            .rev()
+            .find(|&threshold| recovery_probability(availability, threshold, n) >= target_recovery_prob)
+            .map(|threshold| (threshold, n))
+    })
+}
+
+/// Checks that a `StoreShareRequestMessage`'s outer version agrees with the version committed inside its share.
+///
+/// `protect_secret` writes the version both on the outer message and on the inner `DeRecShare`.
+/// A relay could rewrite the outer version without touching the committed share, which would
+/// confuse a helper's indexing; this function decodes the inner share and confirms both agree.
+///
+/// If the share was protected with `ProtectOptions::commit_description`, the outer version description
+/// must likewise equal the committed one, and the committed one must verify against the share's commitment.
+///
+/// # Arguments
+///
+/// * `msg` - The `StoreShareRequestMessage` to check.
+///
+/// # Returns
+///
+/// Returns `Ok(version)` if the outer and inner versions (and committed descriptions) agree, or an
+/// error string if the share cannot be decoded or they differ.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret, check_version_consistency};
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 4, None, None).unwrap();
+/// assert_eq!(check_version_consistency(&sharing[&1]), Ok(4));
+/// ```
+pub fn check_version_consistency(msg: &StoreShareRequestMessage) -> Result<i32, &'static str> {
+    let committed_derec_share = CommittedDeRecShare::decode(msg.share.as_slice())
+        .map_err(|_| "Failed to decode CommittedDeRecShare")?;
+    let derec_share = DeRecShare::decode(committed_derec_share.de_rec_share.as_slice())
+        .map_err(|_| "Failed to decode DeRecShare")?;
+
+    if derec_share.version != msg.version {
+        return Err("Outer message version does not match the committed share version");
+    }
+
+    // a committed description must be repeated verbatim, and must be the one the commitment covers
+    if !derec_share.version_description.is_empty() {
+        if derec_share.version_description != msg.version_description {
+            return Err("Outer version description does not match the committed description");
+        }
+
+        let share = decode_vss_share(committed_derec_share, &derec_share);
+        vss::verify_share(&share).map_err(|_| "Committed version description does not match the commitment")?;
+    }
+
+    Ok(msg.version)
+}
+
+/// Generates the helper's acknowledgement of a `StoreShareRequestMessage`.
+///
+/// After receiving a share from the sharer, a helper replies with a `StoreShareResponseMessage`
+/// reporting whether the share for `version` was stored. This completes the store handshake.
+///
+/// # Arguments
+///
+/// * `_channel_id` - The channel on which the share was received (not used, but may be useful for context).
+/// * `version` - The version number from the `StoreShareRequestMessage` being acknowledged.
+/// * `success` - Whether the helper stored the share.
+///
+/// # Returns
+///
+/// Returns a `StoreShareResponseMessage` with status `Ok` on success, or `Fail` otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{generate_store_share_response, process_store_share_response};
+/// let response = generate_store_share_response(&1, 3, true);
+/// assert_eq!(response.version, 3);
+/// assert!(process_store_share_response(&response).is_ok());
+/// ```
+pub fn generate_store_share_response(
+    _channel_id: &ChannelId,
+    version: i32,
+    success: bool,
+) -> StoreShareResponseMessage {
+    let (status, memo) = if success {
+        (StatusEnum::Ok, String::new())
+    } else {
+        (StatusEnum::Fail, "Helper failed to store the share".to_string())
+    };
+
+    StoreShareResponseMessage {
+        result: Some(DerecResult { status: status as i32, memo }),
+        version,
+    }
+}
+
+/// Reasons `process_store_share_response` reports that a helper did not store its share.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StoreShareError {
+    #[error("missing result in StoreShareResponseMessage")]
+    MissingResult,
+    #[error("helper rejected the share: size limit exceeded")]
+    SizeLimitExceeded,
+    #[error("helper rejected the share: requests too frequent")]
+    TooFrequent,
+    #[error("helper failed to store the share: {memo}")]
+    HelperFailed { memo: String },
+    #[error("unknown status {0} in StoreShareResponseMessage")]
+    UnknownStatus(i32),
+}
+
+/// Processes a helper's `StoreShareResponseMessage` on the sharer side.
+///
+/// # Arguments
+///
+/// * `msg` - The `StoreShareResponseMessage` received from the helper.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the helper reports that the share was stored. Otherwise returns the
+/// `StoreShareError` matching the helper's status, carrying its memo for a plain failure.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{generate_store_share_response, process_store_share_response, StoreShareError};
+/// let response = generate_store_share_response(&1, 3, false);
+/// assert!(matches!(process_store_share_response(&response), Err(StoreShareError::HelperFailed { .. })));
+/// ```
+pub fn process_store_share_response(msg: &StoreShareResponseMessage) -> Result<(), StoreShareError> {
+    let result = msg.result.as_ref().ok_or(StoreShareError::MissingResult)?;
+
+    match StatusEnum::try_from(result.status) {
+        Ok(StatusEnum::Ok) => Ok(()),
+        Ok(StatusEnum::SizeLimitExceeded) => Err(StoreShareError::SizeLimitExceeded),
+        Ok(StatusEnum::TooFrequent) => Err(StoreShareError::TooFrequent),
+        Ok(_) => Err(StoreShareError::HelperFailed { memo: result.memo.clone() }),
+        Err(_) => Err(StoreShareError::UnknownStatus(result.status)),
+    }
+}
+
+/// The sharer's view of which helpers have acknowledged storing their share of one version.
+///
+/// Each `StoreShareResponseMessage` is `record`ed with the channel it arrived on; a later response
+/// from the same helper replaces the earlier one, and responses for other versions are ignored. Once
+/// `is_threshold_met`, enough helpers hold shares for the secret to be recovered, i.e. the version is
+/// safely distributed.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{generate_store_share_response, DistributionStatus};
+/// let mut status = DistributionStatus::new(3);
+/// status.record(1, &generate_store_share_response(&1, 3, true));
+/// status.record(2, &generate_store_share_response(&2, 3, false));
+/// assert_eq!(status.confirmed(), vec![1]);
+/// assert!(!status.is_threshold_met(2));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistributionStatus {
+    version: i32,
+    responses: BTreeMap<ChannelId, Result<(), StoreShareError>>,
+}
+
+impl DistributionStatus {
+    /// Starts tracking the acknowledgements of `version`.
+    pub fn new(version: i32) -> Self {
+        DistributionStatus { version, responses: BTreeMap::new() }
+    }
+
+    /// Records the response received on `channel`, as interpreted by `process_store_share_response`.
+    ///
+    /// Returns `false`, recording nothing, if the response is for another version.
+    pub fn record(&mut self, channel: ChannelId, response: &StoreShareResponseMessage) -> bool {
+        if response.version != self.version {
+            return false;
+        }
+        self.responses.insert(channel, process_store_share_response(response));
+        true
+    }
+
+    /// The channels whose helpers confirmed storing their share, in ascending order.
+    pub fn confirmed(&self) -> Vec<ChannelId> {
+        self.responses.iter().filter(|(_, outcome)| outcome.is_ok()).map(|(channel, _)| *channel).collect()
+    }
+
+    /// The channels whose helpers reported a failure, in ascending order, with the reason.
+    pub fn failed(&self) -> Vec<(ChannelId, &StoreShareError)> {
+        self.responses
+            .iter()
+            .filter_map(|(channel, outcome)| outcome.as_ref().err().map(|reason| (*channel, reason)))
+            .collect()
+    }
+
+    /// Returns whether at least `threshold` helpers confirmed storing their share.
+    pub fn is_threshold_met(&self, threshold: usize) -> bool {
+        self.responses.values().filter(|outcome| outcome.is_ok()).count() >= threshold
+    }
+}
+
+/// Size in bytes of the random salt prepended to an exported sharing.
+pub const EXPORT_SALT_SIZE: usize = 16;
+
+/// Exports a full sharing into a single passphrase-encrypted blob for offline backup.
+///
+/// Each channel's `StoreShareRequestMessage` is encoded together with its channel id and length-prefixed
+/// (see `framing::frame`). The concatenation is encrypted with `channel::encrypt_message` under a key
+/// derived from `passphrase` and a fresh random salt with `kdf::derive_from_passphrase` (default
+/// `Argon2Params`). The output is `salt || nonce || ciphertext || tag`.
+///
+/// # Arguments
+///
+/// * `sharing` - The per-channel messages produced by `protect_secret`.
+/// * `passphrase` - The passphrase protecting the backup.
+///
+/// # Returns
+///
+/// Returns the encrypted backup, to be restored with `import_sharing`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::sharing::{protect_secret, export_sharing, import_sharing};
+/// let sharing = protect_secret(b"id", b"secret", &[1, 2, 3], 2, 1, None, None).unwrap();
+/// let backup = export_sharing(&sharing, "correct horse battery staple");
+/// assert_eq!(import_sharing(&backup, "correct horse battery staple").unwrap(), sharing);
+/// ```
+pub fn export_sharing(
+    sharing: &HashMap<ChannelId, StoreShareRequestMessage>,
+    passphrase: &str,
+) -> Vec<u8> {
+    // sort by channel so that the same sharing always serializes the same way
+    let mut channels: Vec<&ChannelId> = sharing.keys().collect();
+    channels.sort();
+
+    let mut plaintext = Vec::new();
+    for channel in channels {
+        let mut entry = channel.to_be_bytes().to_vec();
+        entry.extend_from_slice(&sharing[channel].encode_to_vec());
+        plaintext.extend_from_slice(&frame(&entry));
+    }
+
+    let mut rng = rand::rngs::OsRng;
+    let mut salt = [0u8; EXPORT_SALT_SIZE];
+    rng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 32];
+    rng.fill_bytes(&mut nonce);
+
+    let key = derive_from_passphrase(passphrase, &salt, Argon2Params::default());
+    let ciphertext = channel::encrypt_message(&plaintext, &key, &nonce)
+        .expect("Failed to encrypt sharing export");
+
+    let mut output = salt.to_vec();
+    output.extend_from_slice(&ciphertext);
+    output
+}
+
+/// Restores a sharing exported with `export_sharing`.
+///
+/// # Arguments
+///
+/// * `backup` - The encrypted backup produced by `export_sharing`.
+/// * `passphrase` - The passphrase the backup was exported with.
+///
+/// # Returns
+///
+/// Returns the per-channel `StoreShareRequestMessage`s, or an error string if the passphrase
+/// is wrong, the backup was tampered with, or its contents cannot be decoded.
+pub fn import_sharing(
+    backup: &[u8],
+    passphrase: &str,
+) -> Result<HashMap<ChannelId, StoreShareRequestMessage>, &'static str> {
+    if backup.len() < EXPORT_SALT_SIZE {
+        return Err("Sharing export is too short");
+    }
+    let (salt, ciphertext) = backup.split_at(EXPORT_SALT_SIZE);
+
+    let key = derive_from_passphrase(passphrase, salt, Argon2Params::default());
+    let plaintext = channel::decrypt_message(ciphertext, &key)
+        .map_err(|_| "Failed to decrypt sharing export: wrong passphrase or corrupted data")?;
+
+    // the backup is authenticated, so its frames need no size limit beyond its own length
+    let mut deframer = Deframer::new(plaintext.len());
+    let entries = deframer.push(&plaintext)?;
+    if deframer.buffered_len() != 0 {
+        return Err("Sharing export ends with a truncated entry");
+    }
+
+    let mut sharing = HashMap::new();
+    for entry in entries {
+        if entry.len() < size_of::<ChannelId>() {
+            return Err("Sharing export entry is missing its channel id");
+        }
+        let (channel, msg) = entry.split_at(size_of::<ChannelId>());
+        let channel = ChannelId::from_be_bytes(channel.try_into().unwrap());
+        let msg = StoreShareRequestMessage::decode(msg)
+            .map_err(|_| "Failed to decode StoreShareRequestMessage in sharing export")?;
+        sharing.insert(channel, msg);
+    }
+
+    Ok(sharing)
+}
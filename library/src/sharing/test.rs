@@ -1,3 +1,446 @@
 #[cfg(test)]
 mod tests {
-}
\ No newline at end of file
+    use prost::Message;
+    use crate::protos::derec_proto::{StoreShareResponseMessage, CommittedDeRecShare, DeRecShare, SenderKind, StatusEnum};
+    use crate::sharing::{
+        protect_secret,
+        protect_secret_ordered,
+        protect_secret_with_key,
+        protect_secret_with_options,
+        protect_secret_with_rng,
+        protect_secret_deterministic,
+        ProtectOptions,
+        ShareAlgorithm,
+        validate_secret_len,
+        extract_channel_share,
+        check_version_consistency,
+        generate_store_share_response,
+        process_store_share_response,
+        StoreShareError,
+        DistributionStatus,
+        export_sharing,
+        import_sharing,
+        share_x_coordinates,
+        is_recoverable,
+        verify_share_round_trip,
+        recovery_probability,
+        recommend_parameters,
+        estimate_share_size,
+        generate_store_challenge,
+        authorize_store_request,
+        verify_store_authorization,
+    };
+    use std::collections::HashSet;
+    use derec_cryptography::vss::{self, DerecVSSError, MAX_SHARES, min_merkle_depth};
+
+    #[test]
+    fn test_protect_secret_rejects_too_many_channels() {
+        let channels: Vec<u64> = (0..MAX_SHARES + 1).collect();
+        assert!(min_merkle_depth(channels.len() as u64) > min_merkle_depth(MAX_SHARES));
+
+        let result = protect_secret(b"id", b"secret", &channels, 2, 1, None, None);
+        assert!(matches!(result, Err(DerecVSSError::TooManyChannels { max: MAX_SHARES })));
+    }
+
+    #[test]
+    fn test_extract_channel_share_verifies_independently() {
+        let channels = vec![7, 8, 9];
+        let sharing = protect_secret(b"id", b"secret", &channels, 2, 1, None, None).unwrap();
+
+        let mut share = extract_channel_share(&sharing, 8).unwrap();
+        assert!(vss::verify_share(&share).is_ok());
+
+        // tampering with the share breaks its Merkle proof
+        share.y[0] ^= 1;
+        assert!(matches!(vss::verify_share(&share), Err(DerecVSSError::CorruptShares)));
+
+        // channels outside the sharing are reported as unknown
+        assert!(matches!(extract_channel_share(&sharing, 10), Err(DerecVSSError::UnknownChannel)));
+    }
+
+    #[test]
+    fn test_check_version_consistency_detects_rewritten_version() {
+        let sharing = protect_secret(b"id", b"secret", [1, 2, 3], 2, 3, None, None).unwrap();
+        let mut msg = sharing[&1].clone();
+        assert_eq!(check_version_consistency(&msg), Ok(3));
+
+        // a relay rewrites the outer version but cannot touch the committed one
+        msg.version = 4;
+        assert!(check_version_consistency(&msg).is_err());
+    }
+
+    #[test]
+    fn test_committed_description_detects_tampering() {
+        let options = ProtectOptions { description: Some("moved to new bank"), commit_description: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", [1, 2, 3], 2, 3, &options).unwrap();
+        assert!(sharing.values().all(|msg| check_version_consistency(msg).is_ok()));
+
+        // a relay rewrites the description the helper reads
+        let mut msg = sharing[&1].clone();
+        msg.version_description = "moved to attacker's bank".to_string();
+        assert!(check_version_consistency(&msg).is_err());
+
+        // rewriting the committed copy as well breaks the share's Merkle proof
+        let mut committed = CommittedDeRecShare::decode(msg.share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.version_description = msg.version_description.clone();
+        committed.de_rec_share = share.encode_to_vec();
+        msg.share = committed.encode_to_vec();
+        assert!(check_version_consistency(&msg).is_err());
+
+        let mut tampered = sharing.clone();
+        tampered.insert(1, msg);
+        let shares: Vec<_> = [1, 2].iter().map(|c| extract_channel_share(&tampered, *c).unwrap()).collect();
+        assert!(matches!(vss::detect_error(&shares), Some(DerecVSSError::CorruptShares)));
+    }
+
+    #[test]
+    fn test_protect_secret_deterministic_is_idempotent() {
+        let seed = [0x42; 32];
+        let first = protect_secret_deterministic(b"id", b"secret", [1, 2, 3], 2, 1, &seed).unwrap();
+        let retry = protect_secret_deterministic(b"id", b"secret", [1, 2, 3], 2, 1, &seed).unwrap();
+        assert_eq!(first, retry);
+
+        // another version, secret id or seed gets an unrelated sharing
+        for other in [
+            protect_secret_deterministic(b"id", b"secret", [1, 2, 3], 2, 2, &seed).unwrap(),
+            protect_secret_deterministic(b"other id", b"secret", [1, 2, 3], 2, 1, &seed).unwrap(),
+            protect_secret_deterministic(b"id", b"secret", [1, 2, 3], 2, 1, &[0x43; 32]).unwrap(),
+        ] {
+            assert_ne!(extract_channel_share(&other, 1).unwrap().y, extract_channel_share(&first, 1).unwrap().y);
+        }
+    }
+
+    #[test]
+    fn test_store_share_response_round_trip() {
+        let ok = generate_store_share_response(&5, 2, true);
+        let ok = StoreShareResponseMessage::decode(ok.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(ok.version, 2);
+        assert_eq!(process_store_share_response(&ok), Ok(()));
+
+        let failed = generate_store_share_response(&5, 2, false);
+        let failed = StoreShareResponseMessage::decode(failed.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(failed.version, 2);
+        assert_eq!(
+            process_store_share_response(&failed),
+            Err(StoreShareError::HelperFailed { memo: "Helper failed to store the share".to_string() })
+        );
+
+        // the helper's status is reported as such, and a response without one is malformed
+        let mut too_frequent = failed.clone();
+        too_frequent.result.as_mut().unwrap().status = StatusEnum::TooFrequent as i32;
+        assert_eq!(process_store_share_response(&too_frequent), Err(StoreShareError::TooFrequent));
+        let mut unknown = failed.clone();
+        unknown.result.as_mut().unwrap().status = 999;
+        assert_eq!(process_store_share_response(&unknown), Err(StoreShareError::UnknownStatus(999)));
+        let missing = StoreShareResponseMessage { result: None, ..failed };
+        assert_eq!(process_store_share_response(&missing), Err(StoreShareError::MissingResult));
+    }
+
+    #[test]
+    fn test_distribution_status_meets_threshold() {
+        let mut status = DistributionStatus::new(2);
+        for channel in [1, 2, 3, 4] {
+            assert!(status.record(channel, &generate_store_share_response(&channel, 2, true)));
+        }
+        assert!(status.record(5, &generate_store_share_response(&5, 2, false)));
+
+        // an acknowledgement of an older version says nothing about this one
+        assert!(!status.record(5, &generate_store_share_response(&5, 1, true)));
+
+        assert_eq!(status.confirmed(), vec![1, 2, 3, 4]);
+        let reason = StoreShareError::HelperFailed { memo: "Helper failed to store the share".to_string() };
+        assert_eq!(status.failed(), vec![(5, &reason)]);
+        assert!(status.is_threshold_met(3));
+        assert!(!status.is_threshold_met(5));
+    }
+
+    #[test]
+    fn test_export_import_sharing_round_trip() {
+        let sharing = protect_secret(b"id", b"secret", [4, 5, 6], 2, 3, Some(&[2, 3]), Some("v3")).unwrap();
+
+        let backup = export_sharing(&sharing, "correct horse battery staple");
+        let restored = import_sharing(&backup, "correct horse battery staple").unwrap();
+        assert_eq!(restored, sharing);
+    }
+
+    #[test]
+    fn test_import_sharing_with_wrong_passphrase_fails() {
+        let sharing = protect_secret(b"id", b"secret", [4, 5, 6], 2, 3, None, None).unwrap();
+
+        let backup = export_sharing(&sharing, "correct horse battery staple");
+        assert!(import_sharing(&backup, "incorrect horse battery staple").is_err());
+    }
+
+    #[test]
+    fn test_share_x_coordinates_are_distinct() {
+        let channels: Vec<u64> = (10..20).collect();
+        let sharing = protect_secret(b"id", b"secret", &channels, 4, 1, None, None).unwrap();
+
+        let xs = share_x_coordinates(&sharing).unwrap();
+        assert_eq!(xs.keys().collect::<HashSet<_>>(), channels.iter().collect::<HashSet<_>>());
+        assert_eq!(xs.values().collect::<HashSet<_>>().len(), channels.len());
+    }
+
+    #[test]
+    fn test_protect_secret_rejects_empty_channels() {
+        let result = protect_secret(b"id", b"secret", [], 2, 1, None, None);
+        assert!(matches!(result, Err(DerecVSSError::NoChannels)));
+    }
+
+    #[test]
+    fn test_protect_secret_rejects_zero_threshold() {
+        let result = protect_secret(b"id", b"secret", [1, 2, 3], 0, 1, None, None);
+        assert!(matches!(result, Err(DerecVSSError::InvalidAccessStructure)));
+    }
+
+    #[test]
+    fn test_protect_secret_rejects_trivial_threshold_unless_allowed() {
+        let result = protect_secret(b"id", b"secret", [1, 2, 3], 1, 1, None, None);
+        assert!(matches!(result, Err(DerecVSSError::TrivialThreshold)));
+
+        let options = ProtectOptions { allow_trivial_threshold: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", [1, 2, 3], 1, 1, &options).unwrap();
+        assert_eq!(sharing.len(), 3);
+
+        // a single helper's share is enough to recover the secret
+        use crate::recovery::{generate_share_request, generate_share_response, recover_from_share_responses};
+        let request = generate_share_request(&2, b"id", 1);
+        let response = generate_share_response(&2, b"id", &request, &sharing[&2], SenderKind::SharerRecovery);
+        assert_eq!(recover_from_share_responses(&[response], b"id", 1, 1).unwrap().data, b"secret");
+    }
+
+    #[test]
+    fn test_protect_secret_rejects_threshold_above_channel_count() {
+        let result = protect_secret(b"id", b"secret", [1, 2, 3], 4, 1, None, None);
+        assert!(matches!(result, Err(DerecVSSError::InvalidAccessStructure)));
+    }
+
+    #[test]
+    fn test_replayed_store_request_is_rejected() {
+        let key = [0x5Au8; 32];
+        let mut sharing = protect_secret(b"id", b"secret", [1, 2, 3], 2, 1, None, None).unwrap();
+        let mut request = sharing.remove(&1).unwrap();
+
+        // without answering a challenge, the request is not authorized
+        let first_challenge = generate_store_challenge();
+        assert!(verify_store_authorization(&request, &first_challenge, &key).is_err());
+
+        authorize_store_request(&mut request, &first_challenge, &key);
+        assert!(verify_store_authorization(&request, &first_challenge, &key).is_ok());
+
+        // a later replay of the same request faces a fresh challenge
+        let second_challenge = generate_store_challenge();
+        assert!(verify_store_authorization(&request, &second_challenge, &key).is_err());
+
+        // and the authorization cannot be moved onto another share or key
+        let mut other = sharing.remove(&2).unwrap();
+        other.store_authorization = request.store_authorization.clone();
+        assert!(verify_store_authorization(&other, &first_challenge, &key).is_err());
+        assert!(verify_store_authorization(&request, &first_challenge, &[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_store_authorization_covers_every_field() {
+        let key = [0x5Au8; 32];
+        let keep_list = [1, 2];
+        let options = ProtectOptions { keep_list: Some(&keep_list), description: Some("new bank"), ..Default::default() };
+        let mut request = protect_secret_with_options(b"id", b"secret", [1, 2, 3], 2, 2, &options).unwrap().remove(&1).unwrap();
+        let challenge = generate_store_challenge();
+        authorize_store_request(&mut request, &challenge, &key);
+
+        // a relay must not be able to make the helper drop the versions the sharer asked it to keep
+        let mut tampered = request.clone();
+        tampered.keep_list = vec![2];
+        assert!(verify_store_authorization(&tampered, &challenge, &key).is_err());
+
+        let mut tampered = request.clone();
+        tampered.version_description = "attacker's bank".to_string();
+        assert!(verify_store_authorization(&tampered, &challenge, &key).is_err());
+
+        let mut tampered = request.clone();
+        tampered.share_algorithm = ShareAlgorithm::ShamirBw6 as i32;
+        assert!(verify_store_authorization(&tampered, &challenge, &key).is_err());
+
+        assert!(verify_store_authorization(&request, &challenge, &key).is_ok());
+    }
+
+    #[test]
+    fn test_protect_secret_with_external_key() {
+        let aes_key = [0x3Cu8; 32];
+        let data = b"a secret large enough to need the envelope, well past one field element";
+        let sharing = protect_secret_with_key(b"id", data, [1, 2, 3], 2, 1, aes_key).unwrap();
+
+        let shares: Vec<vss::VSSShare> = [1, 3]
+            .iter()
+            .map(|channel| extract_channel_share(&sharing, *channel).unwrap())
+            .collect();
+        assert_eq!(vss::recover_key_only(&shares).unwrap(), aes_key);
+        assert_eq!(vss::recover(&shares).unwrap(), data);
+
+        // the helpers' ciphertext opens under the supplied key
+        let plaintext = derec_cryptography::channel::decrypt_message(&shares[0].encrypted_secret, &aes_key).unwrap();
+        assert_eq!(plaintext, data);
+    }
+
+    #[test]
+    fn test_is_recoverable() {
+        let mut sharing = protect_secret(b"id", b"secret", [1, 2, 3], 3, 1, None, None).unwrap();
+        assert!(is_recoverable(&sharing, 3));
+        assert!(!is_recoverable(&sharing, 5));
+        assert!(!is_recoverable(&sharing, 0));
+
+        // a message that no longer decodes does not count
+        sharing.get_mut(&2).unwrap().share = vec![0xFF; 4];
+        assert!(!is_recoverable(&sharing, 3));
+        assert!(is_recoverable(&sharing, 2));
+    }
+
+    #[test]
+    fn test_estimate_share_size_matches_encoding() {
+        for secret_len in [0, 32, 1000] {
+            let secret = vec![0xA5u8; secret_len];
+            let sharing = protect_secret(b"id", &secret, [1, 2, 3, 4, 5], 3, 1, None, None).unwrap();
+            let estimate = estimate_share_size(secret_len, 5, vss::MERKLE_TREE_DEPTH);
+
+            for msg in sharing.values() {
+                let actual = msg.encode_to_vec().len();
+
+                // the estimate counts each sibling's `is_left` flag and the share algorithm, which the encoding
+                // omits when false or 0, and leaves out the caller-chosen secret id
+                let committed = CommittedDeRecShare::decode(msg.share.as_slice()).unwrap();
+                let omitted = 2 * committed.merkle_path.iter().filter(|sibling| !sibling.is_left).count() + 2;
+                let secret_id = 1 + 1 + b"id".len();
+                assert_eq!(estimate, actual + omitted - secret_id, "estimate {estimate}, actual {actual}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_secret_length_is_checked_per_algorithm() {
+        let direct = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirBw6, ..Default::default() };
+        let too_long = [1u8; vss::MAX_DIRECT_SECRET_SIZE + 1];
+        assert!(matches!(
+            validate_secret_len(too_long.len(), ShareAlgorithm::ShamirBw6),
+            Err(DerecVSSError::SecretTooLarge { max: vss::MAX_DIRECT_SECRET_SIZE })
+        ));
+        assert!(matches!(
+            protect_secret_with_options(b"id", too_long, [1, 2, 3], 2, 1, &direct),
+            Err(DerecVSSError::SecretTooLarge { .. })
+        ));
+        assert_eq!(vss::MAX_DIRECT_SECRET_BITS, 248);
+
+        // the envelope takes the same secret, and much longer ones
+        assert!(validate_secret_len(too_long.len(), ShareAlgorithm::ShamirWithAesEnvelope).is_ok());
+        let long = vec![7u8; 4096];
+        assert!(validate_secret_len(long.len(), ShareAlgorithm::ShamirWithErasureCodedEnvelope).is_ok());
+        assert!(protect_secret(b"id", &long, [1, 2, 3], 2, 1, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_protect_secret_with_seeded_rng_is_reproducible() {
+        use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
+
+        let options = ProtectOptions::default();
+        let first = protect_secret_with_rng(b"id", b"secret", [1, 2, 3], 2, 1, &options, &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        let again = protect_secret_with_rng(b"id", b"secret", [1, 2, 3], 2, 1, &options, &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        let other = protect_secret_with_rng(b"id", b"secret", [1, 2, 3], 2, 1, &options, &mut ChaCha8Rng::seed_from_u64(8)).unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_protect_secret_ordered_sorts_by_channel() {
+        let channels = [42u64, 7, 19, 3, 100];
+        for _ in 0..5 {
+            let ordered = protect_secret_ordered(b"id", b"secret", channels, 3, 1, None, None).unwrap();
+            let order: Vec<u64> = ordered.iter().map(|(channel, _)| *channel).collect();
+            assert_eq!(order, vec![3, 7, 19, 42, 100]);
+            assert!(ordered.iter().all(|(_, msg)| msg.version == 1));
+        }
+    }
+
+    #[test]
+    fn test_recommend_parameters_meets_the_target() {
+        // 2 of 3 fall short (0.972), while 2 of 4 reach 0.9963 and 3 of 4 only 0.9477
+        assert_eq!(recommend_parameters(0.9, 0.99), Some((2, 4)));
+        // coin-flip helpers need many more of them
+        assert_eq!(recommend_parameters(0.5, 0.9), Some((2, 7)));
+        // perfectly available helpers need no redundancy
+        assert_eq!(recommend_parameters(1.0, 0.999), Some((2, 2)));
+
+        for (availability, target) in [(0.9, 0.99), (0.5, 0.9), (0.99, 0.9999), (0.7, 0.95)] {
+            let (threshold, n) = recommend_parameters(availability, target).unwrap();
+            assert!(threshold >= 2 && threshold <= n);
+            assert!(recovery_probability(availability, threshold, n) >= target);
+            assert!(recovery_probability(availability, threshold + 1, n) < target);
+        }
+
+        // unreachable with the helpers the commitment can hold, or not a probability at all
+        assert_eq!(recommend_parameters(0.1, 0.999999), None);
+        assert_eq!(recommend_parameters(1.5, 0.9), None);
+    }
+
+    #[test]
+    fn test_verify_share_round_trip_on_fresh_sharings() {
+        let channels = [1u64, 2, 3, 4, 5];
+        for share_algorithm in [ShareAlgorithm::ShamirBw6, ShareAlgorithm::ShamirWithAesEnvelope, ShareAlgorithm::ShamirWithErasureCodedEnvelope] {
+            let options = ProtectOptions { share_algorithm, ..Default::default() };
+            let sharing = protect_secret_with_options(b"id", b"secret", channels, 3, 1, &options).unwrap();
+
+            for channel in channels {
+                let others: Vec<_> = channels.iter().filter(|c| **c != channel).map(|c| sharing[c].clone()).collect();
+                assert!(verify_share_round_trip(&sharing[&channel], &others).is_ok());
+            }
+
+            // below the threshold there is nothing to reconstruct
+            assert!(matches!(
+                verify_share_round_trip(&sharing[&1], &[sharing[&2].clone()]),
+                Err(DerecVSSError::InsufficientShares)
+            ));
+        }
+
+        // a share whose y-coordinate went wrong at share time is caught even without a commitment
+        let options = ProtectOptions { share_algorithm: ShareAlgorithm::ShamirBw6, omit_commitment: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", channels, 3, 1, &options).unwrap();
+        let mut bad = sharing[&1].clone();
+        let committed = CommittedDeRecShare::decode(bad.share.as_slice()).unwrap();
+        let mut share = DeRecShare::decode(committed.de_rec_share.as_slice()).unwrap();
+        share.y = DeRecShare::decode(
+            CommittedDeRecShare::decode(sharing[&2].share.as_slice()).unwrap().de_rec_share.as_slice()
+        ).unwrap().y;
+        bad.share = CommittedDeRecShare { de_rec_share: share.encode_to_vec(), ..committed }.encode_to_vec();
+        let others: Vec<_> = channels[1..].iter().map(|c| sharing[c].clone()).collect();
+        assert!(verify_share_round_trip(&bad, &others).is_err());
+
+        // messages naming different share algorithms cannot belong to one sharing
+        let mut relabeled = others.clone();
+        relabeled[0].share_algorithm = ShareAlgorithm::ShamirWithAesEnvelope as i32;
+        assert!(matches!(
+            verify_share_round_trip(&sharing[&1], &relabeled),
+            Err(DerecVSSError::InconsistentShareAlgorithms)
+        ));
+    }
+
+    #[test]
+    fn test_scattered_leaves_hide_the_channel_count() {
+        let channels = [1u64, 2, 3];
+        let options = ProtectOptions { scatter_leaves: true, ..Default::default() };
+        let sharing = protect_secret_with_options(b"id", b"secret", channels, 2, 1, &options).unwrap();
+
+        // a share's leaf position can be read off the sides of its authentication path
+        let positions: Vec<usize> = channels
+            .iter()
+            .map(|channel| {
+                let share = extract_channel_share(&sharing, *channel).unwrap();
+                assert!(vss::verify_share(&share).is_ok());
+                share.merkle_path.iter().enumerate().map(|(level, (is_left, _))| (*is_left as usize) << level).sum()
+            })
+            .collect();
+        assert_ne!(positions, vec![0, 1, 2]);
+
+        let others = [sharing[&2].clone(), sharing[&3].clone()];
+        assert!(verify_share_round_trip(&sharing[&1], &others).is_ok());
+    }
+}
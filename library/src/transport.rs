@@ -0,0 +1,18 @@
+/// A bidirectional, message-oriented channel to the peer of a DeRec protocol run.
+///
+/// Each `send` delivers one encoded protocol message, and each `recv` yields the next
+/// message from the peer in order. Transports over byte streams can delimit messages
+/// with `framing::frame` and `framing::Deframer`.
+// the futures are driven on the caller's executor (often single-threaded, e.g. WASM),
+// so no `Send` bound is imposed on them
+#[allow(async_fn_in_trait)]
+pub trait Transport {
+    /// Error reported by the underlying transport.
+    type Error;
+
+    /// Sends one message to the peer.
+    async fn send(&mut self, msg: &[u8]) -> Result<(), Self::Error>;
+
+    /// Waits for the next message from the peer.
+    async fn recv(&mut self) -> Result<Vec<u8>, Self::Error>;
+}
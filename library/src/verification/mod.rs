@@ -1,7 +1,14 @@
+#[allow(clippy::module_inception)]
 pub mod verification;
 pub use verification::generate_verification_request;
+pub use verification::generate_verification_request_with_mode;
 pub use verification::generate_verification_response;
+pub use verification::generate_verification_response_for_versions;
+pub use verification::generate_verification_failure_response;
+pub use verification::check_share_response;
+pub use verification::VerificationError;
 pub use verification::verify_share_response;
+pub use verification::verify_share_response_for_request;
 
 use prost::Message;
 use crate::protos::derec_proto::{VerifyShareRequestMessage, VerifyShareResponseMessage};
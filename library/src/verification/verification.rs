@@ -1,11 +1,15 @@
+use std::collections::HashMap;
 use rand::RngCore;
 use crate::protos::derec_proto::{
     VerifyShareRequestMessage,
     VerifyShareResponseMessage,
+    VerifyMode,
     Result as DerecResult,
     StatusEnum
 };
 use crate::types::*;
+use hmac::{Hmac, Mac};
+use thiserror::Error;
 use sha2::*;
 
 /// Generates a verification request for each provided channel.
@@ -32,14 +36,36 @@ use sha2::*;
 /// let requests = generate_verification_request("secret_id", 1);
 /// ```
 pub fn generate_verification_request(
+    secret_id: impl AsRef<[u8]>,
+    version: i32,
+) -> VerifyShareRequestMessage {
+    generate_verification_request_with_mode(secret_id, version, VerifyMode::Sha384)
+}
+
+/// Generates a verification request like `generate_verification_request`, asking for a response in `mode`.
+///
+/// With `VerifyMode::HmacSha384` the helper must answer with an HMAC-SHA384 of the share keyed by the nonce,
+/// which can only be computed from the share content itself; a helper that kept (or forwarded) just a hash of
+/// the share cannot answer. `VerifyMode::Sha384` is the original plain-hash response.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::verification::*;
+/// use derec_library::protos::derec_proto::VerifyMode;
+/// let request = generate_verification_request_with_mode("secret_id", 1, VerifyMode::HmacSha384);
+/// assert_eq!(request.mode(), VerifyMode::HmacSha384);
+/// ```
+pub fn generate_verification_request_with_mode(
     _secret_id: impl AsRef<[u8]>,
     version: i32,
+    mode: VerifyMode,
 ) -> VerifyShareRequestMessage {
     // Generate a nonce using a secure random number generator
     let mut rng = rand::rngs::OsRng;
     let mut nonce: Vec<u8> = vec![0; 32];
     rng.fill_bytes(&mut nonce);
-    VerifyShareRequestMessage { version, nonce }
+    VerifyShareRequestMessage { version, nonce, mode: mode.into() }
 }
 
 /// Generates a verification response for a given share and verification request.
@@ -75,17 +101,79 @@ pub fn generate_verification_response(
     share_content: impl AsRef<[u8]>,
     request: &VerifyShareRequestMessage,
 ) -> VerifyShareResponseMessage {
-    // compute the Sha384 hash of the share content
-    let mut hasher = Sha384::new();
-    hasher.update(share_content);
-    hasher.update(request.nonce.as_slice());
-    let hash = hasher.finalize().to_vec();
+    let mode = request.mode();
 
     VerifyShareResponseMessage {
         result: Some(DerecResult { status: StatusEnum::Ok as i32, memo: String::new() }),
         version: request.version,
         nonce: request.nonce.clone(),
-        hash
+        hash: possession_proof(mode, share_content.as_ref(), &request.nonce),
+        mode: mode.into(),
+    }
+}
+
+/// Generates a verification response like `generate_verification_response`, for a helper holding several
+/// versions of the secret.
+///
+/// The share content is selected by the request's `version` from `shares`. If the helper does not hold that
+/// version, the response carries a `Fail` status and no proof.
+///
+/// # Arguments
+///
+/// * `secret_id` - An identifier for the secret.
+/// * `channel_id` - The channel the request arrived on.
+/// * `shares` - The stored share content of each version the helper holds.
+/// * `request` - The `VerifyShareRequestMessage` naming the version to prove possession of.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use derec_library::verification::*;
+/// let shares = HashMap::from([(1, b"share v1".to_vec()), (3, b"share v3".to_vec())]);
+/// let request = generate_verification_request("secret", 3);
+/// let response = generate_verification_response_for_versions("secret", &2, &shares, &request);
+/// assert!(verify_share_response("secret", &2, b"share v3", &response));
+/// ```
+pub fn generate_verification_response_for_versions(
+    secret_id: impl AsRef<[u8]>,
+    channel_id: &ChannelId,
+    shares: &HashMap<i32, Vec<u8>>,
+    request: &VerifyShareRequestMessage,
+) -> VerifyShareResponseMessage {
+    match shares.get(&request.version) {
+        Some(share_content) => generate_verification_response(secret_id, channel_id, share_content, request),
+        None => generate_verification_failure_response(request, "Requested version is not held"),
+    }
+}
+
+/// Generates a verification response refusing a challenge, with a human-readable reason.
+///
+/// A helper uses this when it cannot prove possession, e.g. because it has pruned the requested version.
+/// The response carries a `Fail` status, no proof, and `memo`, which `check_share_response` reports as
+/// `VerificationError::HelperFailed`.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::verification::*;
+/// let request = generate_verification_request("secret", 2);
+/// let response = generate_verification_failure_response(&request, "version pruned");
+/// assert!(matches!(
+///     check_share_response("secret", &1, b"share", &response),
+///     Err(VerificationError::HelperFailed { memo }) if memo == "version pruned"
+/// ));
+/// ```
+pub fn generate_verification_failure_response(
+    request: &VerifyShareRequestMessage,
+    memo: &str,
+) -> VerifyShareResponseMessage {
+    VerifyShareResponseMessage {
+        result: Some(DerecResult { status: StatusEnum::Fail as i32, memo: memo.to_string() }),
+        version: request.version,
+        nonce: request.nonce.clone(),
+        hash: Vec::new(),
+        mode: request.mode,
     }
 }
 
@@ -96,6 +184,10 @@ pub fn generate_verification_response(
 /// and checks if it matches the hash included in the response. This ensures the integrity
 /// and authenticity of the share content as verified by the original request's nonce.
 ///
+/// The hash is always recomputed in `VerifyMode::Sha384`, the mode of `generate_verification_request`,
+/// whatever mode the response claims. Use `verify_share_response_for_request` to verify a response to
+/// a request made in another mode.
+///
 /// # Arguments
 ///
 /// * `_secret_id` - An identifier for the secret (not used in this function, but may be useful for context).
@@ -119,20 +211,94 @@ pub fn generate_verification_response(
 /// let verify = verify_share_response("secret", &channel, share_content, &response);
 /// assert!(verify);
 /// ```
-
 pub fn verify_share_response(
     _secret_id: impl AsRef<[u8]>,
     _channel_id: &ChannelId,
     share_content: impl AsRef<[u8]>,
     response: &VerifyShareResponseMessage,
 ) -> bool {
-    // compute the Sha384 hash of the share content
-    let mut hasher = Sha384::new();
-    hasher.update(share_content);
-    hasher.update(response.nonce.as_slice());
-    let hash = hasher.finalize().to_vec();
+    possession_proof(VerifyMode::Sha384, share_content.as_ref(), &response.nonce) == response.hash
+}
+
+/// Reasons a verification response fails `check_share_response`.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    #[error("helper refused the challenge: {memo}")]
+    HelperFailed { memo: String },
+    #[error("response does not prove possession of the share")]
+    InvalidProof,
+}
+
+/// Verifies a share response like `verify_share_response`, explaining a failure.
+///
+/// A response whose status is not `Ok` is reported as `VerificationError::HelperFailed` with the helper's
+/// memo, so the sharer learns why (e.g. "share not found") rather than just that verification failed.
+///
+/// # Errors
+///
+/// Returns `VerificationError::HelperFailed` if the helper refused the challenge, or
+/// `VerificationError::InvalidProof` if the proof does not match `share_content`.
+pub fn check_share_response(
+    secret_id: impl AsRef<[u8]>,
+    channel_id: &ChannelId,
+    share_content: impl AsRef<[u8]>,
+    response: &VerifyShareResponseMessage,
+) -> Result<(), VerificationError> {
+    if let Some(result) = response.result.as_ref().filter(|result| result.status != StatusEnum::Ok as i32) {
+        return Err(VerificationError::HelperFailed { memo: result.memo.clone() });
+    }
+    if !verify_share_response(secret_id, channel_id, share_content, response) {
+        return Err(VerificationError::InvalidProof);
+    }
+    Ok(())
+}
+
+/// Verifies a share response like `verify_share_response`, in the mode and against the nonce of the
+/// `request` it answers.
+///
+/// The mode is taken from the verifier's own request, never from the response, so a helper asked for
+/// `VerifyMode::HmacSha384` cannot fall back to the weaker plain-hash response.
+///
+/// # Example
+///
+/// ```rust
+/// use derec_library::verification::*;
+/// use derec_library::protos::derec_proto::VerifyMode;
+/// let request = generate_verification_request_with_mode("secret", 1, VerifyMode::HmacSha384);
+/// let response = generate_verification_response("secret", &2, b"share", &request);
+/// assert!(verify_share_response_for_request("secret", &2, b"share", &request, &response));
+/// ```
+pub fn verify_share_response_for_request(
+    _secret_id: impl AsRef<[u8]>,
+    _channel_id: &ChannelId,
+    share_content: impl AsRef<[u8]>,
+    request: &VerifyShareRequestMessage,
+    response: &VerifyShareResponseMessage,
+) -> bool {
+    response.nonce == request.nonce
+        && response.mode == request.mode
+        && possession_proof(request.mode(), share_content.as_ref(), &request.nonce) == response.hash
+}
 
-    hash == response.hash
+// The value a helper returns to prove it holds `share_content`, for the given nonce.
+fn possession_proof(mode: VerifyMode, share_content: &[u8], nonce: &[u8]) -> Vec<u8> {
+    match mode {
+        VerifyMode::Sha384 => {
+            // compute the Sha384 hash of the share content
+            let mut hasher = Sha384::new();
+            hasher.update(share_content);
+            hasher.update(nonce);
+            hasher.finalize().to_vec()
+        }
+        VerifyMode::HmacSha384 => {
+            // the nonce is the key and the share the message: HMAC hashes keys longer than a block,
+            // so keying with the (long) share would let anyone holding SHA-384(share) answer
+            let mut mac = Hmac::<Sha384>::new_from_slice(nonce)
+                .expect("HMAC accepts keys of any length");
+            mac.update(share_content);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -199,4 +365,74 @@ mod tests {
 
         assert!(!verify_share_response("secret", &41, share_content, &response));
     }
+
+    #[test]
+    fn test_hmac_verification_requires_share_content() {
+        let share_content = vec![0x42u8; 400];
+        let request = generate_verification_request_with_mode("secret", 2, VerifyMode::HmacSha384);
+        let response = generate_verification_response("secret", &7, &share_content, &request);
+
+        assert_eq!(response.mode(), VerifyMode::HmacSha384);
+        assert!(verify_share_response_for_request("secret", &7, &share_content, &request, &response));
+        assert!(!verify_share_response_for_request("secret", &7, b"other content", &request, &response));
+
+        // a helper that kept only the hash of the share cannot answer, however it uses that hash
+        let share_hash = Sha384::digest(&share_content).to_vec();
+        let forged_with_hash_as_content = generate_verification_response("secret", &7, &share_hash, &request);
+        assert!(!verify_share_response_for_request("secret", &7, &share_content, &request, &forged_with_hash_as_content));
+
+        let mut mac = Hmac::<Sha384>::new_from_slice(&share_hash).unwrap();
+        mac.update(&request.nonce);
+        let mut forged_with_hash_as_key = response.clone();
+        forged_with_hash_as_key.hash = mac.finalize().into_bytes().to_vec();
+        assert!(!verify_share_response_for_request("secret", &7, &share_content, &request, &forged_with_hash_as_key));
+
+        // nor can it downgrade to the plain-hash mode when the verifier asked for HMAC, whatever mode it claims
+        let legacy_request = VerifyShareRequestMessage { mode: VerifyMode::Sha384.into(), ..request.clone() };
+        let downgraded = generate_verification_response("secret", &7, &share_content, &legacy_request);
+        assert!(verify_share_response("secret", &7, &share_content, &downgraded));
+        assert!(!verify_share_response_for_request("secret", &7, &share_content, &request, &downgraded));
+        let relabeled = VerifyShareResponseMessage { mode: VerifyMode::HmacSha384.into(), ..downgraded };
+        assert!(!verify_share_response_for_request("secret", &7, &share_content, &request, &relabeled));
+
+        // and the default verifier does not switch modes on the helper's say-so
+        assert!(!verify_share_response("secret", &7, &share_content, &response));
+
+        // a response to another request does not verify against this one
+        let other_request = generate_verification_request_with_mode("secret", 2, VerifyMode::HmacSha384);
+        let other_response = generate_verification_response("secret", &7, &share_content, &other_request);
+        assert!(!verify_share_response_for_request("secret", &7, &share_content, &request, &other_response));
+    }
+
+    #[test]
+    fn test_verification_response_selects_the_requested_version() {
+        let channel = 2;
+        let shares = HashMap::from([(1, b"share v1".to_vec()), (3, b"share v3".to_vec())]);
+
+        let request = generate_verification_request("secret", 3);
+        let response = generate_verification_response_for_versions("secret", &channel, &shares, &request);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Ok as i32);
+        assert_eq!(response.version, 3);
+        assert!(verify_share_response("secret", &channel, b"share v3", &response));
+        assert!(!verify_share_response("secret", &channel, b"share v1", &response));
+
+        let request = generate_verification_request("secret", 2);
+        let response = generate_verification_response_for_versions("secret", &channel, &shares, &request);
+        assert_eq!(response.result.as_ref().unwrap().status, StatusEnum::Fail as i32);
+        assert_eq!(response.version, 2);
+        assert!(response.hash.is_empty());
+    }
+
+    #[test]
+    fn test_refused_challenge_reports_the_memo() {
+        let request = generate_verification_request("secret", 5);
+        let response = generate_verification_failure_response(&request, "version pruned");
+        let error = check_share_response("secret", &1, b"share", &response).unwrap_err();
+        assert_eq!(error, VerificationError::HelperFailed { memo: "version pruned".to_string() });
+        assert_eq!(error.to_string(), "helper refused the challenge: version pruned");
+
+        let response = generate_verification_response("secret", &1, b"share", &request);
+        assert_eq!(check_share_response("secret", &1, b"share", &response), Ok(()));
+        assert_eq!(check_share_response("secret", &1, b"other share", &response), Err(VerificationError::InvalidProof));
+    }
 }
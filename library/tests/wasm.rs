@@ -0,0 +1,72 @@
+//! Tests of the `ts_*` bindings as JavaScript calls them; run with `make test-wasm`.
+#![cfg(target_arch = "wasm32")]
+
+use std::collections::HashMap;
+use derec_library::protos::derec_proto::SenderKind;
+use derec_library::recovery::{generate_share_request, generate_share_response, ts_check_shares_consistent, ts_recover_from_share_responses};
+use derec_library::sharing::{protect_secret, ts_protect_secret, ts_protect_secret_with_options};
+use prost::Message;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// the `{ value: Map<channel, bytes> }` object JavaScript passes for a set of share responses
+#[derive(serde::Serialize)]
+struct JsResponses {
+    value: HashMap<u64, Vec<u8>>,
+}
+
+// the encoded share responses of every channel of a fresh sharing
+fn share_responses(secret: &[u8], channels: &[u64]) -> HashMap<u64, Vec<u8>> {
+    let sharing = protect_secret(b"id", secret, channels, 2, 1, None, None).unwrap();
+    channels
+        .iter()
+        .map(|channel| {
+            let request = generate_share_request(channel, b"id", 1);
+            let response = generate_share_response(channel, b"id", &request, &sharing[channel], SenderKind::SharerRecovery);
+            (*channel, response.encode_to_vec())
+        })
+        .collect()
+}
+
+fn to_js(value: HashMap<u64, Vec<u8>>) -> JsValue {
+    serde_wasm_bindgen::to_value(&JsResponses { value }).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn recover_from_a_non_map_value_is_an_error() {
+    for responses in [JsValue::from_str("not a map"), JsValue::NULL, JsValue::from_f64(3.0)] {
+        let error = ts_recover_from_share_responses(responses, b"id", 1, 2).unwrap_err();
+        assert!(!error.is_empty());
+    }
+}
+
+#[wasm_bindgen_test]
+fn check_shares_consistent_names_the_inconsistency() {
+    let responses = share_responses(b"secret", &[1, 2, 3]);
+    assert_eq!(ts_check_shares_consistent(to_js(responses.clone()), b"id", 1), Ok(()));
+
+    // a share of another sharing of the same secret id and version has another commitment
+    let mut mixed = responses;
+    mixed.insert(3, share_responses(b"other secret", &[3]).remove(&3).unwrap());
+    assert_eq!(
+        ts_check_shares_consistent(to_js(mixed), b"id", 1),
+        Err("InconsistentCommitments".to_string())
+    );
+}
+
+#[wasm_bindgen_test]
+fn protect_secret_with_threshold_one_needs_the_opt_in() {
+    let error = ts_protect_secret(b"id", b"secret", &[1, 2, 3], 1, 1).unwrap_err();
+    assert!(error.contains("threshold"));
+    assert!(ts_protect_secret_with_options(b"id", b"secret", &[1, 2, 3], 1, 1, true).is_ok());
+    assert!(ts_protect_secret_with_options(b"id", b"secret", &[1, 2, 3], 1, 1, false).is_err());
+}
+
+#[wasm_bindgen_test]
+fn protect_secret_reports_invalid_parameters() {
+    let too_many: Vec<u64> = (0..=derec_cryptography::vss::MAX_SHARES).collect();
+    for (channels, threshold) in [(&[][..], 2), (&too_many[..], 2), (&[1, 2, 3][..], 4), (&[1, 2, 3][..], 0)] {
+        let error = ts_protect_secret(b"id", b"secret", channels, threshold, 1).unwrap_err();
+        assert!(!error.is_empty());
+    }
+}